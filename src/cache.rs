@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::size_of;
+use crate::models::{AuthorInfo, BranchInfo, CommitDiff, CommitInfo, DiffCacheStats, DiffLine, DiffStat, FileChange, Hunk, RepoStats};
+
+// A page of history: (tip, followed path, filters, offset, limit)
+type PageKey = (String, Option<String>, String, usize, usize);
+// A commit's diff: (hash, paths it's limited to)
+type DiffKey = (String, Vec<String>);
+
+/// How much memory the diffs kept for going back to may take, unless
+/// configured otherwise
+pub const DIFF_CACHE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Results of expensive history walks, keyed by the commit they were
+/// computed from so a moved branch tip never serves stale data.
+#[derive(Debug, Default)]
+pub struct Cache {
+    authors: HashMap<String, Vec<AuthorInfo>>,
+    commits: HashMap<PageKey, Vec<CommitInfo>>,
+    // The branch list along with the ref targets it was built from
+    branches: Option<(String, Vec<BranchInfo>)>,
+    stats: HashMap<String, RepoStats>,
+    // The diffs looked at most recently, the latest last, with their sizes
+    diffs: VecDeque<(DiffKey, CommitDiff, usize)>,
+    diff_stats: DiffCacheStats,
+    // Much smaller than the diffs, so all are kept
+    diffstats: HashMap<DiffKey, DiffStat>,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        let mut cache = Cache::default();
+        cache.diff_stats.budget = DIFF_CACHE_BUDGET;
+        cache
+    }
+
+    pub fn get_authors(&self, tip: &str) -> Option<&Vec<AuthorInfo>> {
+        self.authors.get(tip)
+    }
+
+    pub fn set_authors(&mut self, tip: &str, authors: Vec<AuthorInfo>) {
+        self.authors.insert(tip.to_string(), authors);
+    }
+
+    pub fn get_commits(&self, tip: &str, path: Option<&str>, filters: &str, offset: usize, limit: usize) -> Option<&Vec<CommitInfo>> {
+        self.commits.get(&(tip.to_string(), path.map(|p| p.to_string()), filters.to_string(), offset, limit))
+    }
+
+    pub fn set_commits(&mut self, tip: &str, path: Option<&str>, filters: &str, offset: usize, limit: usize, commits: Vec<CommitInfo>) {
+        self.commits.insert((tip.to_string(), path.map(|p| p.to_string()), filters.to_string(), offset, limit), commits);
+    }
+
+    /// Drops the history, authors and stats of every tip not in `tips`, as
+    /// once a branch has moved on nothing asks for them again
+    pub fn keep_tips(&mut self, tips: &HashSet<String>) {
+        self.commits.retain(|(tip, ..), _| tips.contains(tip));
+        self.authors.retain(|tip, _| tips.contains(tip));
+        self.stats.retain(|tip, _| tips.contains(tip));
+    }
+
+    pub fn get_branches(&self, fingerprint: &str) -> Option<&Vec<BranchInfo>> {
+        match &self.branches {
+            Some((cached, branches)) if cached == fingerprint => Some(branches),
+            _ => None,
+        }
+    }
+
+    pub fn set_branches(&mut self, fingerprint: &str, branches: Vec<BranchInfo>) {
+        self.branches = Some((fingerprint.to_string(), branches));
+    }
+
+    pub fn get_stats(&self, tip: &str) -> Option<&RepoStats> {
+        self.stats.get(tip)
+    }
+
+    pub fn set_stats(&mut self, tip: &str, stats: RepoStats) {
+        self.stats.insert(tip.to_string(), stats);
+    }
+
+    /// A diff looked at before, which becomes the most recently used. One
+    /// that was cut short only does when the full diff isn't wanted.
+    pub fn get_diff(&mut self, hash: &str, paths: &[String], full: bool) -> Option<&CommitDiff> {
+        let found = self.diffs.iter().position(|((h, p), diff, _)| h == hash && p == paths && !(full && diff.cut_short));
+        let index = match found {
+            Some(index) => index,
+            None => {
+                self.diff_stats.misses += 1;
+                return None;
+            },
+        };
+        self.diff_stats.hits += 1;
+        let entry = self.diffs.remove(index)?;
+        self.diffs.push_back(entry);
+        self.diffs.back().map(|(_, diff, _)| diff)
+    }
+
+    /// Keeps a diff, dropping the least recently used until it fits in the
+    /// budget. One bigger than the whole budget isn't kept at all.
+    pub fn set_diff(&mut self, diff: CommitDiff) {
+        let size = diff_size(&diff);
+        if size > self.diff_stats.budget {
+            self.diff_stats.rejected += 1;
+            return;
+        }
+        // A full diff takes the place of the one that was cut short
+        let key = (diff.hash.clone(), diff.paths.clone());
+        if let Some(index) = self.diffs.iter().position(|(k, _, _)| *k == key) {
+            if let Some((_, _, size)) = self.diffs.remove(index) {
+                self.diff_stats.entries -= 1;
+                self.diff_stats.bytes -= size;
+            }
+        }
+        self.evict_diffs(self.diff_stats.budget - size);
+        self.diff_stats.entries += 1;
+        self.diff_stats.bytes += size;
+        self.diffs.push_back((key, diff, size));
+    }
+
+    pub fn set_diff_budget(&mut self, bytes: usize) {
+        self.diff_stats.budget = bytes;
+        self.evict_diffs(bytes);
+    }
+
+    pub fn diff_stats(&self) -> DiffCacheStats {
+        self.diff_stats
+    }
+
+    // Drops the least recently used diffs until they take at most `bytes`
+    fn evict_diffs(&mut self, bytes: usize) {
+        while self.diff_stats.bytes > bytes {
+            let (_, _, size) = match self.diffs.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            self.diff_stats.entries -= 1;
+            self.diff_stats.bytes -= size;
+            self.diff_stats.evictions += 1;
+            self.diff_stats.evicted_bytes += size;
+        }
+    }
+
+    pub fn get_diffstat(&self, hash: &str, paths: &[String]) -> Option<&DiffStat> {
+        self.diffstats.get(&(hash.to_string(), paths.to_vec()))
+    }
+
+    pub fn set_diffstat(&mut self, stat: DiffStat) {
+        self.diffstats.insert((stat.hash.clone(), stat.paths.clone()), stat);
+    }
+
+    /// Drops every diff and diffstat, once they'd be read differently
+    pub fn clear_diffs(&mut self) {
+        self.diffs.clear();
+        self.diffstats.clear();
+        self.diff_stats.entries = 0;
+        self.diff_stats.bytes = 0;
+    }
+
+    pub fn clear(&mut self) {
+        // The budget is a setting and the counters cover the whole session
+        let diff_stats = DiffCacheStats { entries: 0, bytes: 0, ..self.diff_stats };
+        *self = Cache { diff_stats, ..Cache::default() };
+    }
+}
+
+/// Roughly how much memory a diff takes: its patch, and every line again
+/// in the file model along with the line's own fields
+fn diff_size(diff: &CommitDiff) -> usize {
+    let files: usize = diff
+        .info
+        .files
+        .iter()
+        .map(|file| {
+            let paths = file.old_path.as_ref().map_or(0, String::len) + file.new_path.as_ref().map_or(0, String::len);
+            let hunks: usize = file
+                .hunks
+                .iter()
+                .map(|hunk| {
+                    let lines: usize = hunk.lines.iter().map(|line| line.content.len()).sum();
+                    size_of::<Hunk>() + hunk.header.len() + lines + hunk.lines.len() * size_of::<DiffLine>()
+                })
+                .sum();
+            size_of::<FileChange>() + paths + hunks
+        })
+        .sum();
+    size_of::<CommitDiff>() + diff.hash.len() + diff.patch.len() + files
+}
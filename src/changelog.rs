@@ -0,0 +1,55 @@
+use crate::conventional;
+use crate::models::CommitInfo;
+
+// Sections in the order they're written, with the commit types that go in each
+const SECTIONS: [(&str, &[&str]); 6] = [
+    ("Features", &["feat"]),
+    ("Bug fixes", &["fix"]),
+    ("Performance", &["perf"]),
+    ("Refactoring", &["refactor"]),
+    ("Documentation", &["docs"]),
+    ("Other", &[]),
+];
+
+/// A Markdown changelog of the commits in `from..to`, newest first within
+/// each section. Breaking changes are also listed together at the top,
+/// merges are left out and commits that don't follow Conventional Commits
+/// go under "Other".
+pub fn render(from: &str, to: &str, commits: &[CommitInfo]) -> String {
+    let mut breaking = Vec::new();
+    let mut sections: Vec<Vec<String>> = vec![Vec::new(); SECTIONS.len()];
+    for commit in commits.iter().filter(|c| c.parents.len() < 2) {
+        let short = &commit.hash[..7];
+        match conventional::parse(&commit.message) {
+            Some(parsed) => {
+                let entry = match parsed.scope {
+                    Some(scope) => format!("- **{}:** {} ({})", scope, parsed.description, short),
+                    None => format!("- {} ({})", parsed.description, short),
+                };
+                if parsed.breaking {
+                    breaking.push(entry.clone());
+                }
+                let section = SECTIONS
+                    .iter()
+                    .position(|(_, kinds)| kinds.contains(&parsed.kind.to_lowercase().as_str()))
+                    .unwrap_or(SECTIONS.len() - 1);
+                sections[section].push(entry);
+            },
+            None => {
+                let summary = commit.message.lines().next().unwrap_or("").trim();
+                sections[SECTIONS.len() - 1].push(format!("- {} ({})", summary, short));
+            },
+        }
+    }
+
+    let mut markdown = format!("# Changes from {} to {}\n", from, to);
+    if commits.is_empty() {
+        markdown.push_str("\nNo changes.\n");
+    }
+    let all = std::iter::once(("Breaking changes", breaking))
+        .chain(SECTIONS.iter().map(|(title, _)| *title).zip(sections));
+    for (title, entries) in all.filter(|(_, entries)| !entries.is_empty()) {
+        markdown.push_str(&format!("\n## {}\n\n{}\n", title, entries.join("\n")));
+    }
+    markdown
+}
@@ -0,0 +1,109 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use crate::export::ExportFormat;
+
+/// A terminal-based Git repository visualizer
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Repositories to open, switched between with the repository picker;
+    /// any directory inside one works [default: current directory]
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Also open every repository directly inside this directory
+    #[arg(long, value_name = "DIR")]
+    pub workspace: Option<PathBuf>,
+
+    /// Only show commits that changed this file, following renames
+    #[arg(long, value_name = "FILE")]
+    pub follow: Option<PathBuf>,
+
+    /// Only show commits that changed files matching this pathspec, which
+    /// may be a directory or a glob like `src/**/*.rs`; can be repeated
+    #[arg(long = "path", value_name = "PATHSPEC")]
+    pub pathspecs: Vec<String>,
+
+    /// Write the history to a file instead of opening the viewer, as JSON,
+    /// CSV, a Graphviz graph or an HTML page
+    #[arg(long, value_name = "FORMAT", requires = "out")]
+    pub export: Option<ExportFormat>,
+
+    /// File to export to
+    #[arg(long, value_name = "FILE", requires = "export")]
+    pub out: Option<PathBuf>,
+
+    /// Draw borders, arrows and the graph with plain ASCII instead of
+    /// Unicode symbols and emoji (the default when the locale isn't UTF-8)
+    #[arg(long)]
+    pub ascii: bool,
+}
+
+/// Commands that print to stdout instead of opening the viewer, for
+/// scripts and CI
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the history, one commit per line or as JSON
+    Log {
+        /// Print a JSON array with each commit's hash, author, date,
+        /// message and diffstat
+        #[arg(long)]
+        json: bool,
+
+        /// Stop after this many commits
+        #[arg(short = 'n', long, value_name = "COUNT")]
+        max_count: Option<usize>,
+
+        /// Only include commits that changed files matching this pathspec;
+        /// can be repeated
+        #[arg(long = "path", value_name = "PATHSPEC")]
+        pathspecs: Vec<String>,
+
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Print statistics for a branch: commits per author, the busiest files
+    /// and the average commit size
+    Stats {
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Print the commit graph like `git log --graph --oneline`
+    Graph {
+        /// Print a Graphviz digraph with the branches and tags instead
+        #[arg(long)]
+        dot: bool,
+
+        /// Stop after this many commits
+        #[arg(short = 'n', long, value_name = "COUNT")]
+        max_count: Option<usize>,
+
+        /// Only include commits that changed files matching this pathspec;
+        /// can be repeated
+        #[arg(long = "path", value_name = "PATHSPEC")]
+        pathspecs: Vec<String>,
+
+        #[command(flatten)]
+        target: Target,
+    },
+}
+
+/// Which repository and history a command reads
+#[derive(Debug, clap::Args)]
+pub struct Target {
+    /// Repository to read; any directory inside one works [default: current
+    /// directory]
+    #[arg(value_name = "PATH")]
+    pub path: Option<PathBuf>,
+
+    /// Branch, tag or revision to read [default: HEAD]
+    #[arg(long, value_name = "REV")]
+    pub branch: Option<String>,
+}
@@ -0,0 +1,50 @@
+use anyhow::{bail, Result};
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Clipboard tools tried in order, with the environment variable that has
+// to be set for each to make sense
+const TOOLS: [(&str, &[&str], Option<&str>); 5] = [
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("clip.exe", &[], None),
+];
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        bail!("{} failed", program);
+    }
+    Ok(())
+}
+
+/// Puts `text` on the system clipboard through the platform's clipboard
+/// tool, or failing that asks the terminal to with an OSC 52 escape, which
+/// also works over SSH. Returns how it was copied.
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args, needs) in TOOLS {
+        if needs.is_some_and(|var| std::env::var_os(var).is_none()) {
+            continue;
+        }
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", encoded)?;
+    stdout.flush()?;
+    Ok("the terminal")
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use crate::cli::{Command, Target};
+use crate::export::{self, ExportFormat};
+use git_visualiser::RepoExplorer;
+use crate::models::{CommitInfo, FilterSet, RefKind, RepoStats};
+use crate::ui::{ascii_text, graph_spans, needs_ascii, GraphBuilder};
+
+/// Runs a command given on the command line, printing its output to stdout
+pub fn run(command: &Command) -> Result<()> {
+    match command {
+        Command::Log { json, max_count, pathspecs, target } => {
+            let (mut explorer, branch) = open(target)?;
+            let commits = history(&mut explorer, &branch, pathspecs, *max_count)?;
+            if *json {
+                let commits: Vec<&CommitInfo> = commits.iter().collect();
+                let manager = explorer.manager();
+                let json = export::format_commits(&commits, &Default::default(), &branch, ExportFormat::Json, &mut |commit| {
+                    manager.get_diffstat(&commit.hash, &commit.paths)
+                })?;
+                return print(&json);
+            }
+            let lines: String = commits
+                .iter()
+                .map(|commit| format!("{}\t{}\t{}\t{}\n", &commit.hash[..7], date(commit.time), commit.author, summary(commit)))
+                .collect();
+            print(&lines)
+        },
+        Command::Stats { json, target } => {
+            let (mut explorer, branch) = open(target)?;
+            let stats = explorer.stats(&branch)?;
+            if *json {
+                return print(&(serde_json::to_string_pretty(&StatsReport::new(&branch, &stats))? + "\n"));
+            }
+            print(&stats_text(&branch, &stats))
+        },
+        Command::Graph { dot, max_count, pathspecs, target } => {
+            let (mut explorer, branch) = open(target)?;
+            let commits = history(&mut explorer, &branch, pathspecs, *max_count)?;
+            let labels = explorer.refs()?;
+            let commits: Vec<&CommitInfo> = commits.iter().collect();
+            if *dot {
+                // The graph has no diffstats, so none are read
+                let dot = export::format_commits(&commits, &labels, &branch, ExportFormat::Dot, &mut |_| Ok(Default::default()))?;
+                return print(&dot);
+            }
+            let mut graph = GraphBuilder::default();
+            let mut lines = String::new();
+            for commit in commits {
+                let cells: String = graph_spans(&graph.push(commit)).iter().map(|span| span.content.as_ref()).collect();
+                let refs: Vec<String> = labels
+                    .get(&commit.hash)
+                    .into_iter()
+                    .flatten()
+                    .map(|label| match label.kind {
+                        RefKind::Tag => format!("tag: {}", label.name),
+                        _ => label.name.clone(),
+                    })
+                    .collect();
+                let refs = if refs.is_empty() { String::new() } else { format!("({}) ", refs.join(", ")) };
+                lines.push_str(&format!("{} {} {}{}\n", cells.trim_end(), &commit.hash[..7], refs, summary(commit)));
+            }
+            // Box drawing would come out garbled where the UI falls back to ASCII
+            print(&if needs_ascii() { ascii_text(&lines) } else { lines })
+        },
+    }
+}
+
+/// The repository and the branch to read, the checked-out one unless one
+/// is named
+fn open(target: &Target) -> Result<(RepoExplorer, String)> {
+    let path = match &target.path {
+        Some(path) => path.clone(),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    let explorer = RepoExplorer::open(&path).with_context(|| format!("Not a Git repository ({})", path.display()))?;
+    let branch = match &target.branch {
+        Some(branch) => branch.clone(),
+        None => crate::head_branch(&explorer.head().context("Failed to read HEAD")?),
+    };
+    Ok((explorer, branch))
+}
+
+fn history(explorer: &mut RepoExplorer, branch: &str, pathspecs: &[String], limit: Option<usize>) -> Result<Vec<CommitInfo>> {
+    let paths = crate::cli_path_filter(explorer.manager(), pathspecs)?;
+    let filters = FilterSet { paths, ..Default::default() };
+    explorer.commits(branch, filters).take(limit.unwrap_or(usize::MAX)).collect()
+}
+
+fn summary(commit: &CommitInfo) -> &str {
+    commit.message.lines().next().unwrap_or("")
+}
+
+fn date(time: i64) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Writes to stdout, stopping quietly if it's a pipe that was closed early,
+/// as by `head`
+fn print(text: &str) -> Result<()> {
+    match std::io::stdout().lock().write_all(text.as_bytes()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e).context("Failed to write output"),
+        _ => Ok(()),
+    }
+}
+
+fn average(total: usize, stats: &RepoStats) -> f64 {
+    total as f64 / stats.diffed_commits.max(1) as f64
+}
+
+fn stats_text(branch: &str, stats: &RepoStats) -> String {
+    let mut text = format!("{}: {} commits by {} authors", branch, stats.commits, stats.authors.len());
+    if let (Some(first), Some(last)) = (stats.weeks.first(), stats.weeks.last()) {
+        text.push_str(&format!(", weeks of {} to {}", date(first.start), date(last.start)));
+    }
+    text.push_str(&format!(
+        "\nAverage commit: +{:.1} -{:.1} in {:.1} files\n\nCommits per author:\n",
+        average(stats.insertions, stats),
+        average(stats.deletions, stats),
+        average(stats.files_changed, stats)
+    ));
+    for (author, commits) in &stats.authors {
+        text.push_str(&format!("{:>7}  {}\n", commits, author));
+    }
+    text.push_str("\nBusiest files:\n");
+    for (path, commits) in &stats.files {
+        text.push_str(&format!("{:>7}  {}\n", commits, path));
+    }
+    text
+}
+
+/// `stats --json`, with names on everything `RepoStats` keeps in tuples
+#[derive(Serialize)]
+struct StatsReport<'a> {
+    branch: &'a str,
+    commits: usize,
+    authors: Vec<AuthorCount<'a>>,
+    files: Vec<FileCount<'a>>,
+    weeks: Vec<WeekReport>,
+    average_insertions: f64,
+    average_deletions: f64,
+    average_files_changed: f64,
+}
+
+#[derive(Serialize)]
+struct AuthorCount<'a> {
+    name: &'a str,
+    commits: usize,
+}
+
+#[derive(Serialize)]
+struct FileCount<'a> {
+    path: &'a str,
+    commits: usize,
+}
+
+#[derive(Serialize)]
+struct WeekReport {
+    // The Monday the week starts on
+    start: String,
+    commits: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl<'a> StatsReport<'a> {
+    fn new(branch: &'a str, stats: &'a RepoStats) -> Self {
+        StatsReport {
+            branch,
+            commits: stats.commits,
+            authors: stats.authors.iter().map(|(name, commits)| AuthorCount { name, commits: *commits }).collect(),
+            files: stats.files.iter().map(|(path, commits)| FileCount { path, commits: *commits }).collect(),
+            weeks: stats
+                .weeks
+                .iter()
+                .map(|week| WeekReport { start: date(week.start), commits: week.commits, insertions: week.insertions, deletions: week.deletions })
+                .collect(),
+            average_insertions: average(stats.insertions, stats),
+            average_deletions: average(stats.deletions, stats),
+            average_files_changed: average(stats.files_changed, stats),
+        }
+    }
+}
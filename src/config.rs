@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::forge::ForgeTemplates;
+use crate::git::DEFAULT_SIMILARITY;
+use crate::keymap::{Action, Profile};
+
+/// User settings read from `config.json` in the git-visualiser config
+/// directory. Every field is optional.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub keymap: KeymapConfig,
+    // Reload when HEAD or any ref changes on disk
+    pub auto_refresh: bool,
+    // Width of the commit list as a percentage of the screen
+    pub list_width: u16,
+    pub date_format: DateFormat,
+    pub theme: ThemeName,
+    // Columns of the commit list, in order; any left out are hidden
+    pub columns: Vec<Column>,
+    // Web URLs for self-hosted forges, keyed by host
+    pub forges: HashMap<String, ForgeTemplates>,
+    // Look up the pull request of the selected commit on GitHub
+    pub pull_requests: bool,
+    // Used when GITHUB_TOKEN isn't set
+    pub github_token: Option<String>,
+    // GitHub Enterprise hosts to look pull requests up on, and send the token to
+    pub github_hosts: Vec<String>,
+    // Memory the diffs of recently viewed commits may take, in megabytes
+    pub diff_cache_mb: usize,
+    // How images in diffs are drawn; auto guesses from the terminal
+    pub image_protocol: ImageProtocol,
+    // Pair up files in diffs as renames and copies
+    pub detect_renames: bool,
+    // How alike, in percent, files must be to be paired up
+    pub rename_similarity: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keymap: KeymapConfig::default(),
+            auto_refresh: true,
+            list_width: 30,
+            date_format: DateFormat::default(),
+            theme: ThemeName::default(),
+            columns: COLUMNS.to_vec(),
+            forges: HashMap::new(),
+            pull_requests: true,
+            github_token: None,
+            github_hosts: Vec::new(),
+            diff_cache_mb: 64,
+            image_protocol: ImageProtocol::default(),
+            detect_renames: true,
+            rename_similarity: DEFAULT_SIMILARITY,
+        }
+    }
+}
+
+/// How dates are written in the commit list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    // "3 days ago"
+    #[default]
+    Relative,
+    // 2024-05-01 14:03:22 in the local timezone
+    Local,
+    // 2024-05-01T14:03:22+02:00
+    Iso,
+}
+
+impl DateFormat {
+    pub fn next(self) -> DateFormat {
+        match self {
+            DateFormat::Relative => DateFormat::Local,
+            DateFormat::Local => DateFormat::Iso,
+            DateFormat::Iso => DateFormat::Relative,
+        }
+    }
+}
+
+/// One of the built-in colour themes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    Solarized,
+    Gruvbox,
+}
+
+impl ThemeName {
+    pub fn next(self) -> ThemeName {
+        match self {
+            ThemeName::Dark => ThemeName::Light,
+            ThemeName::Light => ThemeName::Solarized,
+            ThemeName::Solarized => ThemeName::Gruvbox,
+            ThemeName::Gruvbox => ThemeName::Dark,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeName::Dark => "dark",
+            ThemeName::Light => "light",
+            ThemeName::Solarized => "solarized",
+            ThemeName::Gruvbox => "gruvbox",
+        }
+    }
+}
+
+/// The terminal graphics protocol used to draw images in diffs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    // Only describe images, never draw them
+    None,
+}
+
+/// A column of the commit list, after the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    // Initials coloured by email
+    Author,
+    Date,
+    // The first seven characters
+    Hash,
+    // The first line of the message, with the commit's refs before it
+    Subject,
+}
+
+pub const COLUMNS: [Column; 4] = [Column::Author, Column::Date, Column::Hash, Column::Subject];
+
+impl Column {
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Author => "Author",
+            Column::Date => "Date",
+            Column::Hash => "Hash",
+            Column::Subject => "Subject",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct KeymapConfig {
+    pub profile: Profile,
+    // Replaces the default keys of each listed action
+    pub bindings: HashMap<Action, Vec<String>>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/git-visualiser/config.json`, falling back to
+    /// `~/.config/git-visualiser/config.json`
+    pub fn path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("git-visualiser").join("config.json"))
+    }
+
+    /// Loads the config file, or the defaults if there isn't one
+    pub fn load() -> Result<Config> {
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Writes a single setting back to the config file, leaving everything
+    /// else in it as the user wrote it
+    pub fn save_value(key: &str, value: serde_json::Value) -> Result<()> {
+        let path = Config::path().context("No config directory")?;
+        let mut settings = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(_) => serde_json::Value::Object(Default::default()),
+        };
+        settings
+            .as_object_mut()
+            .with_context(|| format!("{} is not a JSON object", path.display()))?
+            .insert(key.to_string(), value);
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&settings)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,33 @@
+/// A commit summary following the Conventional Commits format, e.g.
+/// `feat(parser)!: drop the old syntax`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conventional<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+/// Parses the first line of `message`, or returns None if it doesn't start
+/// with a `type(scope)!:` prefix. A `BREAKING CHANGE:` footer also marks
+/// the commit as breaking.
+pub fn parse(message: &str) -> Option<Conventional<'_>> {
+    let summary = message.lines().next()?;
+    let (prefix, description) = summary.split_once(':')?;
+    let description = description.trim();
+    let (prefix, bang) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) || description.is_empty() {
+        return None;
+    }
+    let footer = message.lines().skip(1).any(|line| {
+        line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+    });
+    Some(Conventional { kind, scope, breaking: bang || footer, description })
+}
@@ -0,0 +1,168 @@
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use crate::git::{CancelToken, GitManager, COMMIT_PAGE_SIZE};
+use crate::models::{BranchInfo, CommitDiff, CommitInfo, DiffStat, FileChange, FilterSet, HeadState, RangeDiff, RefLabel, RepoStats, TagInfo};
+
+/// A repository opened for reading its history, refs and diffs
+pub struct RepoExplorer {
+    manager: GitManager,
+}
+
+impl RepoExplorer {
+    /// Opens the repository containing `path`, searching parent directories
+    /// the same way `git` does
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(RepoExplorer { manager: GitManager::new(path)? })
+    }
+
+    /// The working tree's top directory, or the git directory if it's bare
+    pub fn root(&self) -> PathBuf {
+        self.manager.root()
+    }
+
+    pub fn head(&self) -> Result<HeadState> {
+        self.manager.head_state()
+    }
+
+    /// Local branches followed by remote-tracking ones
+    pub fn branches(&mut self) -> Result<Vec<BranchInfo>> {
+        self.manager.get_branches()
+    }
+
+    pub fn tags(&self) -> Result<Vec<TagInfo>> {
+        self.manager.get_tags()
+    }
+
+    /// Branches, tags and HEAD by the hash of the commit they point at
+    pub fn refs(&self) -> Result<HashMap<String, Vec<RefLabel>>> {
+        self.manager.get_ref_map()
+    }
+
+    /// The history of `rev`, a branch, tag or any revision `git` accepts,
+    /// newest first with children before their parents. Only commits
+    /// passing `filters` are included.
+    pub fn commits(&mut self, rev: &str, filters: FilterSet) -> CommitStream<'_> {
+        CommitStream::new(&mut self.manager, rev, None, filters)
+    }
+
+    /// The commits on `rev` that changed the file at `path`, relative to
+    /// the repository root, following it back through renames. Their diffs
+    /// only cover that file, under the name it had in each.
+    pub fn file_history(&mut self, rev: &str, path: &str) -> CommitStream<'_> {
+        CommitStream::new(&mut self.manager, rev, Some(path.to_string()), FilterSet::default())
+    }
+
+    /// What `commit` changed against its first parent, worked out now
+    /// rather than while its history was read. Recent diffs are cached.
+    pub fn diff(&mut self, commit: &CommitInfo) -> Result<DiffModel> {
+        Ok(DiffModel::from(self.manager.commit_diff(&commit.hash, &commit.paths, true)?))
+    }
+
+    /// How many lines `commit` added and removed in each file, counted by
+    /// git without building the patch, so it's quick even for huge commits
+    pub fn diffstat(&mut self, commit: &CommitInfo) -> Result<DiffStat> {
+        self.manager.get_diffstat(&commit.hash, &commit.paths)
+    }
+
+    /// The changes of `oldest` through `newest` combined, from the parent of
+    /// `oldest` to `newest`
+    pub fn diff_range(&self, oldest: &str, newest: &str) -> Result<DiffModel> {
+        Ok(DiffModel::from(self.manager.range_diff(oldest, newest)?))
+    }
+
+    /// Commits per author and week, the busiest files and the totals for
+    /// the average commit size, over all of `rev`'s history
+    pub fn stats(&mut self, rev: &str) -> Result<RepoStats> {
+        self.manager.repo_stats(rev)
+    }
+
+    /// Everything else the repository can be asked, from blame and grep to
+    /// checkouts and cherry-picks
+    pub fn manager(&mut self) -> &mut GitManager {
+        &mut self.manager
+    }
+}
+
+/// A branch's history, read a page at a time as the stream is iterated.
+/// An error ends the stream after it's been returned.
+pub struct CommitStream<'a> {
+    manager: &'a mut GitManager,
+    rev: String,
+    path: Option<String>,
+    filters: FilterSet,
+    // Commits read but not returned yet, and how many came before them
+    page: VecDeque<CommitInfo>,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> CommitStream<'a> {
+    fn new(manager: &'a mut GitManager, rev: &str, path: Option<String>, filters: FilterSet) -> Self {
+        CommitStream {
+            manager,
+            rev: rev.to_string(),
+            path,
+            filters,
+            page: VecDeque::new(),
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for CommitStream<'_> {
+    type Item = Result<CommitInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() && !self.done {
+            match self.manager.get_commits_page(&self.rev, self.path.as_deref(), &self.filters, self.offset, COMMIT_PAGE_SIZE, &CancelToken::never()) {
+                Ok(page) => {
+                    self.done = page.len() < COMMIT_PAGE_SIZE;
+                    self.offset += page.len();
+                    self.page = page.into();
+                },
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                },
+            }
+        }
+        self.page.pop_front().map(Ok)
+    }
+}
+
+/// The changes between two trees: the patch as `git diff` prints it, and
+/// each changed file with its hunks and lines
+#[derive(Debug, Clone)]
+pub struct DiffModel {
+    pub patch: String,
+    pub files: Vec<FileChange>,
+}
+
+impl DiffModel {
+    /// The file at `path` on either side of the change
+    pub fn file(&self, path: &str) -> Option<&FileChange> {
+        self.files.iter().find(|file| file.new_path.as_deref() == Some(path) || file.old_path.as_deref() == Some(path))
+    }
+
+    /// Number of added and removed lines across all files
+    pub fn line_stats(&self) -> (usize, usize) {
+        self.files
+            .iter()
+            .map(|file| file.line_stats())
+            .fold((0, 0), |(added, removed), (a, r)| (added + a, removed + r))
+    }
+}
+
+impl From<CommitDiff> for DiffModel {
+    fn from(diff: CommitDiff) -> Self {
+        DiffModel { patch: diff.patch, files: diff.info.files }
+    }
+}
+
+impl From<RangeDiff> for DiffModel {
+    fn from(diff: RangeDiff) -> Self {
+        DiffModel { patch: diff.patch, files: diff.info.files }
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::models::{CommitInfo, DiffStat, RefKind, RefLabel};
+use crate::ui::{graph_glyphs, GraphBuilder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    // The commit graph as a Graphviz digraph
+    Dot,
+    // A standalone page with the graph, messages and diffstats
+    Html,
+    // A patch series in mbox form, for `git am`
+    Patch,
+}
+
+impl ExportFormat {
+    /// Picks the format from the file's extension: `.csv`, `.dot`, `.gv`,
+    /// `.html`, `.htm`, `.patch` or `.mbox`, and JSON for anything else
+    pub fn from_path(path: &Path) -> ExportFormat {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("csv") => ExportFormat::Csv,
+            Some("dot") | Some("gv") => ExportFormat::Dot,
+            Some("html") | Some("htm") => ExportFormat::Html,
+            Some("patch") | Some("mbox") => ExportFormat::Patch,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+/// A commit as written to an export, with its diffstat summed up
+#[derive(Debug, Serialize)]
+struct ExportedCommit<'a> {
+    hash: &'a str,
+    author: &'a str,
+    date: String,
+    message: &'a str,
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+impl<'a> ExportedCommit<'a> {
+    fn new(commit: &'a CommitInfo, stat: &DiffStat) -> Self {
+        ExportedCommit {
+            hash: &commit.hash,
+            author: &commit.author,
+            date: chrono::DateTime::from_timestamp(commit.time, 0)
+                .map(|date| date.to_rfc3339())
+                .unwrap_or_default(),
+            message: commit.message.trim_end(),
+            files_changed: stat.files.len(),
+            insertions: stat.insertions,
+            deletions: stat.deletions,
+        }
+    }
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(commits: &[ExportedCommit]) -> String {
+    let mut csv = String::from("hash,author,date,message,files_changed,insertions,deletions\n");
+    for commit in commits {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            commit.hash,
+            csv_field(commit.author),
+            commit.date,
+            csv_field(commit.message),
+            commit.files_changed,
+            commit.insertions,
+            commit.deletions
+        ));
+    }
+    csv
+}
+
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Newest commits at the top with an edge to each parent, and branches
+/// and tags as labels pointing at their commits. Parents outside the
+/// export are left out rather than drawn as empty nodes.
+fn to_dot(commits: &[&CommitInfo], labels: &HashMap<String, Vec<RefLabel>>) -> String {
+    let exported: HashSet<&str> = commits.iter().map(|c| c.hash.as_str()).collect();
+    let mut dot = String::from("digraph commits {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for commit in commits {
+        let summary = commit.message.lines().next().unwrap_or("");
+        let label = format!("{}\\n{}", &commit.hash[..7], summary.replace('\\', "\\\\").replace('"', "\\\""));
+        dot.push_str(&format!("    {} [label=\"{}\"];\n", dot_string(&commit.hash), label));
+        for parent in commit.parents.iter().filter(|p| exported.contains(p.as_str())) {
+            dot.push_str(&format!("    {} -> {};\n", dot_string(&commit.hash), dot_string(parent)));
+        }
+        for label in labels.get(&commit.hash).into_iter().flatten() {
+            let (text, colour) = match label.kind {
+                RefKind::Head => (label.name.clone(), "lightblue"),
+                RefKind::LocalBranch => (label.name.clone(), "palegreen"),
+                RefKind::RemoteBranch => (label.name.clone(), "lightpink"),
+                RefKind::Tag => (format!("tag: {}", label.name), "khaki"),
+            };
+            let id = dot_string(&format!("{} {}", text, commit.hash));
+            let target = dot_string(&commit.hash);
+            dot.push_str(&format!("    {} [label={}, shape=cds, style=filled, fillcolor={}];\n", id, dot_string(&text), colour));
+            dot.push_str(&format!("    {} -> {} [style=dashed, arrowhead=none];\n", id, target));
+            dot.push_str(&format!("    {{ rank=same; {}; {}; }}\n", id, target));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The graph's lane colours on the page, fixed whatever theme the terminal uses
+const LANE_COLOURS: [&str; 6] = ["#d9534f", "#3c9a3c", "#c9a227", "#b04fc0", "#2aa9b8", "#3f6fd8"];
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; }
+td { padding: 0 0.6em; vertical-align: top; white-space: nowrap; }
+td.graph, td.hash { font-family: monospace; white-space: pre; line-height: 1.2; }
+td.hash { color: #a07a00; }
+td.message { white-space: normal; }
+td.stat { font-family: monospace; }
+.added { color: #3c9a3c; }
+.deleted { color: #d9534f; }
+.ref { font-size: 0.8em; border-radius: 3px; padding: 0 0.3em; margin-right: 0.3em; }
+.head { background: #cde3f7; } .branch { background: #d4f0d4; } .remote { background: #f7d4d4; } .tag { background: #f2e6b3; }
+details { color: #555; } details pre { white-space: pre-wrap; margin: 0.3em 0; }
+";
+
+/// A standalone page laid out like the commit list: the graph, short
+/// hash, message with its refs, author, date and diffstat of each commit
+fn to_html(title: &str, commits: &[&CommitInfo], exported: &[ExportedCommit], labels: &HashMap<String, Vec<RefLabel>>) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{} commits</p>\n<table>\n",
+        commits.len(),
+        title = escape_html(title),
+    );
+
+    let mut graph = GraphBuilder::default();
+    for (commit, exported) in commits.iter().zip(exported) {
+        let graph_cells: String = graph_glyphs(&graph.push(commit))
+            .into_iter()
+            .map(|(glyph, lane)| match lane {
+                Some(lane) => format!("<span style=\"color: {}\">{}</span>", LANE_COLOURS[lane % LANE_COLOURS.len()], glyph),
+                None => glyph.to_string(),
+            })
+            .collect();
+
+        let refs: String = labels
+            .get(&commit.hash)
+            .into_iter()
+            .flatten()
+            .map(|label| {
+                let (class, text) = match label.kind {
+                    RefKind::Head => ("head", label.name.clone()),
+                    RefKind::LocalBranch => ("branch", label.name.clone()),
+                    RefKind::RemoteBranch => ("remote", label.name.clone()),
+                    RefKind::Tag => ("tag", format!("tag: {}", label.name)),
+                };
+                format!("<span class=\"ref {}\">{}</span>", class, escape_html(&text))
+            })
+            .collect();
+        let mut lines = commit.message.trim_end().splitn(2, '\n');
+        let summary = escape_html(lines.next().unwrap_or(""));
+        let body = match lines.next().map(str::trim) {
+            Some(body) if !body.is_empty() => format!("<details><summary>more</summary><pre>{}</pre></details>", escape_html(body)),
+            _ => String::new(),
+        };
+
+        html.push_str(&format!(
+            "<tr><td class=\"graph\">{}</td><td class=\"hash\">{}</td><td class=\"message\">{}{}{}</td><td>{}</td><td>{}</td><td class=\"stat\"><span class=\"added\">+{}</span> <span class=\"deleted\">-{}</span> in {} files</td></tr>\n",
+            graph_cells,
+            &commit.hash[..7],
+            refs,
+            summary,
+            body,
+            escape_html(&commit.author),
+            exported.date,
+            exported.insertions,
+            exported.deletions,
+            exported.files_changed,
+        ));
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Formats `commits` in the given format; `labels` are the refs shown on
+/// the graph and `title` heads an HTML page. Each commit's diffstat is read
+/// with `diffstat`, by the formats that show one.
+pub fn format_commits(
+    commits: &[&CommitInfo],
+    labels: &HashMap<String, Vec<RefLabel>>,
+    title: &str,
+    format: ExportFormat,
+    diffstat: &mut dyn FnMut(&CommitInfo) -> Result<DiffStat>,
+) -> Result<String> {
+    let mut exported = || -> Result<Vec<ExportedCommit>> {
+        commits.iter().map(|commit| Ok(ExportedCommit::new(commit, &diffstat(commit)?))).collect()
+    };
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&exported()?)? + "\n",
+        ExportFormat::Csv => to_csv(&exported()?),
+        ExportFormat::Dot => to_dot(commits, labels),
+        ExportFormat::Html => to_html(title, commits, &exported()?, labels),
+        // Formatting patches needs the repository, so callers write those
+        ExportFormat::Patch => anyhow::bail!("Patches are written from the repository"),
+    };
+    Ok(contents)
+}
+
+/// Writes `commits` to `out` in the given format
+pub fn write_commits<'a>(
+    commits: impl IntoIterator<Item = &'a CommitInfo>,
+    labels: &HashMap<String, Vec<RefLabel>>,
+    title: &str,
+    format: ExportFormat,
+    out: &Path,
+    diffstat: &mut dyn FnMut(&CommitInfo) -> Result<DiffStat>,
+) -> Result<()> {
+    let commits: Vec<&CommitInfo> = commits.into_iter().collect();
+    let contents = format_commits(&commits, labels, title, format, diffstat)?;
+    std::fs::write(out, contents).with_context(|| format!("Failed to write {}", out.display()))
+}
@@ -0,0 +1,120 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+/// Web URLs of a forge's pages. `{base}` is the repository's page, e.g.
+/// `https://github.com/owner/repo`, `{hash}` the full commit hash and
+/// `{path}` a file's path in the repository.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgeTemplates {
+    pub commit: String,
+    pub file: String,
+}
+
+impl ForgeTemplates {
+    fn new(commit: &str, file: &str) -> Self {
+        ForgeTemplates { commit: commit.to_string(), file: file.to_string() }
+    }
+
+    /// The built-in templates for GitHub, GitLab and Bitbucket hosts
+    fn for_host(host: &str) -> Option<ForgeTemplates> {
+        if host.contains("github") {
+            Some(ForgeTemplates::new("{base}/commit/{hash}", "{base}/blob/{hash}/{path}"))
+        } else if host.contains("gitlab") {
+            Some(ForgeTemplates::new("{base}/-/commit/{hash}", "{base}/-/blob/{hash}/{path}"))
+        } else if host.contains("bitbucket") {
+            Some(ForgeTemplates::new("{base}/commits/{hash}", "{base}/src/{hash}/{path}"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Splits a remote URL into its host and the repository's path on it,
+/// for `git@host:owner/repo.git`, `ssh://git@host:22/owner/repo` and
+/// `https://host/owner/repo.git` alike
+pub fn host_and_path(url: &str) -> Option<(String, String)> {
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host.to_lowercase(), path.to_string()))
+}
+
+/// Percent-encodes each segment of a slash-separated path for a URL, so
+/// characters like `#`, `?`, `&` or spaces in it can't change its meaning
+fn encode_path(path: &str) -> String {
+    path.split('/').map(encode_segment).collect::<Vec<_>>().join("/")
+}
+
+fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// A repository on a forge, worked out from its remote URL
+pub struct Forge {
+    base: String,
+    templates: ForgeTemplates,
+}
+
+impl Forge {
+    /// Recognises GitHub, GitLab and Bitbucket, and any host given its own
+    /// templates in the config
+    pub fn from_remote(url: &str, custom: &HashMap<String, ForgeTemplates>) -> Result<Forge> {
+        let (host, path) = host_and_path(url).with_context(|| format!("Can't tell the host of {}", url))?;
+        let templates = match custom.get(&host).cloned().or_else(|| ForgeTemplates::for_host(&host)) {
+            Some(templates) => templates,
+            None => bail!("{} isn't a known forge; add its URLs to \"forges\" in the config", host),
+        };
+        Ok(Forge { base: format!("https://{}/{}", host, encode_path(&path)), templates })
+    }
+
+    fn fill(&self, template: &str, hash: &str, path: &str) -> String {
+        template.replace("{base}", &self.base).replace("{hash}", hash).replace("{path}", path)
+    }
+
+    pub fn commit_url(&self, hash: &str) -> String {
+        self.fill(&self.templates.commit, hash, "")
+    }
+
+    pub fn file_url(&self, hash: &str, path: &str) -> String {
+        self.fill(&self.templates.file, hash, &encode_path(path))
+    }
+}
+
+/// Opens `url` in the default browser
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not through cmd, which would read characters like & in the URL
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start the browser")?;
+    Ok(())
+}
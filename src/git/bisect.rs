@@ -0,0 +1,58 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use super::GitManager;
+use crate::models::BisectStep;
+
+// Above this many candidates, working out how many of them each one
+// reaches takes too much memory, so the middle one is tested instead
+const MAX_EXACT_CANDIDATES: usize = 20_000;
+
+impl GitManager {
+    /// The commit to test next, the way `git bisect` picks it: of the
+    /// commits reachable from `bad` but from none of `good`, the one whose
+    /// ancestors among them are closest to half
+    pub fn bisect_step(&self, bad: &str, good: &[String]) -> Result<BisectStep> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+        revwalk.push(git2::Oid::from_str(bad)?)?;
+        for hash in good {
+            revwalk.hide(git2::Oid::from_str(hash)?)?;
+        }
+        // Children come before their parents
+        let candidates = revwalk.collect::<Result<Vec<_>, _>>()?;
+
+        match candidates.len() {
+            0 => bail!("The bad commit is an ancestor of a good one"),
+            1 => return Ok(BisectStep::Culprit(bad.to_string())),
+            _ => {},
+        }
+        let total = candidates.len();
+        if total > MAX_EXACT_CANDIDATES {
+            let hash = candidates[total / 2].to_string();
+            return Ok(BisectStep::Probe { hash, remaining: total });
+        }
+
+        let index: HashMap<git2::Oid, usize> = candidates.iter().enumerate().map(|(i, oid)| (*oid, i)).collect();
+        let words = total.div_ceil(64);
+        let mut reach = vec![vec![0u64; words]; total];
+        let mut best = (0, 0);
+        // Parents first, so each commit's ancestors are known when it's reached
+        for i in (0..total).rev() {
+            let commit = self.repo.find_commit(candidates[i])?;
+            let mut ancestors = vec![0u64; words];
+            ancestors[i / 64] |= 1 << (i % 64);
+            for parent in commit.parent_ids().filter_map(|id| index.get(&id)) {
+                for (word, parent_word) in ancestors.iter_mut().zip(&reach[*parent]) {
+                    *word |= parent_word;
+                }
+            }
+            let count = ancestors.iter().map(|word| word.count_ones() as usize).sum::<usize>();
+            let balance = count.min(total - count);
+            if balance > best.1 {
+                best = (i, balance);
+            }
+            reach[i] = ancestors;
+        }
+        Ok(BisectStep::Probe { hash: candidates[best.0].to_string(), remaining: total })
+    }
+}
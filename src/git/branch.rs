@@ -0,0 +1,38 @@
+use anyhow::Result;
+use git2::{BranchType, Oid};
+use super::GitManager;
+
+impl GitManager {
+    /// Creates a local branch pointing at `hash`, without checking it out
+    pub fn create_branch(&self, name: &str, hash: &str) -> Result<()> {
+        let commit = self.repo.find_commit(Oid::from_str(hash)?)?;
+        self.repo.branch(name, &commit, false)?;
+        Ok(())
+    }
+
+    /// Whether every commit on the local branch is already reachable from
+    /// HEAD, so deleting it loses nothing
+    pub fn is_branch_merged(&self, name: &str) -> Result<bool> {
+        let tip = self.repo.find_branch(name, BranchType::Local)?.get().peel_to_commit()?.id();
+        let head = match self.repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(head) => head.id(),
+            Err(_) => return Ok(false),
+        };
+        Ok(tip == head || self.repo.graph_descendant_of(head, tip)?)
+    }
+
+    pub fn delete_branch(&self, name: &str) -> Result<()> {
+        let mut branch = self.repo.find_branch(name, BranchType::Local)?;
+        if branch.is_head() {
+            anyhow::bail!("{} is checked out", name);
+        }
+        branch.delete()?;
+        Ok(())
+    }
+
+    pub fn rename_branch(&self, name: &str, new_name: &str) -> Result<()> {
+        let mut branch = self.repo.find_branch(name, BranchType::Local)?;
+        branch.rename(new_name, false)?;
+        Ok(())
+    }
+}
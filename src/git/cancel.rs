@@ -0,0 +1,60 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counts the loads started for a list, so each one can tell when a newer
+/// one has taken its place
+#[derive(Clone, Default)]
+pub struct Generation(Arc<AtomicU64>);
+
+impl Generation {
+    /// Starts a new generation, cancelling every token handed out so far
+    pub fn advance(&self) -> CancelToken {
+        let started = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+        CancelToken { current: self.0.clone(), started }
+    }
+
+    /// A token for more work in the current generation
+    pub fn token(&self) -> CancelToken {
+        CancelToken { current: self.0.clone(), started: self.0.load(Ordering::SeqCst) }
+    }
+}
+
+/// Checked as history is walked, to stop once the walk is no longer wanted
+#[derive(Clone)]
+pub struct CancelToken {
+    current: Arc<AtomicU64>,
+    started: u64,
+}
+
+impl CancelToken {
+    /// For walks nothing will cancel, such as an export
+    pub fn never() -> Self {
+        Generation::default().token()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.current.load(Ordering::SeqCst) != self.started
+    }
+
+    /// Fails with `Cancelled` once a newer generation has started
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
+
+/// The error a cancelled walk stops with, told apart from real failures
+/// with `anyhow::Error::is`
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, Oid};
+use super::GitManager;
+
+impl GitManager {
+    /// Switches HEAD to a local branch, updating the index and working tree.
+    /// A remote-tracking branch is checked out detached, as `git checkout
+    /// origin/main` does.
+    pub fn checkout_branch(&self, name: &str) -> Result<()> {
+        match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => {
+                let commit = branch.get().peel_to_commit()?;
+                self.checkout_safely(&commit)?;
+                let refname = branch.get().name().unwrap_or("").to_string();
+                self.repo.set_head(&refname)?;
+            },
+            Err(_) => {
+                let branch = self.repo.find_branch(name, BranchType::Remote)?;
+                let commit = branch.get().peel_to_commit()?;
+                self.checkout_safely(&commit)?;
+                self.repo.set_head_detached(commit.id())?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Detaches HEAD at `hash`
+    pub fn checkout_commit(&self, hash: &str) -> Result<()> {
+        let commit = self.repo.find_commit(Oid::from_str(hash)?)?;
+        self.checkout_safely(&commit)?;
+        self.repo.set_head_detached(commit.id())?;
+        Ok(())
+    }
+
+    // Local changes are carried over, but the checkout refuses to run if
+    // any of them would be overwritten
+    fn checkout_safely(&self, commit: &git2::Commit) -> Result<()> {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        self.repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+        Ok(())
+    }
+}
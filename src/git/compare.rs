@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use super::{diff, GitManager, COMMIT_PAGE_SIZE};
+use crate::models::{CommitInfo, Comparison, MarkedDiff, RangeDiff};
+
+impl GitManager {
+    /// Compares `base...target` the way GitHub's compare page does
+    pub fn compare(&self, base: &str, target: &str) -> Result<Comparison> {
+        let base_id = self.branch_tip(base).with_context(|| format!("Unknown ref {}", base))?;
+        let target_id = self.branch_tip(target).with_context(|| format!("Unknown ref {}", target))?;
+
+        let merge_base = self
+            .repo
+            .merge_base(base_id, target_id)
+            .with_context(|| format!("{} and {} have no common history", base, target))?;
+        let from = self.repo.find_commit(merge_base)?.tree()?;
+        let to = self.repo.find_commit(target_id)?.tree()?;
+        let mut changes = self.repo.diff_tree_to_tree(Some(&from), Some(&to), Some(&mut self.diff_options()))?;
+        self.find_renames(&mut changes)?;
+        // Only a page of each side is listed, but they're counted in full
+        let (target_count, base_count) = self.repo.graph_ahead_behind(target_id, base_id)?;
+
+        Ok(Comparison {
+            base: base.to_string(),
+            target: target.to_string(),
+            only_base: self.commits_between(Some(target_id), base_id, COMMIT_PAGE_SIZE)?,
+            only_target: self.commits_between(Some(base_id), target_id, COMMIT_PAGE_SIZE)?,
+            base_count,
+            target_count,
+            files: diff::diff_info(&changes)?.files,
+        })
+    }
+
+    /// Combined diff of `oldest` through `newest`, against the empty tree
+    /// if `oldest` is a root commit
+    pub fn range_diff(&self, oldest: &str, newest: &str) -> Result<RangeDiff> {
+        let from = match self.repo.find_commit(git2::Oid::from_str(oldest)?)?.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let to = self.repo.find_commit(git2::Oid::from_str(newest)?)?.tree()?;
+        let mut changes = self.repo.diff_tree_to_tree(from.as_ref(), Some(&to), Some(&mut self.diff_options()))?;
+        self.find_renames(&mut changes)?;
+        Ok(RangeDiff {
+            oldest: oldest.to_string(),
+            newest: newest.to_string(),
+            patch: diff::patch_text(&changes)?,
+            info: diff::diff_info(&changes)?,
+        })
+    }
+
+    /// Replays `hashes`, oldest first, on top of the first one's parent and
+    /// diffs the result against that parent. Commits that don't follow on
+    /// from the one before are merged in like a cherry-pick, which writes
+    /// the resulting trees to the object database.
+    pub fn marked_diff(&self, hashes: &[String]) -> Result<MarkedDiff> {
+        // A root commit is replayed on top of an empty tree
+        let empty = self.repo.find_tree(self.repo.treebuilder(None)?.write()?)?;
+
+        let mut base = None;
+        let mut tree: Option<git2::Tree> = None;
+        for hash in hashes {
+            let commit = self.repo.find_commit(git2::Oid::from_str(hash)?)?;
+            let parent = match commit.parent(0) {
+                Ok(parent) => parent.tree()?,
+                Err(_) => empty.clone(),
+            };
+            tree = Some(match tree {
+                Some(ours) if ours.id() != parent.id() => {
+                    let mut index = self.repo.merge_trees(&parent, &ours, &commit.tree()?, None)?;
+                    if index.has_conflicts() {
+                        anyhow::bail!("{} conflicts with the marked commits before it", &hash[..7]);
+                    }
+                    self.repo.find_tree(index.write_tree_to(&self.repo)?)?
+                },
+                _ => commit.tree()?,
+            });
+            base.get_or_insert(parent);
+        }
+        let (base, tree) = match (base, tree) {
+            (Some(base), Some(tree)) => (base, tree),
+            _ => anyhow::bail!("No commits are marked"),
+        };
+
+        let mut changes = self.repo.diff_tree_to_tree(Some(&base), Some(&tree), Some(&mut self.diff_options()))?;
+        self.find_renames(&mut changes)?;
+        Ok(MarkedDiff {
+            hashes: hashes.to_vec(),
+            patch: diff::patch_text(&changes)?,
+            info: diff::diff_info(&changes)?,
+        })
+    }
+
+    /// What to compare `branch` against by default: its upstream, or else
+    /// the usual main branch
+    pub fn default_compare_base(&self, branch: &str) -> String {
+        let upstream = self
+            .repo
+            .find_branch(branch, git2::BranchType::Local)
+            .and_then(|local| local.upstream())
+            .ok()
+            .and_then(|upstream| upstream.name().ok().flatten().map(|name| name.to_string()));
+        if let Some(upstream) = upstream {
+            return upstream;
+        }
+        ["main", "master"]
+            .into_iter()
+            .find(|name| *name != branch && self.repo.find_branch(name, git2::BranchType::Local).is_ok())
+            .unwrap_or("HEAD")
+            .to_string()
+    }
+
+    /// Every commit in `to` that isn't in `from`, or all of its history
+    /// without one, newest first, for a changelog
+    pub fn commits_since(&self, from: Option<&str>, to: &str) -> Result<Vec<CommitInfo>> {
+        let to_id = self.branch_tip(to).with_context(|| format!("Unknown ref {}", to))?;
+        let from_id = match from {
+            Some(from) => Some(self.branch_tip(from).with_context(|| format!("Unknown ref {}", from))?),
+            None => None,
+        };
+        self.commits_between(from_id, to_id, usize::MAX)
+    }
+
+    /// The newest tag reachable from `rev`, which is where its changelog
+    /// usually starts
+    pub fn latest_tag(&self, rev: &str) -> Option<String> {
+        let commit = self.repo.find_commit(self.branch_tip(rev).ok()?).ok()?;
+        let mut options = git2::DescribeOptions::new();
+        options.describe_tags().max_candidates_tags(1000);
+        let mut format = git2::DescribeFormatOptions::new();
+        format.abbreviated_size(0);
+        let described = commit.as_object().describe(&options).ok()?;
+        described.format(Some(&format)).ok()
+    }
+
+    /// Commits reachable from `tip` but not from `hidden`, newest first
+    fn commits_between(&self, hidden: Option<git2::Oid>, tip: git2::Oid, limit: usize) -> Result<Vec<CommitInfo>> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        revwalk.push(tip)?;
+        if let Some(hidden) = hidden {
+            revwalk.hide(hidden)?;
+        }
+
+        let mut commits = Vec::new();
+        for oid in revwalk.take(limit) {
+            commits.push(self.commit_summary(&self.repo.find_commit(oid?)?));
+        }
+        Ok(commits)
+    }
+}
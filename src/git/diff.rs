@@ -0,0 +1,227 @@
+use anyhow::Result;
+use git2::{Delta, Diff, DiffFormat};
+use std::cell::{Cell, RefCell};
+use crate::models::{ChangeStatus, DiffInfo, DiffLine, DiffStat, FileChange, FileStat, Hunk, LineKind};
+
+fn file_path(file: git2::DiffFile) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().into_owned())
+}
+
+fn change_status(delta: Delta) -> ChangeStatus {
+    match delta {
+        Delta::Added | Delta::Untracked => ChangeStatus::Added,
+        Delta::Deleted => ChangeStatus::Deleted,
+        Delta::Renamed => ChangeStatus::Renamed,
+        Delta::Copied => ChangeStatus::Copied,
+        Delta::Typechange => ChangeStatus::TypeChanged,
+        _ => ChangeStatus::Modified,
+    }
+}
+
+/// Files bigger than this on either side of a change have their diff cut
+/// short to `LARGE_FILE_LINES` lines, unless the full diff is asked for
+pub const LARGE_FILE_SIZE: u64 = 128 * 1024;
+pub const LARGE_FILE_LINES: usize = 1000;
+
+/// A byte count the way people write file sizes, e.g. "12 KB" or "1.5 MB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || size >= 10.0 {
+        format!("{:.0} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// What's shown for a binary file in place of its lines, with its size
+/// before and after
+pub fn binary_summary(status: ChangeStatus, old_size: u64, new_size: u64) -> String {
+    match status {
+        ChangeStatus::Added => format!("Binary file added ({})", format_size(new_size)),
+        ChangeStatus::Deleted => format!("Binary file deleted ({})", format_size(old_size)),
+        _ => format!("Binary file changed ({} → {})", format_size(old_size), format_size(new_size)),
+    }
+}
+
+/// The line ending a file's diff that was cut short
+pub fn omitted_note(lines: usize) -> String {
+    format!("... {} more lines of this file's diff not shown", lines)
+}
+
+/// How many lines of the delta's diff to keep, if it's cut short
+fn line_limit(delta: &git2::DiffDelta, cut_large: bool) -> Option<usize> {
+    let large = delta.old_file().size().max(delta.new_file().size()) > LARGE_FILE_SIZE;
+    (cut_large && large).then_some(LARGE_FILE_LINES)
+}
+
+/// Builds the structured file/hunk/line model from git2's delta, hunk and
+/// line callbacks.
+pub fn diff_info(diff: &Diff) -> Result<DiffInfo> {
+    build_info(diff, false)
+}
+
+/// Like `diff_info`, but keeping only the first lines of large files
+pub fn cut_diff_info(diff: &Diff) -> Result<DiffInfo> {
+    build_info(diff, true)
+}
+
+fn build_info(diff: &Diff, cut_large: bool) -> Result<DiffInfo> {
+    // Every callback needs to reach the file list, hence the RefCell
+    let files: RefCell<Vec<FileChange>> = RefCell::new(Vec::new());
+    // Lines kept for the current file, and how many it may have
+    let kept = Cell::new(0);
+    let limit = Cell::new(None);
+
+    diff.foreach(
+        &mut |delta, _| {
+            let status = change_status(delta.status());
+            kept.set(0);
+            limit.set(line_limit(&delta, cut_large));
+            files.borrow_mut().push(FileChange {
+                // Added files have no old side and deleted files no new side
+                old_path: file_path(delta.old_file()).filter(|_| status != ChangeStatus::Added),
+                new_path: file_path(delta.new_file()).filter(|_| status != ChangeStatus::Deleted),
+                status,
+                binary: delta.flags().is_binary(),
+                old_size: delta.old_file().size(),
+                new_size: delta.new_file().size(),
+                hunks: Vec::new(),
+                omitted_lines: 0,
+            });
+            true
+        },
+        Some(&mut |_, _| {
+            if let Some(file) = files.borrow_mut().last_mut() {
+                file.binary = true;
+            }
+            true
+        }),
+        Some(&mut |_, hunk| {
+            if limit.get().is_some_and(|limit| kept.get() >= limit) {
+                return true;
+            }
+            if let Some(file) = files.borrow_mut().last_mut() {
+                file.hunks.push(Hunk {
+                    header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                    lines: Vec::new(),
+                });
+            }
+            true
+        }),
+        Some(&mut |_, _, line| {
+            let kind = match line.origin() {
+                '+' => LineKind::Added,
+                '-' => LineKind::Removed,
+                ' ' => LineKind::Context,
+                _ => return true,
+            };
+            let mut files = files.borrow_mut();
+            if limit.get().is_some_and(|limit| kept.get() >= limit) {
+                if let Some(file) = files.last_mut() {
+                    file.omitted_lines += 1;
+                }
+                return true;
+            }
+            kept.set(kept.get() + 1);
+            if let Some(hunk) = files.last_mut().and_then(|file| file.hunks.last_mut()) {
+                hunk.lines.push(DiffLine {
+                    kind,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    content: String::from_utf8_lossy(line.content()).trim_end_matches('\n').to_string(),
+                });
+            }
+            true
+        }),
+    )?;
+
+    Ok(DiffInfo { files: files.into_inner() })
+}
+
+/// Counts each file's added and removed lines in one pass over the diff,
+/// leaving out the lines themselves, and totals them up
+pub fn diff_stat(diff: &Diff) -> Result<DiffStat> {
+    let files: RefCell<Vec<FileStat>> = RefCell::new(Vec::new());
+    diff.foreach(
+        &mut |delta, _| {
+            let status = change_status(delta.status());
+            files.borrow_mut().push(FileStat {
+                old_path: file_path(delta.old_file()).filter(|_| status != ChangeStatus::Added),
+                new_path: file_path(delta.new_file()).filter(|_| status != ChangeStatus::Deleted),
+                status,
+                binary: delta.flags().is_binary(),
+                insertions: 0,
+                deletions: 0,
+            });
+            true
+        },
+        // Whether a file is binary is only known once its content's been read
+        Some(&mut |_, _| {
+            if let Some(file) = files.borrow_mut().last_mut() {
+                file.binary = true;
+            }
+            true
+        }),
+        None,
+        Some(&mut |_, _, line| {
+            if let Some(file) = files.borrow_mut().last_mut() {
+                match line.origin() {
+                    '+' => file.insertions += 1,
+                    '-' => file.deletions += 1,
+                    _ => {},
+                }
+            }
+            true
+        }),
+    )?;
+    let files = files.into_inner();
+    Ok(DiffStat {
+        insertions: files.iter().map(|file| file.insertions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+        ..Default::default()
+    })
+}
+
+/// Renders the diff as unified patch text, exactly as `git show` would
+/// print it, so it can be copied and applied
+pub fn patch_text(diff: &Diff) -> Result<String> {
+    build_patch(diff, false)
+}
+
+/// Like `patch_text`, but keeping only the first lines of large files. The
+/// lines left out are counted in `cut_diff_info`, for the views to say so.
+pub fn cut_patch_text(diff: &Diff) -> Result<String> {
+    build_patch(diff, true)
+}
+
+fn build_patch(diff: &Diff, cut_large: bool) -> Result<String> {
+    let mut patch = String::new();
+    // Lines kept of the current file, and how many it may have
+    let (mut kept, mut limit) = (0, None);
+    diff.print(DiffFormat::Patch, |delta, _, line| {
+        match line.origin() {
+            'F' => (kept, limit) = (0, line_limit(&delta, cut_large)),
+            _ if limit.is_some_and(|limit| kept >= limit) => return true,
+            '+' | '-' | ' ' => kept += 1,
+            _ => {},
+        }
+        // Content lines carry their +/-/space marker separately from the text
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        let content = String::from_utf8_lossy(line.content());
+        patch.push_str(&content);
+        if !content.ends_with('\n') {
+            patch.push('\n');
+        }
+        true
+    })?;
+    Ok(patch)
+}
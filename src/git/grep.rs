@@ -0,0 +1,67 @@
+use anyhow::Result;
+use regex::Regex;
+use std::path::Path;
+use std::thread;
+use super::GitManager;
+use crate::models::GrepMatch;
+
+// Enough to browse, without holding on to every line of a huge tree
+pub const MAX_GREP_MATCHES: usize = 10_000;
+
+/// Lines of the blobs in `files` matching `pattern`, read through a
+/// repository handle of this thread's own
+fn grep_blobs(repo_path: &Path, files: &[(String, git2::Oid)], pattern: &Regex) -> Result<Vec<GrepMatch>> {
+    let repo = git2::Repository::open(repo_path)?;
+    let mut matches = Vec::new();
+    for (path, oid) in files {
+        let blob = repo.find_blob(*oid)?;
+        if blob.is_binary() {
+            continue;
+        }
+        let content = String::from_utf8_lossy(blob.content());
+        for (i, line) in content.lines().enumerate() {
+            if pattern.is_match(line) {
+                matches.push(GrepMatch { path: path.clone(), line: i + 1, text: line.to_string() });
+            }
+        }
+        if matches.len() >= MAX_GREP_MATCHES {
+            break;
+        }
+    }
+    Ok(matches)
+}
+
+impl GitManager {
+    /// Every line matching `pattern` in the files as of `commit`, by path.
+    /// The blobs are split between a thread per core.
+    pub fn grep(&self, commit: &str, pattern: &Regex) -> Result<Vec<GrepMatch>> {
+        let tree = self.repo.find_commit(git2::Oid::from_str(commit)?)?.tree()?;
+        let mut files = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                let name = String::from_utf8_lossy(entry.name_bytes());
+                files.push((format!("{}{}", dir, name), entry.id()));
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        let chunk = files.len().div_ceil(threads).max(1);
+        let repo_path = self.repo.path();
+        let results: Vec<Result<Vec<GrepMatch>>> = thread::scope(|scope| {
+            let workers: Vec<_> = files
+                .chunks(chunk)
+                .map(|files| scope.spawn(move || grep_blobs(repo_path, files, pattern)))
+                .collect();
+            workers.into_iter().map(|worker| worker.join().expect("grep thread panicked")).collect()
+        });
+
+        let mut matches = Vec::new();
+        for result in results {
+            matches.extend(result?);
+        }
+        matches.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+        matches.truncate(MAX_GREP_MATCHES);
+        Ok(matches)
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use super::pickaxe::PickaxeQuery;
+use super::{Cancelled, Generation, GitManager, Visit};
+use crate::models::FilterSet;
+
+// How often the worker reports how far it has got
+const PROGRESS_EVERY: usize = 200;
+
+pub enum SearchEvent {
+    Found(String),
+    // Commits looked at so far
+    Progress(usize),
+    Done(usize),
+    Failed(String),
+}
+
+/// Looks through a branch's whole history on a worker thread for commits
+/// passing a test, reporting each one as it's found, in the order the
+/// history is listed. The search stops when this is dropped.
+pub struct HistorySearch {
+    pub branch: String,
+    pub query: String,
+    // The file followed and the filters the history was walked with
+    pub path: Option<String>,
+    pub filters: FilterSet,
+    events: Receiver<SearchEvent>,
+    cancel: Generation,
+}
+
+/// Runs `search` on a worker thread, sending what it finds to `events`
+/// and then how it ended
+fn run<F>(events: Sender<SearchEvent>, search: F)
+where
+    F: FnOnce(&Sender<SearchEvent>) -> Result<usize> + Send + 'static,
+{
+    thread::spawn(move || {
+        let event = match search(&events) {
+            Ok(scanned) => SearchEvent::Done(scanned),
+            // Nobody is listening any more
+            Err(e) if e.is::<Cancelled>() => return,
+            Err(e) => SearchEvent::Failed(e.to_string()),
+        };
+        let _ = events.send(event);
+    });
+}
+
+impl HistorySearch {
+    /// Finds the commits whose diffs match a pickaxe query. Like git log,
+    /// merges aren't searched.
+    pub fn pickaxe(repo_path: PathBuf, branch: &str, input: &str, query: PickaxeQuery) -> Self {
+        Self::spawn(repo_path, branch, input, move |git_manager, commit| {
+            Ok(commit.parent_count() < 2 && git_manager.pickaxe_matches(commit, &query)?)
+        })
+    }
+
+    /// Finds the commits whose hash, message or author match the regex,
+    /// among those the commit list would show: the ones passing `filters`,
+    /// or changing `path` when a file's history is followed
+    pub fn messages(repo_path: PathBuf, branch: &str, path: Option<&str>, filters: &FilterSet, input: &str, pattern: Regex) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let cancel = Generation::default();
+        let token = cancel.token();
+        let (tip, followed, walk_filters) = (branch.to_string(), path.map(str::to_string), filters.clone());
+
+        run(event_tx, move |events| {
+            let mut git_manager = GitManager::new(&repo_path)?;
+            let progress = events.clone();
+            git_manager.on_walk_progress(Some(Box::new(move |scanned, _| {
+                let _ = progress.send(SearchEvent::Progress(scanned));
+            })));
+            let tip = git_manager.branch_tip(&tip)?;
+            git_manager.walk_history(tip, followed.as_deref(), &walk_filters, &token, |commit, _| {
+                let summary = git_manager.commit_summary(commit);
+                if !summary.search_fields().iter().any(|field| pattern.is_match(field)) {
+                    return Ok(Visit::Skipped);
+                }
+                let _ = events.send(SearchEvent::Found(summary.hash));
+                Ok(Visit::Taken)
+            })
+        });
+
+        HistorySearch { branch: branch.to_string(), query: input.to_string(), path: path.map(str::to_string), filters: filters.clone(), events: event_rx, cancel }
+    }
+
+    fn spawn<F>(repo_path: PathBuf, branch: &str, input: &str, test: F) -> Self
+    where
+        F: Fn(&GitManager, &git2::Commit) -> Result<bool> + Send + 'static,
+    {
+        let (event_tx, event_rx) = mpsc::channel();
+        let cancel = Generation::default();
+        let token = cancel.token();
+        let tip = branch.to_string();
+
+        run(event_tx, move |events| {
+            let git_manager = GitManager::new(&repo_path)?;
+            let mut revwalk = git_manager.repo.revwalk()?;
+            revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+            revwalk.push(git_manager.branch_tip(&tip)?)?;
+
+            let mut scanned = 0;
+            for oid in revwalk {
+                token.check()?;
+                let commit = git_manager.repo.find_commit(oid?)?;
+                scanned += 1;
+                if test(&git_manager, &commit)? {
+                    let _ = events.send(SearchEvent::Found(commit.id().to_string()));
+                }
+                if scanned % PROGRESS_EVERY == 0 {
+                    let _ = events.send(SearchEvent::Progress(scanned));
+                }
+            }
+            Ok(scanned)
+        });
+
+        HistorySearch { branch: branch.to_string(), query: input.to_string(), path: None, filters: FilterSet::default(), events: event_rx, cancel }
+    }
+
+    /// Returns the next event, if any, without blocking
+    pub fn try_recv(&self) -> Option<SearchEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+impl Drop for HistorySearch {
+    fn drop(&mut self) {
+        self.cancel.advance();
+    }
+}
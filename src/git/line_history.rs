@@ -0,0 +1,158 @@
+use anyhow::Result;
+use std::path::Path;
+use super::{entry_id, GitManager};
+use crate::models::{DiffLine, Hunk, LineChange, LineKind};
+
+/// A hunk's old start and length, then its new start and length
+type Span = (usize, usize, usize, usize);
+
+/// Whether the hunk changed any of the lines `start` to `end` of its new side
+fn touches((_, _, new_start, new_lines): Span, (start, end): (usize, usize)) -> bool {
+    if new_lines == 0 {
+        // Lines removed from between two lines of the range
+        new_start >= start && new_start < end
+    } else {
+        new_start <= end && new_start + new_lines > start
+    }
+}
+
+/// Where line `line` of the new side was on the old side. A changed line
+/// maps to the first or last line of what it replaced.
+fn map_line(hunks: &[Span], line: usize, first: bool) -> usize {
+    let mut shift = 0i64;
+    for &(old_start, old_lines, new_start, new_lines) in hunks {
+        if new_lines > 0 && (new_start..new_start + new_lines).contains(&line) {
+            // With nothing removed, the old start is the line before the addition
+            return match (old_lines, first) {
+                (0, true) => old_start + 1,
+                (0, false) => old_start,
+                (_, true) => old_start,
+                (_, false) => old_start + old_lines - 1,
+            };
+        }
+        let before = if new_lines > 0 { new_start + new_lines <= line } else { new_start < line };
+        if !before {
+            break;
+        }
+        shift += old_lines as i64 - new_lines as i64;
+    }
+    (line as i64 + shift) as usize
+}
+
+/// Where the lines `range` of the new side were on the old side, or None if
+/// they were all added
+fn map_range(hunks: &[Span], (start, end): (usize, usize)) -> Option<(usize, usize)> {
+    let (old_start, old_end) = (map_line(hunks, start, true), map_line(hunks, end, false));
+    (old_start <= old_end).then_some((old_start, old_end))
+}
+
+/// The header of a hunk cut down to `lines`, as git would write it
+fn hunk_header((old_start, _, new_start, _): Span, lines: &[DiffLine]) -> String {
+    let side = |kind: LineKind, start: usize| {
+        let numbers: Vec<u32> = lines
+            .iter()
+            .filter(|line| line.kind == kind)
+            .filter_map(|line| if kind == LineKind::Added { line.new_lineno } else { line.old_lineno })
+            .collect();
+        match numbers.as_slice() {
+            [] => format!("{},0", start),
+            [only] => only.to_string(),
+            [first, ..] => format!("{},{}", first, numbers.len()),
+        }
+    };
+    format!("@@ -{} +{} @@", side(LineKind::Removed, old_start), side(LineKind::Added, new_start))
+}
+
+impl GitManager {
+    /// The commits that changed lines `start` to `end`, counted from 1, of
+    /// `path` as of `commit`, newest first, like `git log -L`. The lines are
+    /// followed back along first parents and through renames until they
+    /// were added, and each commit comes with only the hunks touching them.
+    pub fn line_history(&self, commit: &str, path: &str, start: usize, end: usize) -> Result<Vec<LineChange>> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(git2::Oid::from_str(commit)?)?;
+        revwalk.simplify_first_parent()?;
+
+        let (mut path, mut range) = (path.to_string(), (start, end));
+        let mut changes = Vec::new();
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            let blob = match entry_id(&commit.tree()?, &path) {
+                Some(blob) => self.repo.find_blob(blob)?,
+                None => break,
+            };
+            let parent_tree = match commit.parents().next() {
+                Some(parent) => Some(parent.tree()?),
+                None => None,
+            };
+            let parent_blob = parent_tree.as_ref().and_then(|tree| entry_id(tree, &path));
+            if parent_blob == Some(blob.id()) {
+                continue;
+            }
+
+            // A file that appears out of nowhere may have been renamed
+            let old_path = match (&parent_tree, parent_blob) {
+                (Some(_), Some(_)) => Some(path.clone()),
+                (Some(_), None) => self.rename_source(&commit, &path)?,
+                (None, _) => None,
+            };
+            let old_blob = match (&parent_tree, &old_path) {
+                (Some(tree), Some(old_path)) => entry_id(tree, old_path).map(|id| self.repo.find_blob(id)).transpose()?,
+                _ => None,
+            };
+            let old_content = old_blob.as_ref().map(|blob| blob.content()).unwrap_or_default();
+
+            // Without context, hunks hold only the lines that changed
+            let mut opts = git2::DiffOptions::new();
+            opts.context_lines(0);
+            let patch = git2::Patch::from_buffers(
+                old_content,
+                old_path.as_deref().map(Path::new),
+                blob.content(),
+                Some(Path::new(&path)),
+                Some(&mut opts),
+            )?;
+
+            let mut spans = Vec::new();
+            for index in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(index)?;
+                spans.push((hunk.old_start() as usize, hunk.old_lines() as usize, hunk.new_start() as usize, hunk.new_lines() as usize));
+            }
+            let old_range = map_range(&spans, range);
+
+            // Only the lines in the range are kept of the hunks touching it
+            let within = |number: Option<u32>, range: Option<(usize, usize)>| {
+                number.zip(range).is_some_and(|(number, (start, end))| (start..=end).contains(&(number as usize)))
+            };
+            let mut hunks = Vec::new();
+            for (index, &span) in spans.iter().enumerate().filter(|&(_, &span)| touches(span, range)) {
+                let mut lines = Vec::new();
+                for line in 0..patch.num_lines_in_hunk(index)? {
+                    let line = patch.line_in_hunk(index, line)?;
+                    let kind = match line.origin() {
+                        '+' if within(line.new_lineno(), Some(range)) => LineKind::Added,
+                        '-' if within(line.old_lineno(), old_range) => LineKind::Removed,
+                        _ => continue,
+                    };
+                    lines.push(DiffLine {
+                        kind,
+                        old_lineno: line.old_lineno(),
+                        new_lineno: line.new_lineno(),
+                        content: String::from_utf8_lossy(line.content()).trim_end_matches('\n').to_string(),
+                    });
+                }
+                hunks.push(Hunk { header: hunk_header(span, &lines), lines });
+            }
+            if !hunks.is_empty() {
+                changes.push(LineChange { commit: self.commit_summary(&commit), path: path.clone(), range, hunks });
+            }
+
+            match (old_path, old_range) {
+                (Some(old_path), Some(old_range)) => (path, range) = (old_path, old_range),
+                // The lines were added here
+                _ => break,
+            }
+        }
+        Ok(changes)
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+use std::sync::Mutex;
+#[cfg(not(feature = "tokio"))]
+use std::thread;
+use crate::git::{CancelToken, Cancelled, Generation, GitManager};
+use crate::models::{CommitInfo, FilterSet};
+
+struct LoadRequest {
+    branch: String,
+    // Only load commits touching this file
+    path: Option<String>,
+    filters: FilterSet,
+    offset: usize,
+    limit: usize,
+    // Drop the worker's cache first so the page is read afresh
+    refresh: bool,
+    cancel: CancelToken,
+}
+
+pub enum LoadEvent {
+    Commits {
+        branch: String,
+        path: Option<String>,
+        filters: FilterSet,
+        offset: usize,
+        commits: Vec<CommitInfo>,
+    },
+    // How far a walk has got before its page is ready: the commits looked
+    // at so far and how many of those made it into the page
+    Progress {
+        branch: String,
+        path: Option<String>,
+        filters: FilterSet,
+        scanned: usize,
+        found: usize,
+    },
+    Error {
+        branch: String,
+        path: Option<String>,
+        filters: FilterSet,
+        message: String,
+    },
+}
+
+/// Reads the requested page, or nothing if the request was cancelled
+/// before or while it ran. Progress is sent to `events` as the walk goes.
+fn load(git_manager: &mut GitManager, request: LoadRequest, events: &Sender<LoadEvent>) -> Option<LoadEvent> {
+    if request.cancel.is_cancelled() {
+        return None;
+    }
+    if request.refresh {
+        git_manager.clear_cache();
+    }
+    let (events, cancel) = (events.clone(), request.cancel.clone());
+    let (branch, path, filters) = (request.branch.clone(), request.path.clone(), request.filters.clone());
+    git_manager.on_walk_progress(Some(Box::new(move |scanned, found| {
+        if !cancel.is_cancelled() {
+            let (branch, path, filters) = (branch.clone(), path.clone(), filters.clone());
+            let _ = events.send(LoadEvent::Progress { branch, path, filters, scanned, found });
+        }
+    })));
+    let page = git_manager.get_commits_page(&request.branch, request.path.as_deref(), &request.filters, request.offset, request.limit, &request.cancel);
+    git_manager.on_walk_progress(None);
+    let event = match page {
+        Ok(commits) => LoadEvent::Commits {
+            branch: request.branch,
+            path: request.path,
+            filters: request.filters,
+            offset: request.offset,
+            commits,
+        },
+        Err(e) if e.is::<Cancelled>() => return None,
+        Err(e) => LoadEvent::Error {
+            branch: request.branch,
+            path: request.path,
+            filters: request.filters,
+            message: e.to_string(),
+        },
+    };
+    Some(event)
+}
+
+/// Runs revwalks and diff generation off the UI thread so it keeps drawing
+/// while history is loaded. Each load opens its own repository handle
+/// because `git2::Repository` can't be shared across threads.
+pub struct CommitLoader {
+    backend: Backend,
+    events: Receiver<LoadEvent>,
+    // The branch, file and filters last asked for. Asking for another list
+    // starts a new generation, which cancels every load for the old one.
+    view: Mutex<Option<(String, Option<String>, FilterSet)>>,
+    generation: Generation,
+}
+
+impl CommitLoader {
+    pub fn spawn(repo_path: PathBuf) -> Result<Self> {
+        // Open once up front so a broken repository is reported immediately
+        GitManager::new(&repo_path)?;
+
+        let (event_tx, event_rx) = mpsc::channel();
+        Ok(CommitLoader {
+            backend: Backend::spawn(repo_path, event_tx)?,
+            events: event_rx,
+            view: Mutex::new(None),
+            generation: Generation::default(),
+        })
+    }
+
+    pub fn request(&self, branch: &str, path: Option<&str>, filters: &FilterSet, offset: usize, limit: usize) {
+        self.send(branch, path, filters, offset, limit, false);
+    }
+
+    /// Like `request`, but bypasses anything the worker has cached
+    pub fn refresh(&self, branch: &str, path: Option<&str>, filters: &FilterSet, offset: usize, limit: usize) {
+        self.send(branch, path, filters, offset, limit, true);
+    }
+
+    fn send(&self, branch: &str, path: Option<&str>, filters: &FilterSet, offset: usize, limit: usize, refresh: bool) {
+        let view = (branch.to_string(), path.map(|p| p.to_string()), filters.clone());
+        let mut last = self.view.lock().unwrap();
+        // The next page of the same list carries on in the same generation
+        let cancel = if refresh || offset == 0 || last.as_ref() != Some(&view) {
+            self.generation.advance()
+        } else {
+            self.generation.token()
+        };
+        *last = Some(view.clone());
+        let (branch, path, filters) = view;
+        self.backend.send(LoadRequest { branch, path, filters, offset, limit, refresh, cancel });
+    }
+
+    /// Returns the next finished load, if any, without blocking
+    pub fn try_recv(&self) -> Option<LoadEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+/// One worker thread taking requests in turn. Cancelled requests are
+/// skipped and a cancelled walk stops early, so a load for another branch
+/// only waits for the commit the old one is on.
+#[cfg(not(feature = "tokio"))]
+struct Backend {
+    requests: Sender<LoadRequest>,
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Backend {
+    fn spawn(repo_path: PathBuf, events: Sender<LoadEvent>) -> Result<Self> {
+        let (request_tx, request_rx) = mpsc::channel::<LoadRequest>();
+        thread::spawn(move || {
+            let mut git_manager = match GitManager::new(&repo_path) {
+                Ok(manager) => manager,
+                Err(_) => return,
+            };
+            for request in request_rx {
+                let event = match load(&mut git_manager, request, &events) {
+                    Some(event) => event,
+                    None => continue,
+                };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Backend { requests: request_tx })
+    }
+
+    fn send(&self, request: LoadRequest) {
+        let _ = self.requests.send(request);
+    }
+}
+
+/// Each load is a blocking task on a tokio runtime, so a load for another
+/// branch, file or filter starts straight away beside any cancelled ones
+/// still winding down.
+#[cfg(feature = "tokio")]
+struct Backend {
+    runtime: tokio::runtime::Runtime,
+    repo_path: PathBuf,
+    events: Sender<LoadEvent>,
+    // Repository handles between loads, each keeping its cache
+    idle: Arc<Mutex<Vec<GitManager>>>,
+}
+
+#[cfg(feature = "tokio")]
+impl Backend {
+    fn spawn(repo_path: PathBuf, events: Sender<LoadEvent>) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(4)
+            .thread_name("commit-loader")
+            .build()?;
+        Ok(Backend { runtime, repo_path, events, idle: Arc::new(Mutex::new(Vec::new())) })
+    }
+
+    fn send(&self, request: LoadRequest) {
+        if request.refresh {
+            // Every idle handle's cache is as stale as the one this load clears
+            self.idle.lock().unwrap().clear();
+        }
+        let (repo_path, events, idle) = (self.repo_path.clone(), self.events.clone(), self.idle.clone());
+        self.runtime.spawn_blocking(move || {
+            let pooled = idle.lock().unwrap().pop();
+            let mut git_manager = match pooled {
+                Some(manager) => manager,
+                None => match GitManager::new(&repo_path) {
+                    Ok(manager) => manager,
+                    Err(_) => return,
+                },
+            };
+            let event = load(&mut git_manager, request, &events);
+            idle.lock().unwrap().push(git_manager);
+            if let Some(event) = event {
+                let _ = events.send(event);
+            }
+        });
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use super::{diff, GitManager};
+use crate::models::{MergeDiff, MergeView};
+
+impl GitManager {
+    /// The merge commit `hash` diffed against one of its parents, or the
+    /// combined view: only the files that differ from every parent, which
+    /// are the ones the merge itself had to settle
+    pub fn merge_diff(&self, hash: &str, view: MergeView) -> Result<MergeDiff> {
+        let commit = self.repo.find_commit(git2::Oid::from_str(hash)?)?;
+        let tree = commit.tree()?;
+
+        let mut changes = match view {
+            MergeView::Parent(nth) => {
+                let parent = commit.parent(nth)?.tree()?;
+                self.repo.diff_tree_to_tree(Some(&parent), Some(&tree), Some(&mut self.diff_options()))?
+            },
+            MergeView::Combined => {
+                let mut common: Option<HashSet<String>> = None;
+                for parent in commit.parents() {
+                    let changes = self.repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&tree), None)?;
+                    let paths: HashSet<String> = changes
+                        .deltas()
+                        .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .collect();
+                    common = Some(match common {
+                        Some(common) => common.intersection(&paths).cloned().collect(),
+                        None => paths,
+                    });
+                }
+                let common = common.unwrap_or_default();
+                // No pathspec at all would match every file
+                if common.is_empty() {
+                    return Ok(MergeDiff {
+                        hash: hash.to_string(),
+                        view,
+                        patch: String::new(),
+                        info: Default::default(),
+                    });
+                }
+
+                let mut opts = self.diff_options();
+                opts.disable_pathspec_match(true);
+                for path in &common {
+                    opts.pathspec(path);
+                }
+                let parent = commit.parent(0)?.tree()?;
+                let changes = self.repo.diff_tree_to_tree(Some(&parent), Some(&tree), Some(&mut opts))?;
+                changes
+            },
+        };
+        self.find_renames(&mut changes)?;
+
+        Ok(MergeDiff {
+            hash: hash.to_string(),
+            view,
+            patch: diff::patch_text(&changes)?,
+            info: diff::diff_info(&changes)?,
+        })
+    }
+}
@@ -1,87 +1,826 @@
-use anyhow::Result;
-use git2::{Repository, BranchType};
-use chrono::{Local, TimeZone};
-use std::path::Path;
-use crate::models::CommitInfo;
-
-pub struct GitManager {
-    repo: Repository,
-}
-
-impl GitManager {
-    pub fn new(path: &Path) -> Result<Self> {
-        let repo = Repository::open(path)?;
-        Ok(GitManager { repo })
-    }
-
-    pub fn branch_exists(&self, branch_name: &str) -> bool {
-        self.repo.find_branch(branch_name, BranchType::Local).is_ok()
-    }
-
-    pub fn get_commits(&self, branch: &str) -> Result<Vec<CommitInfo>> {
-        let branch = self.repo.find_branch(branch, BranchType::Local)?;
-        let commit = branch.get().peel_to_commit()?;
-        
-        let mut commits = Vec::new();
-        let mut revwalk = self.repo.revwalk()?;
-        revwalk.push(commit.id())?;
-
-        for oid in revwalk {
-            let oid = oid?;
-            let commit = self.repo.find_commit(oid)?;
-            
-            let author = commit.author();
-            let name = author.name().unwrap_or("Unknown");
-            let email = author.email().unwrap_or("unknown@email.com");
-            
-            let date = match Local.timestamp_opt(commit.time().seconds(), 0) {
-                chrono::LocalResult::Single(dt) => dt,
-                chrono::LocalResult::Ambiguous(_, _) => Local::now(),
-                chrono::LocalResult::None => Local::now(),
-            };
-            let date_str = date.format("%Y-%m-%d %H:%M:%S").to_string();
-
-            let diff = if let Ok(parent) = commit.parent(0) {
-                let mut diff_opts = git2::DiffOptions::new();
-                let diff = self.repo.diff_tree_to_tree(
-                    Some(&parent.tree()?),
-                    Some(&commit.tree()?),
-                    Some(&mut diff_opts),
-                )?;
-                
-                let mut diff_str = String::new();
-                diff.print(git2::DiffFormat::Patch, |_, _, line| {
-                    diff_str.push_str(&format!("{}\n", String::from_utf8_lossy(line.content())));
-                    true
-                })?;
-                
-                Some(diff_str)
-            } else {
-                None
-            };
-
-            commits.push(CommitInfo {
-                hash: oid.to_string(),
-                message: commit.message().unwrap_or("").to_string(),
-                author: format!("{} <{}>", name, email),
-                date: date_str,
-                diff,
-            });
-        }
-
-        Ok(commits)
-    }
-
-    pub fn get_branches(&self) -> Result<Vec<String>> {
-        let mut branches = Vec::new();
-        
-        for branch in self.repo.branches(Some(git2::BranchType::Local))? {
-            let (branch, _) = branch?;
-            if let Some(name) = branch.name()? {
-                branches.push(name.to_string());
-            }
-        }
-        
-        Ok(branches)
-    }
-} 
\ No newline at end of file
+mod bisect;
+mod branch;
+mod cancel;
+mod checkout;
+mod compare;
+mod diff;
+mod grep;
+pub mod history_search;
+mod line_history;
+pub mod loader;
+mod merge;
+mod patch;
+mod pick;
+pub mod pickaxe;
+mod reflog;
+pub mod remote;
+mod stash;
+pub mod stats;
+mod status;
+mod tag;
+pub mod watcher;
+
+use anyhow::Result;
+use git2::{Repository, BranchType, Sort};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::cache::Cache;
+pub use self::cancel::{CancelToken, Cancelled, Generation};
+pub use self::diff::{binary_summary, format_size, omitted_note, LARGE_FILE_LINES, LARGE_FILE_SIZE};
+pub use self::grep::MAX_GREP_MATCHES;
+pub use self::pick::PickOutcome;
+use crate::models::{AuthorInfo, BlameLine, BranchInfo, CommitDiff, CommitInfo, DiffCacheStats, DiffContext, DiffStat, FilterSet, HeadState, RefKind, RefLabel, TagInfo, Whitespace};
+use crate::pathspec::PathFilter;
+
+/// What a walk through history did with a commit it was handed
+pub enum Visit {
+    // Passed over, as when it's on an earlier page
+    Skipped,
+    Taken,
+    // Taken, and nothing more is wanted
+    Done,
+}
+
+/// Number of commits fetched per page while scrolling through history
+pub const COMMIT_PAGE_SIZE: usize = 200;
+
+/// How many commits a filtered walk gets through between progress reports
+const WALK_PROGRESS_BATCH: usize = 1000;
+
+/// How alike two files must be, in percent, to count as a rename or copy
+/// unless set otherwise; the same as git's own default
+pub const DEFAULT_SIMILARITY: u16 = 50;
+
+pub struct GitManager {
+    repo: Repository,
+    cache: Cache,
+    // Told how many commits a walk has looked at and how many it kept
+    walk_progress: Option<Box<dyn Fn(usize, usize) + Send>>,
+    // How alike files must be to be paired up as renames and copies in
+    // diffs, or None to not look for them
+    similarity: Option<u16>,
+    whitespace: Whitespace,
+    context: DiffContext,
+}
+
+impl GitManager {
+    /// Opens the repository containing `path`, searching parent directories
+    /// the same way `git` itself does
+    pub fn new(path: &Path) -> Result<Self> {
+        let repo = Repository::discover(path)?;
+        Ok(GitManager { repo, cache: Cache::new(), walk_progress: None, similarity: Some(DEFAULT_SIMILARITY), whitespace: Whitespace::default(), context: DiffContext::default() })
+    }
+
+    /// Top-level directory of the repository, wherever it was opened from
+    pub fn root(&self) -> PathBuf {
+        // Rebuilding from components drops the trailing slash git2 leaves on
+        self.repo
+            .workdir()
+            .unwrap_or_else(|| self.repo.path())
+            .components()
+            .collect()
+    }
+
+    /// Whether the repository has no working tree, as on a server
+    pub fn is_bare(&self) -> bool {
+        self.repo.is_bare()
+    }
+
+    /// The commits a shallow clone's history stops at, whose parents
+    /// weren't fetched; empty for a full clone
+    pub fn shallow_boundary(&self) -> Result<HashSet<String>> {
+        if !self.repo.is_shallow() {
+            return Ok(HashSet::new());
+        }
+        let shallow = std::fs::read_to_string(self.repo.path().join("shallow"))?;
+        Ok(shallow.lines().map(|line| line.trim().to_string()).filter(|hash| !hash.is_empty()).collect())
+    }
+
+    /// The `.git` directory, or the repository itself if it is bare
+    pub fn git_dir(&self) -> PathBuf {
+        self.repo.path().to_path_buf()
+    }
+
+    /// The commit `branch` currently points at, if it resolves
+    pub fn tip(&self, branch: &str) -> Option<String> {
+        self.branch_tip(branch).ok().map(|oid| oid.to_string())
+    }
+
+    pub fn head_state(&self) -> Result<HeadState> {
+        match self.repo.head() {
+            Ok(head) if head.is_branch() => Ok(HeadState::Branch(head.shorthand().unwrap_or("HEAD").to_string())),
+            Ok(head) => {
+                let id = head.peel_to_commit()?.id().to_string();
+                Ok(HeadState::Detached(id[..7.min(id.len())].to_string()))
+            },
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                // HEAD names a branch that doesn't exist yet, so read the name from the symbolic ref
+                let head = self.repo.find_reference("HEAD")?;
+                let target = head.symbolic_target().unwrap_or("HEAD");
+                Ok(HeadState::Unborn(target.trim_start_matches("refs/heads/").to_string()))
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Resolves a local branch, a remote-tracking branch such as
+    /// `origin/main`, a tag, or any other revision like `HEAD`, in that
+    /// order, to the commit it points at
+    fn branch_tip(&self, branch_name: &str) -> Result<git2::Oid> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, BranchType::Local)
+            .or_else(|_| self.repo.find_branch(branch_name, BranchType::Remote));
+        let commit = match branch {
+            Ok(branch) => branch.get().peel_to_commit()?,
+            Err(_) => match self.repo.find_reference(&format!("refs/tags/{}", branch_name)) {
+                Ok(tag) => tag.peel_to_commit()?,
+                Err(_) => self.repo.revparse_single(branch_name)?.peel_to_commit()?,
+            },
+        };
+        Ok(commit.id())
+    }
+
+    /// Drops everything cached so the next calls read the repository afresh
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Drops what's cached for tips no ref points at any more, keeping
+    /// `tip`'s, which may be a commit browsed from without a ref
+    fn prune_cache(&mut self, tip: git2::Oid) -> Result<()> {
+        let mut tips = HashSet::from([tip.to_string()]);
+        // A detached HEAD is the one tip no ref under refs/ names
+        if let Ok(head) = self.repo.head().and_then(|head| head.peel_to_commit()) {
+            tips.insert(head.id().to_string());
+        }
+        for reference in self.repo.references()? {
+            if let Ok(commit) = reference?.peel_to_commit() {
+                tips.insert(commit.id().to_string());
+            }
+        }
+        self.cache.keep_tips(&tips);
+        Ok(())
+    }
+
+    /// Has walks for history pages report every so often how many commits
+    /// they've looked at and how many of those they've kept, as a filtered
+    /// walk can go through a lot of history to fill a page
+    pub fn on_walk_progress(&mut self, report: Option<Box<dyn Fn(usize, usize) + Send>>) {
+        self.walk_progress = report;
+    }
+
+    fn report_walk(&self, scanned: usize, found: usize) {
+        if scanned > 0 && scanned.is_multiple_of(WALK_PROGRESS_BATCH) {
+            if let Some(report) = &self.walk_progress {
+                report(scanned, found);
+            }
+        }
+    }
+
+    /// Walks `branch` and returns at most `limit` commits after skipping the
+    /// first `offset`, so history can be loaded a page at a time. With a
+    /// `path`, only commits that changed that file are returned, following
+    /// it back through renames, and their diffs are limited to it. Only
+    /// commits passing `filters` count towards the offset and limit. The
+    /// walk stops with `Cancelled` as soon as `cancel` says it's unwanted.
+    pub fn get_commits_page(&mut self, branch: &str, path: Option<&str>, filters: &FilterSet, offset: usize, limit: usize, cancel: &CancelToken) -> Result<Vec<CommitInfo>> {
+        let tip = self.branch_tip(branch)?;
+        let key = filters.key();
+
+        if let Some(commits) = self.cache.get_commits(&tip.to_string(), path, &key, offset, limit) {
+            return Ok(commits.clone());
+        }
+
+        // Earlier pages are walked past again to reach this one
+        let mut commits = Vec::new();
+        let mut matched = 0;
+        self.walk_history(tip, path, filters, cancel, |commit, paths| {
+            matched += 1;
+            if matched <= offset {
+                return Ok(Visit::Skipped);
+            }
+            commits.push(CommitInfo { paths, ..self.commit_summary(commit) });
+            Ok(if commits.len() >= limit { Visit::Done } else { Visit::Taken })
+        })?;
+
+        self.prune_cache(tip)?;
+        self.cache.set_commits(&tip.to_string(), path, &key, offset, limit, commits.clone());
+        Ok(commits)
+    }
+
+    /// Walks the history from `tip` as the commit list shows it, handing each
+    /// commit passing `filters`, or changing `path` when following a file,
+    /// to `visit` with its paths until it says it's done. Returns how many
+    /// commits were looked at.
+    pub fn walk_history<F>(&self, tip: git2::Oid, path: Option<&str>, filters: &FilterSet, cancel: &CancelToken, mut visit: F) -> Result<usize>
+    where
+        F: FnMut(&git2::Commit, Vec<String>) -> Result<Visit>,
+    {
+        let mut revwalk = self.repo.revwalk()?;
+        // Topological order keeps children above their parents, which the graph relies on
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        revwalk.push(tip)?;
+        if filters.first_parent {
+            revwalk.simplify_first_parent()?;
+        }
+        if let Some(path) = path {
+            return self.file_history(revwalk, path, filters, cancel, visit);
+        }
+
+        let mut found = 0;
+        let mut scanned = 0;
+        for oid in revwalk {
+            cancel.check()?;
+            self.report_walk(scanned, found);
+            scanned += 1;
+            let commit = self.repo.find_commit(oid?)?;
+            // Without filters looking at commits, every one gets through
+            if filters.checks_commits() && !self.passes(&commit, filters)? {
+                continue;
+            }
+            match visit(&commit, Vec::new())? {
+                Visit::Skipped => {},
+                Visit::Taken => found += 1,
+                Visit::Done => break,
+            }
+        }
+        Ok(scanned)
+    }
+
+    /// Reads `branch`'s history a page at a time until it ends or `limit`
+    /// commits are found, for exports and the command line
+    pub fn get_history(&mut self, branch: &str, path: Option<&str>, filters: &FilterSet, limit: Option<usize>) -> Result<Vec<CommitInfo>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut commits = Vec::new();
+        while commits.len() < limit {
+            let page = self.get_commits_page(branch, path, filters, commits.len(), COMMIT_PAGE_SIZE, &CancelToken::never())?;
+            let done = page.len() < COMMIT_PAGE_SIZE;
+            commits.extend(page);
+            if done {
+                break;
+            }
+        }
+        commits.truncate(limit);
+        Ok(commits)
+    }
+
+    /// Whether `commit` gets through `filters`, looking at its diff only
+    /// when the cheaper checks pass
+    fn passes(&self, commit: &git2::Commit, filters: &FilterSet) -> Result<bool> {
+        if !filters.matches_summary(&self.commit_summary(commit)) {
+            return Ok(false);
+        }
+        match &filters.paths {
+            Some(paths) => self.touches(commit, paths),
+            None => Ok(true),
+        }
+    }
+
+    /// Whether the commit changed a file matching `paths` against its first
+    /// parent, on either side of a rename
+    fn touches(&self, commit: &git2::Commit, paths: &PathFilter) -> Result<bool> {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let touched = diff.deltas().any(|delta| {
+            [delta.old_file().path(), delta.new_file().path()]
+                .into_iter()
+                .flatten()
+                .any(|path| paths.matches(&path.to_string_lossy()))
+        });
+        Ok(touched)
+    }
+
+    /// Picks the commits out of `revwalk` that changed `path` and hands
+    /// those passing `filters` to `visit`, with the file's path there and
+    /// the one it was renamed from. Paging means walking from the tip again
+    /// each time, since the path being followed depends on every rename
+    /// seen on the way down.
+    fn file_history<F>(&self, revwalk: git2::Revwalk, path: &str, filters: &FilterSet, cancel: &CancelToken, mut visit: F) -> Result<usize>
+    where
+        F: FnMut(&git2::Commit, Vec<String>) -> Result<Visit>,
+    {
+        let mut path = path.to_string();
+        let mut found = 0;
+        let mut scanned = 0;
+
+        for oid in revwalk {
+            cancel.check()?;
+            self.report_walk(scanned, found);
+            scanned += 1;
+            let commit = self.repo.find_commit(oid?)?;
+            let blob = entry_id(&commit.tree()?, &path);
+
+            // Like `git log -- path`, a commit counts when the file differs
+            // from every parent, so merges that took one side are skipped
+            let mut parent_blobs = Vec::new();
+            for parent in commit.parents() {
+                parent_blobs.push(entry_id(&parent.tree()?, &path));
+            }
+            let changed = if parent_blobs.is_empty() {
+                blob.is_some()
+            } else {
+                parent_blobs.iter().all(|parent_blob| *parent_blob != blob)
+            };
+            if !changed {
+                continue;
+            }
+
+            // A file that appears out of nowhere may have been renamed
+            let renamed_from = match (blob, parent_blobs.first()) {
+                (Some(_), Some(None)) => self.rename_source(&commit, &path)?,
+                _ => None,
+            };
+
+            // Renames are followed through commits that are filtered out too
+            if self.passes(&commit, filters)? {
+                let mut paths = vec![path.clone()];
+                paths.extend(renamed_from.clone());
+                match visit(&commit, paths)? {
+                    Visit::Skipped => {},
+                    Visit::Taken => found += 1,
+                    Visit::Done => break,
+                }
+            }
+
+            if let Some(old_path) = renamed_from {
+                path = old_path;
+            }
+        }
+
+        Ok(scanned)
+    }
+
+    /// The path `path` was renamed from in `commit`, if it was
+    fn rename_source(&self, commit: &git2::Commit, path: &str) -> Result<Option<String>> {
+        let parent = commit.parent(0)?;
+        let mut diff = self.repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+        diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+
+        let source = diff.deltas().find_map(|delta| {
+            let renamed = delta.status() == git2::Delta::Renamed;
+            let new_path = delta.new_file().path()?.to_str()?;
+            if renamed && new_path == path {
+                Some(delta.old_file().path()?.to_str()?.to_string())
+            } else {
+                None
+            }
+        });
+        Ok(source)
+    }
+
+    /// The commit's hash, message, author and date, without its diff
+    fn commit_summary(&self, commit: &git2::Commit) -> CommitInfo {
+        let author = commit.author();
+        let name = author.name().unwrap_or("Unknown");
+        let email = author.email().unwrap_or("unknown@email.com");
+        let committer = commit.committer();
+
+        CommitInfo {
+            hash: commit.id().to_string(),
+            message: commit.message().unwrap_or("").to_string(),
+            author: format!("{} <{}>", name, email),
+            author_email: email.to_string(),
+            time: author.when().seconds(),
+            committer: format!(
+                "{} <{}>",
+                committer.name().unwrap_or("Unknown"),
+                committer.email().unwrap_or("unknown@email.com")
+            ),
+            commit_time: committer.when().seconds(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// The commit's diff against its first parent, limited to `paths` if
+    /// any are given. Unless `full` is set, the diffs of files over
+    /// `LARGE_FILE_SIZE` are cut short. Recent diffs are cached, up to the
+    /// memory budget set with `set_diff_cache_budget`, so going back to a
+    /// commit doesn't diff it again.
+    pub fn commit_diff(&mut self, hash: &str, paths: &[String], full: bool) -> Result<CommitDiff> {
+        if let Some(diff) = self.cache.get_diff(hash, paths, full) {
+            return Ok(diff.clone());
+        }
+        let git_diff = self.first_parent_diff(hash, paths)?;
+        let (patch, info) = match full {
+            true => (diff::patch_text(&git_diff)?, diff::diff_info(&git_diff)?),
+            false => (diff::cut_patch_text(&git_diff)?, diff::cut_diff_info(&git_diff)?),
+        };
+        let cut_short = info.files.iter().any(|file| file.omitted_lines > 0);
+        // The counts come from the same diff, so asking for them next doesn't read it again
+        let stat = match self.cache.get_diffstat(hash, paths) {
+            Some(_) => None,
+            None => Some(diff::diff_stat(&git_diff)?),
+        };
+        let diff = CommitDiff { hash: hash.to_string(), paths: paths.to_vec(), patch, info, cut_short };
+        drop(git_diff);
+        if let Some(stat) = stat {
+            self.cache.set_diffstat(DiffStat { hash: hash.to_string(), paths: paths.to_vec(), ..stat });
+        }
+        self.cache.set_diff(diff.clone());
+        Ok(diff)
+    }
+
+    /// Caps the memory the recent diffs may take, dropping the least
+    /// recently looked at ones if they already take more
+    pub fn set_diff_cache_budget(&mut self, bytes: usize) {
+        self.cache.set_diff_budget(bytes);
+    }
+
+    pub fn diff_cache_stats(&self) -> DiffCacheStats {
+        self.cache.diff_stats()
+    }
+
+    /// Sets how alike files must be, in percent, to show as renamed or
+    /// copied, or turns looking for them off, which is quicker for commits
+    /// touching many files
+    pub fn set_rename_detection(&mut self, similarity: Option<u16>) {
+        if similarity != self.similarity {
+            self.similarity = similarity;
+            // The diffs kept were read with the old setting
+            self.cache.clear_diffs();
+        }
+    }
+
+    pub fn rename_detection(&self) -> Option<u16> {
+        self.similarity
+    }
+
+    /// Leaves whitespace differences out of diffs as asked
+    pub fn set_whitespace(&mut self, whitespace: Whitespace) {
+        if whitespace != self.whitespace {
+            self.whitespace = whitespace;
+            self.cache.clear_diffs();
+        }
+    }
+
+    /// Sets how much unchanged text diffs show around each change
+    pub fn set_diff_context(&mut self, context: DiffContext) {
+        if context != self.context {
+            self.context = context;
+            self.cache.clear_diffs();
+        }
+    }
+
+    /// Options for the diffs that are shown, following the settings
+    fn diff_options(&self) -> git2::DiffOptions {
+        let mut opts = git2::DiffOptions::new();
+        // More lines than any file has, but small enough that git's
+        // arithmetic on it can't overflow
+        let context = if self.context.full_file { i32::MAX as u32 / 2 } else { self.context.lines };
+        opts.context_lines(context)
+            .ignore_whitespace(self.whitespace.ignore_all)
+            .ignore_whitespace_change(self.whitespace.ignore_change)
+            .ignore_blank_lines(self.whitespace.ignore_blank_lines);
+        opts
+    }
+
+    /// Pairs up deleted and added files as renames, and added files with
+    /// the changed ones they were copied from, if that's turned on
+    fn find_renames(&self, diff: &mut git2::Diff) -> Result<()> {
+        if let Some(similarity) = self.similarity {
+            let mut opts = git2::DiffFindOptions::new();
+            opts.renames(true).copies(true).rename_threshold(similarity).copy_threshold(similarity);
+            diff.find_similar(Some(&mut opts))?;
+        }
+        Ok(())
+    }
+
+    /// How many lines the commit added and removed in each file, as in
+    /// `commit_diff` but without keeping the lines, so they're exact even
+    /// where its diff is cut short
+    pub fn get_diffstat(&mut self, hash: &str, paths: &[String]) -> Result<DiffStat> {
+        if let Some(stat) = self.cache.get_diffstat(hash, paths) {
+            return Ok(stat.clone());
+        }
+        let stat = diff::diff_stat(&self.first_parent_diff(hash, paths)?)?;
+        let stat = DiffStat { hash: hash.to_string(), paths: paths.to_vec(), ..stat };
+        self.cache.set_diffstat(stat.clone());
+        Ok(stat)
+    }
+
+    fn first_parent_diff(&self, hash: &str, paths: &[String]) -> Result<git2::Diff<'_>> {
+        let commit = self.repo.find_commit(git2::Oid::from_str(hash)?)?;
+        // A root commit is diffed against the empty tree, so everything it adds shows
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut diff_opts = self.diff_options();
+        for path in paths {
+            diff_opts.pathspec(path);
+        }
+        let mut diff = self.repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut diff_opts),
+        )?;
+        if self.similarity.is_none() && !paths.is_empty() {
+            // Show a followed rename as one change rather than a delete and an add
+            diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+        }
+        self.find_renames(&mut diff)?;
+        Ok(diff)
+    }
+
+    /// Annotates every line of `path` as of `commit` with the commit that
+    /// last changed it
+    pub fn blame(&self, commit: &str, path: &str) -> Result<Vec<BlameLine>> {
+        let oid = git2::Oid::from_str(commit)?;
+        let blob = self
+            .repo
+            .find_commit(oid)?
+            .tree()?
+            .get_path(Path::new(path))?
+            .to_object(&self.repo)?
+            .peel_to_blob()?;
+        if blob.is_binary() {
+            anyhow::bail!("{} is a binary file", path);
+        }
+
+        let mut opts = git2::BlameOptions::new();
+        opts.newest_commit(oid);
+        let blame = self.repo.blame_file(Path::new(path), Some(&mut opts))?;
+
+        let content = String::from_utf8_lossy(blob.content());
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let hunk = blame.get_line(i + 1);
+                let signature = hunk.as_ref().map(|h| h.final_signature());
+                BlameLine {
+                    hash: hunk.as_ref().map(|h| h.final_commit_id().to_string()).unwrap_or_default(),
+                    author: signature.as_ref().and_then(|s| s.name()).unwrap_or("Unknown").to_string(),
+                    time: signature.as_ref().map(|s| s.when().seconds()).unwrap_or(0),
+                    content: line.to_string(),
+                }
+            })
+            .collect();
+        Ok(lines)
+    }
+
+    /// Lists the entries of directory `dir` ("" for the root) in the tree of
+    /// `commit`, directories first, as (name, is_dir) pairs
+    pub fn tree_entries(&self, commit: &str, dir: &str) -> Result<Vec<(String, bool)>> {
+        let root = self.repo.find_commit(git2::Oid::from_str(commit)?)?.tree()?;
+        let tree = if dir.is_empty() {
+            root
+        } else {
+            root.get_path(Path::new(dir))?.to_object(&self.repo)?.peel_to_tree()?
+        };
+
+        let mut entries: Vec<(String, bool)> = tree
+            .iter()
+            .map(|entry| {
+                let name = String::from_utf8_lossy(entry.name_bytes()).into_owned();
+                (name, entry.kind() == Some(git2::ObjectType::Tree))
+            })
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(entries)
+    }
+
+    /// Contents of the file at `path` as of `commit`
+    pub fn file_content(&self, commit: &str, path: &str) -> Result<String> {
+        let blob = self.blob_at(commit, path)?;
+        if blob.is_binary() {
+            anyhow::bail!("{} is a binary file", path);
+        }
+        Ok(String::from_utf8_lossy(blob.content()).into_owned())
+    }
+
+    /// The raw bytes of the file at `path` as of `commit`, binary or not
+    pub fn file_bytes(&self, commit: &str, path: &str) -> Result<Vec<u8>> {
+        Ok(self.blob_at(commit, path)?.content().to_vec())
+    }
+
+    /// How big the file at `path` is as of `commit`, without reading it
+    pub fn file_size(&self, commit: &str, path: &str) -> Result<u64> {
+        let entry = self.repo.find_commit(git2::Oid::from_str(commit)?)?.tree()?.get_path(Path::new(path))?;
+        let (size, _) = self.repo.odb()?.read_header(entry.id())?;
+        Ok(size as u64)
+    }
+
+    fn blob_at(&self, commit: &str, path: &str) -> Result<git2::Blob<'_>> {
+        let blob = self
+            .repo
+            .find_commit(git2::Oid::from_str(commit)?)?
+            .tree()?
+            .get_path(Path::new(path))?
+            .to_object(&self.repo)?
+            .peel_to_blob()?;
+        Ok(blob)
+    }
+
+    /// Converts `path`, relative to the current directory or absolute, into
+    /// a path relative to the repository root as git stores it
+    pub fn repo_path(&self, path: &Path) -> Result<String> {
+        let absolute = std::env::current_dir()?.join(path);
+        let relative = absolute.strip_prefix(self.root()).unwrap_or(path);
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Ok(components.join("/"))
+    }
+
+    /// Lists everyone who authored a commit reachable from `branch`, one
+    /// entry per email address. The walk is cached against the branch tip.
+    pub fn get_authors(&mut self, branch: &str) -> Result<Vec<AuthorInfo>> {
+        let tip = self.branch_tip(branch)?;
+
+        if let Some(authors) = self.cache.get_authors(&tip.to_string()) {
+            return Ok(authors.clone());
+        }
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        revwalk.push(tip)?;
+
+        // Newest commits come first, so each author keeps their latest name
+        let mut seen = HashSet::new();
+        let mut authors = Vec::new();
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            let author = commit.author();
+            let email = author.email().unwrap_or("unknown@email.com").to_string();
+            if seen.insert(email.to_lowercase()) {
+                authors.push(AuthorInfo {
+                    name: author.name().unwrap_or("Unknown").to_string(),
+                    email,
+                });
+            }
+        }
+
+        authors.sort_by_key(|a| a.name.to_lowercase());
+        self.prune_cache(tip)?;
+        self.cache.set_authors(&tip.to_string(), authors.clone());
+        Ok(authors)
+    }
+
+    /// Every branch ref with its target plus HEAD, which changes whenever
+    /// the branch list would
+    fn branch_fingerprint(&self) -> Result<String> {
+        let mut fingerprint = match self.repo.find_reference("HEAD") {
+            Ok(head) => format!("HEAD {:?} {:?}\n", head.symbolic_target(), head.target()),
+            Err(_) => String::new(),
+        };
+        for reference in self.repo.references()? {
+            let reference = reference?;
+            if reference.is_branch() || reference.is_remote() {
+                fingerprint.push_str(&format!("{} {:?}\n", reference.name().unwrap_or(""), reference.target()));
+            }
+        }
+        Ok(fingerprint)
+    }
+
+    fn branch_ahead_behind(&self, branch: &git2::Branch) -> Option<(usize, usize)> {
+        let upstream = branch.upstream().ok()?;
+        let local = branch.get().target()?;
+        let upstream = upstream.get().target()?;
+        self.repo.graph_ahead_behind(local, upstream).ok()
+    }
+
+    /// Lists local branches followed by remote-tracking branches, the
+    /// latter sorted so each remote's branches are grouped together
+    pub fn get_branches(&mut self) -> Result<Vec<BranchInfo>> {
+        let fingerprint = self.branch_fingerprint()?;
+        if let Some(branches) = self.cache.get_branches(&fingerprint) {
+            return Ok(branches.clone());
+        }
+
+        let mut branches = Vec::new();
+        
+        for branch in self.repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.name()? {
+                branches.push(BranchInfo {
+                    name: name.to_string(),
+                    is_current: branch.is_head(),
+                    remote: None,
+                    ahead_behind: self.branch_ahead_behind(&branch),
+                });
+            }
+        }
+
+        let mut remote_branches = Vec::new();
+        for branch in self.repo.branches(Some(git2::BranchType::Remote))? {
+            let (branch, _) = branch?;
+            // Skip symbolic refs like origin/HEAD, which just alias another branch
+            if branch.get().symbolic_target().is_some() {
+                continue;
+            }
+            let name = match branch.name()? {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let remote = self
+                .repo
+                .branch_remote_name(branch.get().name().unwrap_or(""))
+                .ok()
+                .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+                .unwrap_or_else(|| name.split('/').next().unwrap_or("").to_string());
+            remote_branches.push(BranchInfo {
+                name,
+                is_current: false,
+                remote: Some(remote),
+                ahead_behind: None,
+            });
+        }
+        remote_branches.sort_by(|a, b| a.remote.cmp(&b.remote).then_with(|| a.name.cmp(&b.name)));
+        branches.extend(remote_branches);
+
+        self.cache.set_branches(&fingerprint, branches.clone());
+        Ok(branches)
+    }
+
+    /// Lists all tags with the commit each one points at, newest first
+    pub fn get_tags(&self) -> Result<Vec<TagInfo>> {
+        let mut tags = Vec::new();
+
+        for name in self.repo.tag_names(None)?.iter().flatten() {
+            let reference = self.repo.find_reference(&format!("refs/tags/{}", name))?;
+            // Tags of trees or blobs have no place in the history view
+            let commit = match reference.peel_to_commit() {
+                Ok(commit) => commit,
+                Err(_) => continue,
+            };
+            let message = reference
+                .peel_to_tag()
+                .ok()
+                .and_then(|tag| tag.message().map(|m| m.trim().to_string()));
+            tags.push((commit.time().seconds(), TagInfo {
+                name: name.to_string(),
+                target: commit.id().to_string(),
+                message,
+            }));
+        }
+
+        tags.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+        Ok(tags.into_iter().map(|(_, tag)| tag).collect())
+    }
+
+    /// Maps each commit to the branches, tags and HEAD pointing at it
+    pub fn get_ref_map(&self) -> Result<HashMap<String, Vec<RefLabel>>> {
+        let mut map: HashMap<String, Vec<RefLabel>> = HashMap::new();
+
+        if let Ok(head) = self.repo.head() {
+            if let Ok(commit) = head.peel_to_commit() {
+                let name = match head.shorthand() {
+                    Some(branch) if head.is_branch() => format!("HEAD -> {}", branch),
+                    _ => "HEAD".to_string(),
+                };
+                map.entry(commit.id().to_string()).or_default().push(RefLabel { name, kind: RefKind::Head });
+            }
+        }
+
+        for reference in self.repo.references()? {
+            let reference = reference?;
+            let kind = if reference.is_branch() {
+                RefKind::LocalBranch
+            } else if reference.is_remote() {
+                RefKind::RemoteBranch
+            } else if reference.is_tag() {
+                RefKind::Tag
+            } else {
+                continue;
+            };
+            // Skip aliases like origin/HEAD
+            if reference.symbolic_target().is_some() {
+                continue;
+            }
+            let commit = match reference.peel_to_commit() {
+                Ok(commit) => commit,
+                Err(_) => continue,
+            };
+            let name = match reference.shorthand() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            let labels = map.entry(commit.id().to_string()).or_default();
+            // HEAD already names the branch it points at
+            if kind == RefKind::LocalBranch && labels.iter().any(|l| l.name == format!("HEAD -> {}", name)) {
+                continue;
+            }
+            labels.push(RefLabel { name, kind });
+        }
+
+        // Order like git: HEAD, local branches, remote branches, then tags
+        for labels in map.values_mut() {
+            labels.sort_by_key(|label| label.kind as u8);
+        }
+
+        Ok(map)
+    }
+}
+
+/// The blob at `path` in `tree`, or None if there's nothing there
+fn entry_id(tree: &git2::Tree, path: &str) -> Option<git2::Oid> {
+    tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use super::{diff, GitManager};
+use crate::models::{DiffInfo, PatchPreview};
+
+// Longest subject kept in a patch's file name, as in git
+const MAX_NAME_LENGTH: usize = 52;
+
+/// A patch file named the way `git format-patch` would, such as
+/// `0001-fix-the-parser.patch`
+fn file_name(number: usize, summary: &str) -> String {
+    let mut name = String::new();
+    for c in summary.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.truncate(MAX_NAME_LENGTH);
+    let name = name.trim_end_matches(['-', '.']);
+    format!("{:04}-{}.patch", number, name)
+}
+
+impl GitManager {
+    /// Formats `hashes`, oldest first, as numbered patches in mbox form
+    /// like `git format-patch`, ready for `git am`, each with the file name
+    /// git would give it. As there, merges are left out.
+    pub fn format_patches(&self, hashes: &[&str]) -> Result<Vec<(String, String)>> {
+        let mut commits = Vec::new();
+        for hash in hashes {
+            let commit = self.repo.find_commit(git2::Oid::from_str(hash)?)?;
+            if commit.parent_count() < 2 {
+                commits.push(commit);
+            }
+        }
+
+        let mut patches = Vec::new();
+        for (i, commit) in commits.iter().enumerate() {
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            let message = commit.message().unwrap_or("");
+            let (summary, body) = message.split_once('\n').unwrap_or((message, ""));
+            let email = git2::Email::from_diff(
+                &diff,
+                i + 1,
+                commits.len(),
+                &commit.id(),
+                summary,
+                body.trim_start_matches('\n'),
+                &commit.author(),
+                &mut git2::EmailCreateOptions::new(),
+            )?;
+            patches.push((file_name(i + 1, summary), String::from_utf8_lossy(email.as_slice()).into_owned()));
+        }
+        Ok(patches)
+    }
+}
+
+/// Splits an mbox of patches at each `From <hash>` line, keeping the
+/// subject of each; anything else is one patch
+fn split_mbox(text: &str) -> Vec<(Option<String>, String)> {
+    let mut patches: Vec<(Option<String>, String)> = Vec::new();
+    for line in text.split_inclusive('\n') {
+        let starts_email = line
+            .strip_prefix("From ")
+            .and_then(|rest| rest.split(' ').next())
+            .is_some_and(|hash| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+        match patches.last_mut() {
+            Some((subject, patch)) if !starts_email => {
+                if let (None, Some(header)) = (&subject, line.strip_prefix("Subject: ")) {
+                    // Drop the `[PATCH 1/2]` numbering
+                    let header = header.trim();
+                    let header = match header.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+                        Some((_, rest)) => rest,
+                        None => header,
+                    };
+                    *subject = Some(header.to_string());
+                }
+                patch.push_str(line);
+            },
+            _ => patches.push((None, line.to_string())),
+        }
+    }
+    patches
+}
+
+impl GitManager {
+    /// Reads a patch or mbox file, checking every patch in it parses
+    pub fn read_patch(&self, path: &Path) -> Result<PatchPreview> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let patches = split_mbox(&text);
+        let mut files = Vec::new();
+        for (i, (_, patch)) in patches.iter().enumerate() {
+            let diff = git2::Diff::from_buffer(patch.as_bytes())
+                .with_context(|| format!("Patch {} of {} can't be parsed", i + 1, patches.len()))?;
+            files.extend(diff::diff_info(&diff)?.files);
+        }
+        if files.is_empty() {
+            anyhow::bail!("{} doesn't contain any changes", path.display());
+        }
+        Ok(PatchPreview {
+            path: path.display().to_string(),
+            patches,
+            patch: text,
+            info: DiffInfo { files },
+        })
+    }
+
+    /// Applies each patch in turn to the working tree or the index,
+    /// stopping at the first that doesn't apply and naming the files it
+    /// conflicts on. Returns how many were applied.
+    pub fn apply_patch(&self, preview: &PatchPreview, location: git2::ApplyLocation) -> Result<usize> {
+        let total = preview.patches.len();
+        for (i, (subject, patch)) in preview.patches.iter().enumerate() {
+            let diff = git2::Diff::from_buffer(patch.as_bytes())?;
+
+            // libgit2 lets a new file replace one already there, which git refuses
+            let index = self.repo.index()?;
+            let mut conflicts: Vec<String> = diff
+                .deltas()
+                .filter(|delta| delta.status() == git2::Delta::Added)
+                .filter_map(|delta| delta.new_file().path())
+                .filter(|path| match location {
+                    git2::ApplyLocation::Index => index.get_path(path, 0).is_some(),
+                    _ => self.repo.workdir().is_some_and(|dir| dir.join(path).exists()),
+                })
+                .map(|path| format!("{} (already exists)", path.display()))
+                .collect();
+            let which = match (total, subject) {
+                (1, _) => "The patch".to_string(),
+                (_, Some(subject)) => format!("Patch {} of {} ({})", i + 1, total, subject),
+                (_, None) => format!("Patch {} of {}", i + 1, total),
+            };
+            let applied = match i {
+                0 => String::new(),
+                1 => "; the one before it was applied".to_string(),
+                _ => format!("; the {} before it were applied", i),
+            };
+
+            if conflicts.is_empty() {
+                let result = self
+                    .repo
+                    .apply(&diff, location, Some(git2::ApplyOptions::new().check(true)))
+                    .and_then(|_| self.repo.apply(&diff, location, None));
+                let error = match result {
+                    Ok(()) => continue,
+                    Err(e) => e,
+                };
+
+                // Check one file at a time to find those the patch conflicts on
+                for (n, delta) in diff.deltas().enumerate() {
+                    let mut seen = 0;
+                    let mut opts = git2::ApplyOptions::new();
+                    opts.check(true).delta_callback(|_| {
+                        seen += 1;
+                        seen == n + 1
+                    });
+                    if self.repo.apply(&diff, location, Some(&mut opts)).is_err() {
+                        let path = delta.new_file().path().or(delta.old_file().path());
+                        conflicts.extend(path.map(|path| path.display().to_string()));
+                    }
+                }
+                // Hunks that check out can still clash with untracked files
+                if conflicts.is_empty() {
+                    anyhow::bail!("{} doesn't apply: {}{}", which, error.message(), applied);
+                }
+            }
+            anyhow::bail!("{} doesn't apply to {}{}", which, conflicts.join(", "), applied);
+        }
+        Ok(total)
+    }
+}
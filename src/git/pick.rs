@@ -0,0 +1,83 @@
+use anyhow::Result;
+use git2::build::CheckoutBuilder;
+use git2::Oid;
+use super::GitManager;
+
+/// How replaying a commit onto HEAD went
+pub enum PickOutcome {
+    Committed(Oid),
+    // Paths that didn't merge cleanly; nothing was written
+    Conflicts(Vec<String>),
+}
+
+impl GitManager {
+    /// Replays `hash` on top of HEAD as a new commit, keeping its author and
+    /// message like `git cherry-pick`. The merge happens in memory, so a
+    /// conflict leaves HEAD, the index and the working tree untouched.
+    pub fn cherry_pick(&self, hash: &str) -> Result<PickOutcome> {
+        let commit = self.repo.find_commit(Oid::from_str(hash)?)?;
+        if commit.parent_count() > 1 {
+            anyhow::bail!("{} is a merge commit", &hash[..7.min(hash.len())]);
+        }
+        let head = self.repo.head()?.peel_to_commit()?;
+
+        let mut index = self.repo.cherrypick_commit(&commit, &head, 0, None)?;
+        if index.has_conflicts() {
+            return Ok(PickOutcome::Conflicts(conflicted_paths(&index)?));
+        }
+
+        let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+        let committer = self.repo.signature()?;
+        let message = commit.message().unwrap_or("");
+        self.checkout_before_commit(&tree)?;
+        let oid = self.repo.commit(Some("HEAD"), &commit.author(), &committer, message, &tree, &[&head])?;
+        Ok(PickOutcome::Committed(oid))
+    }
+
+    /// Commits the inverse of `hash` on top of HEAD, like `git revert`.
+    /// Conflicts are reported the same way as for cherry-picks.
+    pub fn revert(&self, hash: &str) -> Result<PickOutcome> {
+        let commit = self.repo.find_commit(Oid::from_str(hash)?)?;
+        if commit.parent_count() > 1 {
+            anyhow::bail!("{} is a merge commit", &hash[..7.min(hash.len())]);
+        }
+        let head = self.repo.head()?.peel_to_commit()?;
+
+        let mut index = self.repo.revert_commit(&commit, &head, 0, None)?;
+        if index.has_conflicts() {
+            return Ok(PickOutcome::Conflicts(conflicted_paths(&index)?));
+        }
+
+        let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+        let signature = self.repo.signature()?;
+        let message = format!(
+            "Revert \"{}\"\n\nThis reverts commit {}.\n",
+            commit.summary().unwrap_or(""),
+            commit.id()
+        );
+        self.checkout_before_commit(&tree)?;
+        let oid = self.repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&head])?;
+        Ok(PickOutcome::Committed(oid))
+    }
+
+    /// Moves the index and working tree to `tree` ahead of committing it, so
+    /// the checkout still compares against the old HEAD
+    fn checkout_before_commit(&self, tree: &git2::Tree) -> Result<()> {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        self.repo.checkout_tree(tree.as_object(), Some(&mut checkout))?;
+        Ok(())
+    }
+}
+
+fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::cell::Cell;
+use super::GitManager;
+
+/// What a diff must contain, like `git log -S` or `git log -G`
+pub enum PickaxeQuery {
+    // The number of occurrences of the text changes
+    Text(String),
+    // An added or removed line matches
+    Regex(Regex),
+}
+
+impl PickaxeQuery {
+    /// `/pattern/` is a regex, anything else is plain text
+    pub fn parse(input: &str) -> Result<PickaxeQuery> {
+        match input.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            Some(pattern) if !pattern.is_empty() => {
+                let regex = Regex::new(pattern).with_context(|| format!("Invalid regex {}", pattern))?;
+                Ok(PickaxeQuery::Regex(regex))
+            },
+            _ => Ok(PickaxeQuery::Text(input.to_string())),
+        }
+    }
+}
+
+impl GitManager {
+    /// Whether the commit's changes against its first parent add or remove
+    /// what the query looks for. Binary files are skipped.
+    pub(super) fn pickaxe_matches(&self, commit: &git2::Commit, query: &PickaxeQuery) -> Result<bool> {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+        // Occurrences in the current file's added lines minus those in its
+        // removed ones. As with `git log -S`, text moved to another file
+        // doesn't count.
+        let balance = Cell::new(0i64);
+        let matched = Cell::new(false);
+        diff.foreach(
+            &mut |_, _| {
+                matched.set(matched.get() || balance.replace(0) != 0);
+                !matched.get()
+            },
+            None,
+            None,
+            Some(&mut |_, _, line| {
+                let sign = match line.origin() {
+                    '+' => 1,
+                    '-' => -1,
+                    _ => return true,
+                };
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.trim_end_matches(['\n', '\r']);
+                match query {
+                    PickaxeQuery::Text(text) => balance.set(balance.get() + sign * content.matches(text.as_str()).count() as i64),
+                    PickaxeQuery::Regex(regex) => matched.set(regex.is_match(content)),
+                }
+                // A regex only needs one matching line
+                !matched.get()
+            }),
+        )
+        .or_else(|e| if matched.get() { Ok(()) } else { Err(e) })?;
+        Ok(matched.get() || balance.get() != 0)
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use super::GitManager;
+use crate::models::ReflogEntry;
+
+impl GitManager {
+    /// Reflog entries of HEAD and every local branch, newest first
+    pub fn get_reflog(&self) -> Result<Vec<ReflogEntry>> {
+        let mut names = vec!["HEAD".to_string()];
+        for branch in self.repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.get().name() {
+                names.push(name.to_string());
+            }
+        }
+
+        let mut entries = Vec::new();
+        for name in names {
+            let shorthand = name.trim_start_matches("refs/heads/").to_string();
+            for (index, entry) in self.repo.reflog(&name)?.iter().enumerate() {
+                let time = entry.committer().when().seconds();
+                let date = match Local.timestamp_opt(time, 0) {
+                    chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    _ => String::new(),
+                };
+                entries.push(ReflogEntry {
+                    ref_name: shorthand.clone(),
+                    index,
+                    hash: entry.id_new().to_string(),
+                    message: entry.message().unwrap_or("").to_string(),
+                    time,
+                    date,
+                });
+            }
+        }
+
+        // Sorting is stable, so each ref's entries keep their reflog order
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+        Ok(entries)
+    }
+}
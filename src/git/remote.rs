@@ -0,0 +1,251 @@
+use anyhow::{Context, Result};
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, Cred, CredentialType, FetchOptions, PushOptions, RemoteCallbacks};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use super::GitManager;
+
+/// A network operation to run in the background
+#[derive(Debug, Clone)]
+pub enum RemoteOp {
+    Fetch { remote: String },
+    // Fast-forward the branch to its upstream
+    Pull { branch: String },
+    Push { remote: String, branch: String },
+    // Fetch the rest of a shallow clone's history
+    Unshallow { remote: String },
+}
+
+pub enum RemoteEvent {
+    Progress(String),
+    Done(String),
+    Failed(String),
+}
+
+/// How far a branch has moved from the branch it tracks
+pub struct AheadBehind {
+    pub upstream: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// Callbacks shared by fetch and push: credentials from the SSH agent, git's
+/// credential helpers or `GIT_TOKEN`, and progress forwarded to `progress`
+fn callbacks<'a>(config: git2::Config, progress: &'a dyn Fn(String)) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    // libgit2 keeps asking for as long as credentials are rejected
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+        let username = username.unwrap_or("git");
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username);
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Ok(cred) = Cred::credential_helper(&config, url, Some(username)) {
+                return Ok(cred);
+            }
+            if let Ok(token) = std::env::var("GIT_TOKEN") {
+                return Cred::userpass_plaintext(username, &token);
+            }
+        }
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+        Cred::default()
+    });
+
+    // Only report when the percentage moves, the callback fires constantly
+    let mut last = None;
+    callbacks.transfer_progress(move |stats| {
+        let percent = stats.received_objects() * 100 / stats.total_objects().max(1);
+        if last != Some(percent) {
+            last = Some(percent);
+            progress(format!(
+                "Receiving objects: {}% ({}/{})",
+                percent,
+                stats.received_objects(),
+                stats.total_objects()
+            ));
+        }
+        true
+    });
+    let mut last = None;
+    callbacks.push_transfer_progress(move |current, total, _| {
+        let percent = current * 100 / total.max(1);
+        if last != Some(percent) {
+            last = Some(percent);
+            progress(format!("Writing objects: {}% ({}/{})", percent, current, total));
+        }
+    });
+    callbacks
+}
+
+impl GitManager {
+    pub fn remote_url(&self, name: &str) -> Result<String> {
+        let remote = self.repo.find_remote(name)?;
+        Ok(remote.url().with_context(|| format!("{} has no URL", name))?.to_string())
+    }
+
+    pub fn remote_names(&self) -> Result<Vec<String>> {
+        Ok(self.repo.remotes()?.iter().flatten().map(|name| name.to_string()).collect())
+    }
+
+    /// The remote a branch pushes to and pulls from: its upstream's, or
+    /// `origin` if it has none
+    pub fn default_remote(&self, branch: &str) -> String {
+        self.repo
+            .branch_upstream_remote(&format!("refs/heads/{}", branch))
+            .ok()
+            .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| "origin".to_string())
+    }
+
+    pub fn ahead_behind(&self, branch: &str) -> Result<Option<AheadBehind>> {
+        let local = self.repo.find_branch(branch, BranchType::Local)?;
+        let upstream = match local.upstream() {
+            Ok(upstream) => upstream,
+            Err(_) => return Ok(None),
+        };
+        let local_tip = local.get().peel_to_commit()?.id();
+        let upstream_tip = upstream.get().peel_to_commit()?.id();
+        let (ahead, behind) = self.repo.graph_ahead_behind(local_tip, upstream_tip)?;
+        Ok(Some(AheadBehind {
+            upstream: upstream.name()?.unwrap_or("").to_string(),
+            ahead,
+            behind,
+        }))
+    }
+
+    /// Fetches every branch of `remote`, like `git fetch <remote>`
+    pub fn fetch(&self, remote: &str, progress: &dyn Fn(String)) -> Result<String> {
+        let mut remote = self.repo.find_remote(remote)?;
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(callbacks(self.repo.config()?, progress));
+        let refspecs: [&str; 0] = [];
+        remote.fetch(&refspecs, Some(&mut options), None)?;
+        Ok(format!("Fetched from {}", remote.name().unwrap_or("")))
+    }
+
+    /// Fetches the history a shallow clone is missing, like
+    /// `git fetch --unshallow`. libgit2 can't deepen by a number of commits.
+    pub fn unshallow(&self, remote: &str, progress: &dyn Fn(String)) -> Result<String> {
+        let mut remote = self.repo.find_remote(remote)?;
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(callbacks(self.repo.config()?, progress));
+        // The largest depth is how libgit2 is told to unshallow
+        options.depth(i32::MAX);
+        let refspecs: [&str; 0] = [];
+        remote.fetch(&refspecs, Some(&mut options), None)?;
+        Ok(format!("Fetched the whole history from {}", remote.name().unwrap_or("")))
+    }
+
+    /// Fetches the branch's upstream and fast-forwards onto it. Branches that
+    /// have diverged are left alone.
+    pub fn pull(&self, branch: &str, progress: &dyn Fn(String)) -> Result<String> {
+        let refname = format!("refs/heads/{}", branch);
+        let remote = self
+            .repo
+            .branch_upstream_remote(&refname)
+            .with_context(|| format!("{} has no upstream branch", branch))?;
+        self.fetch(remote.as_str().unwrap_or("origin"), progress)?;
+
+        let local = self.repo.find_branch(branch, BranchType::Local)?;
+        let head = local.get().peel_to_commit()?.id();
+        let target = local.upstream()?.get().peel_to_commit()?;
+        if target.id() == head || self.repo.graph_descendant_of(head, target.id())? {
+            return Ok(format!("{} is already up to date", branch));
+        }
+        if !self.repo.graph_descendant_of(target.id(), head)? {
+            anyhow::bail!("{} has diverged from its upstream, merge or rebase it instead", branch);
+        }
+
+        // Update the files before the branch moves, so the checkout still
+        // compares against the old tip
+        if local.is_head() {
+            let mut checkout = CheckoutBuilder::new();
+            checkout.safe();
+            self.repo.checkout_tree(target.as_object(), Some(&mut checkout))?;
+        }
+        self.repo
+            .find_reference(&refname)?
+            .set_target(target.id(), "pull: fast-forward")?;
+        let hash = target.id().to_string();
+        Ok(format!("Fast-forwarded {} to {}", branch, &hash[..7]))
+    }
+
+    /// Pushes the branch to the same name on `remote`, setting it as the
+    /// upstream if the branch didn't have one
+    pub fn push(&self, remote_name: &str, branch: &str, progress: &dyn Fn(String)) -> Result<String> {
+        let mut remote = self.repo.find_remote(remote_name)?;
+        let rejected = RefCell::new(None);
+        let mut callbacks = callbacks(self.repo.config()?, progress);
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(format!("{} was rejected: {}", refname, status));
+            }
+            Ok(())
+        });
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+        remote.push(&[refspec.as_str()], Some(&mut options))?;
+        if let Some(message) = rejected.take() {
+            anyhow::bail!(message);
+        }
+
+        let mut local = self.repo.find_branch(branch, BranchType::Local)?;
+        if local.upstream().is_err() {
+            local.set_upstream(Some(&format!("{}/{}", remote_name, branch)))?;
+        }
+        Ok(format!("Pushed {} to {}", branch, remote_name))
+    }
+}
+
+/// A fetch, pull or push running on its own thread, with its own repository
+/// handle like the commit loader
+pub struct RemoteTask {
+    pub op: RemoteOp,
+    events: Receiver<RemoteEvent>,
+}
+
+impl RemoteTask {
+    pub fn spawn(repo_path: PathBuf, op: RemoteOp) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let task_op = op.clone();
+        thread::spawn(move || {
+            let progress = |message: String| {
+                let _ = event_tx.send(RemoteEvent::Progress(message));
+            };
+            let result = GitManager::new(&repo_path).and_then(|git_manager| match &task_op {
+                RemoteOp::Fetch { remote } => git_manager.fetch(remote, &progress),
+                RemoteOp::Pull { branch } => git_manager.pull(branch, &progress),
+                RemoteOp::Push { remote, branch } => git_manager.push(remote, branch, &progress),
+                RemoteOp::Unshallow { remote } => git_manager.unshallow(remote, &progress),
+            });
+            let event = match result {
+                Ok(message) => RemoteEvent::Done(message),
+                Err(e) => RemoteEvent::Failed(e.to_string()),
+            };
+            let _ = event_tx.send(event);
+        });
+        RemoteTask { op, events: event_rx }
+    }
+
+    /// Returns the next event, if any, without blocking
+    pub fn try_recv(&self) -> Option<RemoteEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            // The thread went away without saying how it ended
+            Err(TryRecvError::Disconnected) => Some(RemoteEvent::Failed("the operation stopped unexpectedly".to_string())),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use git2::{Oid, StashApplyOptions};
+use super::{diff, GitManager};
+use crate::models::StashInfo;
+
+impl GitManager {
+    /// Lists the stashes, newest first, like `git stash list`
+    pub fn get_stashes(&mut self) -> Result<Vec<StashInfo>> {
+        let mut stashes = Vec::new();
+        self.repo.stash_foreach(|index, message, oid| {
+            stashes.push(StashInfo { index, message: message.to_string(), hash: oid.to_string() });
+            true
+        })?;
+        Ok(stashes)
+    }
+
+    /// The changes a stash recorded to tracked files, against the commit
+    /// it was made on
+    pub fn stash_diff(&self, stash: &StashInfo) -> Result<String> {
+        let commit = self.repo.find_commit(Oid::from_str(&stash.hash)?)?;
+        let base = commit.parent(0)?.tree()?;
+        let mut diff = self.repo.diff_tree_to_tree(Some(&base), Some(&commit.tree()?), Some(&mut self.diff_options()))?;
+        self.find_renames(&mut diff)?;
+        diff::patch_text(&diff)
+    }
+
+    pub fn stash_apply(&mut self, index: usize) -> Result<()> {
+        self.repo.stash_apply(index, Some(&mut StashApplyOptions::new()))?;
+        Ok(())
+    }
+
+    /// Applies the stash and drops it if that went through cleanly
+    pub fn stash_pop(&mut self, index: usize) -> Result<()> {
+        self.repo.stash_pop(index, Some(&mut StashApplyOptions::new()))?;
+        Ok(())
+    }
+
+    pub fn stash_drop(&mut self, index: usize) -> Result<()> {
+        self.repo.stash_drop(index)?;
+        Ok(())
+    }
+}
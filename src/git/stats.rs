@@ -0,0 +1,149 @@
+use anyhow::Result;
+use git2::Sort;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use super::{diff, GitManager};
+use crate::models::{FileTouch, RepoStats, WeekStats};
+
+const WEEK: i64 = 7 * 24 * 3600;
+// The epoch fell on a Thursday; shifting by three days makes weeks start on Monday
+const MONDAY_OFFSET: i64 = 3 * 24 * 3600;
+const BUSIEST_FILES: usize = 50;
+
+fn week_start(time: i64) -> i64 {
+    (time + MONDAY_OFFSET).div_euclid(WEEK) * WEEK - MONDAY_OFFSET
+}
+
+impl GitManager {
+    /// Walks all of `branch`'s history, diffing every non-merge commit
+    /// against its parent. Slow on big repositories, so it runs on the
+    /// stats worker and is cached against the branch tip.
+    pub fn repo_stats(&mut self, branch: &str) -> Result<RepoStats> {
+        let tip = self.branch_tip(branch)?;
+        if let Some(stats) = self.cache.get_stats(&tip.to_string()) {
+            return Ok(stats.clone());
+        }
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        revwalk.push(tip)?;
+
+        let mut stats = RepoStats::default();
+        let mut authors: HashMap<String, usize> = HashMap::new();
+        let mut weeks: HashMap<i64, WeekStats> = HashMap::new();
+        let mut files: HashMap<String, usize> = HashMap::new();
+        let mut path_ids: HashMap<String, usize> = HashMap::new();
+        let mut author_ids: HashMap<String, usize> = HashMap::new();
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            stats.commits += 1;
+            let name = commit.author().name().unwrap_or("Unknown").to_string();
+            *authors.entry(name.clone()).or_default() += 1;
+            let start = week_start(commit.author().when().seconds());
+            let week = weeks.entry(start).or_insert(WeekStats { start, ..Default::default() });
+            week.commits += 1;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            // Every file's lines are counted in the one pass over the diff
+            let diff_stat = diff::diff_stat(&diff)?;
+            stats.diffed_commits += 1;
+            week.insertions += diff_stat.insertions;
+            week.deletions += diff_stat.deletions;
+            stats.insertions += diff_stat.insertions;
+            stats.deletions += diff_stat.deletions;
+            stats.files_changed += diff_stat.files.len();
+            let time = commit.author().when().seconds();
+            let next_id = stats.author_names.len();
+            let author = *author_ids.entry(name.clone()).or_insert(next_id);
+            if author == next_id {
+                stats.author_names.push(name);
+            }
+            for file_stat in diff_stat.files {
+                let path = match file_stat.new_path.or(file_stat.old_path) {
+                    Some(path) => path,
+                    None => continue,
+                };
+                *files.entry(path.clone()).or_default() += 1;
+
+                let (insertions, deletions) = (file_stat.insertions, file_stat.deletions);
+                let next_id = stats.paths.len();
+                let file = *path_ids.entry(path.clone()).or_insert(next_id);
+                if file == next_id {
+                    stats.paths.push(path);
+                }
+                stats.touches.push(FileTouch { time, file, author, commit: stats.commits, insertions, deletions });
+            }
+        }
+
+        stats.authors = authors.into_iter().collect();
+        stats.authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.files = files.into_iter().collect();
+        stats.files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.files.truncate(BUSIEST_FILES);
+        if let (Some(&first), Some(&last)) = (weeks.keys().min(), weeks.keys().max()) {
+            stats.weeks = (first..=last)
+                .step_by(WEEK as usize)
+                .map(|start| weeks.get(&start).copied().unwrap_or(WeekStats { start, ..Default::default() }))
+                .collect();
+        }
+
+        self.prune_cache(tip)?;
+        self.cache.set_stats(&tip.to_string(), stats.clone());
+        Ok(stats)
+    }
+}
+
+pub struct StatsEvent {
+    pub branch: String,
+    pub result: Result<RepoStats, String>,
+}
+
+/// Computes statistics on a worker thread, which keeps its repository
+/// handle and therefore its cache between requests
+pub struct StatsWorker {
+    requests: Sender<String>,
+    events: Receiver<StatsEvent>,
+}
+
+impl StatsWorker {
+    pub fn spawn(repo_path: PathBuf) -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<String>();
+        let (event_tx, event_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut git_manager = match GitManager::new(&repo_path) {
+                Ok(manager) => manager,
+                Err(_) => return,
+            };
+            for branch in request_rx {
+                let result = git_manager.repo_stats(&branch).map_err(|e| e.to_string());
+                if event_tx.send(StatsEvent { branch, result }).is_err() {
+                    break;
+                }
+            }
+        });
+
+        StatsWorker { requests: request_tx, events: event_rx }
+    }
+
+    pub fn request(&self, branch: &str) {
+        let _ = self.requests.send(branch.to_string());
+    }
+
+    /// Returns the next finished computation, if any, without blocking
+    pub fn try_recv(&self) -> Option<StatsEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::Result;
+use git2::build::CheckoutBuilder;
+use git2::{ApplyLocation, ApplyOptions, DiffOptions, Status, StatusOptions};
+use std::path::Path;
+use super::{diff, GitManager};
+use crate::models::{ChangeStatus, StatusSection, WorkingFile};
+
+fn index_status(status: Status) -> Option<ChangeStatus> {
+    if status.is_index_new() {
+        Some(ChangeStatus::Added)
+    } else if status.is_index_deleted() {
+        Some(ChangeStatus::Deleted)
+    } else if status.is_index_renamed() {
+        Some(ChangeStatus::Renamed)
+    } else if status.is_index_typechange() {
+        Some(ChangeStatus::TypeChanged)
+    } else if status.is_index_modified() {
+        Some(ChangeStatus::Modified)
+    } else {
+        None
+    }
+}
+
+fn worktree_status(status: Status) -> Option<ChangeStatus> {
+    if status.is_wt_deleted() {
+        Some(ChangeStatus::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(ChangeStatus::Renamed)
+    } else if status.is_wt_typechange() {
+        Some(ChangeStatus::TypeChanged)
+    } else if status.is_wt_modified() {
+        Some(ChangeStatus::Modified)
+    } else {
+        None
+    }
+}
+
+impl GitManager {
+    /// Lists staged, unstaged and untracked files, in that order
+    pub fn get_status(&self) -> Result<Vec<WorkingFile>> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+
+        let mut files = Vec::new();
+        for entry in self.repo.statuses(Some(&mut opts))?.iter() {
+            let path = match entry.path() {
+                Some(path) => path.to_string(),
+                None => continue,
+            };
+            let status = entry.status();
+            if status.is_wt_new() {
+                files.push(WorkingFile { path, section: StatusSection::Untracked, status: ChangeStatus::Added });
+                continue;
+            }
+            if let Some(change) = index_status(status) {
+                files.push(WorkingFile { path: path.clone(), section: StatusSection::Staged, status: change });
+            }
+            if let Some(change) = worktree_status(status) {
+                files.push(WorkingFile { path, section: StatusSection::Unstaged, status: change });
+            }
+        }
+
+        files.sort_by(|a, b| a.section.cmp(&b.section).then_with(|| a.path.cmp(&b.path)));
+        Ok(files)
+    }
+
+    /// Whether any tracked file has staged or unstaged changes
+    pub fn is_dirty(&self) -> Result<bool> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(false);
+        Ok(!self.repo.statuses(Some(&mut opts))?.is_empty())
+    }
+
+    /// The patch for one entry of the status list: HEAD against the index
+    /// for staged changes, the index against the working tree otherwise
+    pub fn working_diff(&self, file: &WorkingFile) -> Result<String> {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(&file.path).disable_pathspec_match(true);
+
+        let diff = match file.section {
+            StatusSection::Staged => {
+                // Before the first commit everything staged is new
+                let head = self.repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+                self.repo.diff_tree_to_index(head.as_ref(), None, Some(&mut opts))?
+            },
+            StatusSection::Unstaged => self.repo.diff_index_to_workdir(None, Some(&mut opts))?,
+            StatusSection::Untracked => {
+                opts.include_untracked(true).show_untracked_content(true);
+                self.repo.diff_index_to_workdir(None, Some(&mut opts))?
+            },
+        };
+        diff::patch_text(&diff)
+    }
+
+    /// Adds the working tree version of the file to the index, like `git add`
+    pub fn stage(&self, file: &WorkingFile) -> Result<()> {
+        let mut index = self.repo.index()?;
+        let path = Path::new(&file.path);
+        if file.status == ChangeStatus::Deleted {
+            index.remove_path(path)?;
+        } else {
+            index.add_path(path)?;
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// Applies a single hunk of the file's diff to the index: an unstaged
+    /// hunk is staged, and a staged hunk is reverse-applied to unstage it
+    pub fn apply_hunk(&self, file: &WorkingFile, hunk: usize) -> Result<()> {
+        let mut opts = DiffOptions::new();
+        opts.pathspec(&file.path).disable_pathspec_match(true);
+
+        let diff = match file.section {
+            StatusSection::Staged => {
+                let head = self.repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+                // Reversed, the diff turns the index back into HEAD
+                opts.reverse(true);
+                self.repo.diff_tree_to_index(head.as_ref(), None, Some(&mut opts))?
+            },
+            StatusSection::Unstaged => self.repo.diff_index_to_workdir(None, Some(&mut opts))?,
+            // A new file is a single hunk, so stage all of it
+            StatusSection::Untracked => return self.stage(file),
+        };
+
+        // The hunk callback sees the hunks in patch order; skip all but one
+        let mut seen = 0;
+        let mut apply = ApplyOptions::new();
+        apply.hunk_callback(|_| {
+            seen += 1;
+            seen - 1 == hunk
+        });
+        self.repo.apply(&diff, ApplyLocation::Index, Some(&mut apply))?;
+        Ok(())
+    }
+
+    /// Puts the file's index entry back to what HEAD has, like `git reset -- path`
+    pub fn unstage(&self, file: &WorkingFile) -> Result<()> {
+        match self.repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(head) => self.repo.reset_default(Some(head.as_object()), [&file.path])?,
+            // With no commits yet, unstaging just drops the file from the index
+            Err(_) => {
+                let mut index = self.repo.index()?;
+                index.remove_path(Path::new(&file.path))?;
+                index.write()?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Message of the commit HEAD points at, to start an amend from
+    pub fn head_message(&self) -> Option<String> {
+        let head = self.repo.head().ok()?.peel_to_commit().ok()?;
+        head.message().map(|m| m.trim_end().to_string())
+    }
+
+    /// Commits the index as the configured user, or rewrites HEAD with it
+    /// when amending. With `sign_off` a Signed-off-by trailer is added.
+    pub fn create_commit(&self, message: &str, amend: bool, sign_off: bool) -> Result<git2::Oid> {
+        let signature = self.repo.signature()?;
+        let mut message = message.trim_end().to_string();
+        if message.is_empty() {
+            anyhow::bail!("the commit message is empty");
+        }
+        if sign_off {
+            let trailer = format!(
+                "Signed-off-by: {} <{}>",
+                signature.name().unwrap_or(""),
+                signature.email().unwrap_or("")
+            );
+            if !message.contains(&trailer) {
+                message.push_str("\n\n");
+                message.push_str(&trailer);
+            }
+        }
+        message.push('\n');
+
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let head = self.repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+        let oid = match (amend, head) {
+            // Amending keeps the original author and takes the new committer
+            (true, Some(head)) => head.amend(Some("HEAD"), None, Some(&signature), None, Some(&message), Some(&tree))?,
+            (true, None) => anyhow::bail!("there is no commit to amend"),
+            (false, head) => {
+                let parents: Vec<&git2::Commit> = head.iter().collect();
+                self.repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?
+            },
+        };
+        Ok(oid)
+    }
+
+    /// Throws away unstaged changes to the file, or deletes it if untracked
+    pub fn discard(&self, file: &WorkingFile) -> Result<()> {
+        match file.section {
+            StatusSection::Untracked => {
+                std::fs::remove_file(self.root().join(&file.path))?;
+            },
+            StatusSection::Unstaged => {
+                let mut checkout = CheckoutBuilder::new();
+                checkout.force().path(&file.path);
+                self.repo.checkout_index(None, Some(&mut checkout))?;
+            },
+            StatusSection::Staged => anyhow::bail!("unstage {} before discarding it", file.path),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use git2::Oid;
+use super::GitManager;
+
+impl GitManager {
+    /// Tags `hash`: annotated with the configured user as tagger when a
+    /// message is given, lightweight otherwise
+    pub fn create_tag(&self, name: &str, hash: &str, message: Option<&str>) -> Result<()> {
+        let target = self.repo.find_object(Oid::from_str(hash)?, None)?;
+        match message {
+            Some(message) => {
+                let tagger = self.repo.signature()?;
+                self.repo.tag(name, &target, &tagger, message, false)?;
+            },
+            None => {
+                self.repo.tag_lightweight(name, &target, false)?;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<()> {
+        self.repo.tag_delete(name)?;
+        Ok(())
+    }
+}
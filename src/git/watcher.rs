@@ -0,0 +1,72 @@
+use anyhow::Result;
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+// Git touches several files per operation, so wait for them to settle
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Watches HEAD and the refs of a repository so the view can reload when a
+/// commit, checkout or fetch happens in another terminal
+pub struct RepoWatcher {
+    // Dropping the watcher stops it, so it has to be kept alive
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    git_dir: PathBuf,
+    last_change: Option<Instant>,
+}
+
+impl RepoWatcher {
+    pub fn new(git_dir: &Path) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        // HEAD and packed-refs are replaced by renames rather than written in
+        // place, so watch the directory holding them instead of the files
+        watcher.watch(git_dir, RecursiveMode::NonRecursive)?;
+        watcher.watch(&git_dir.join("refs"), RecursiveMode::Recursive)?;
+
+        Ok(RepoWatcher {
+            _watcher: watcher,
+            events: rx,
+            git_dir: git_dir.to_path_buf(),
+            last_change: None,
+        })
+    }
+
+    fn is_ref_path(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.git_dir) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        // Lock files come and go while git writes the real ref
+        if relative.extension().is_some_and(|ext| ext == "lock") {
+            return false;
+        }
+        relative == Path::new("HEAD") || relative == Path::new("packed-refs") || relative.starts_with("refs")
+    }
+
+    /// Returns true once the refs have changed and stayed quiet for a moment
+    pub fn poll(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            if let Ok(event) = event {
+                // Reading the refs, as reloading does, isn't a change
+                if matches!(event.kind, EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write)) {
+                    continue;
+                }
+                if event.paths.iter().any(|path| self.is_ref_path(path)) {
+                    self.last_change = Some(Instant::now());
+                }
+            }
+        }
+
+        match self.last_change {
+            Some(changed) if changed.elapsed() >= SETTLE_TIME => {
+                self.last_change = None;
+                true
+            },
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::forge;
+use crate::models::{PullLookup, PullRequest};
+
+// How long to stop asking GitHub after failing to reach it
+const OFFLINE_RETRY: Duration = Duration::from_secs(60);
+
+/// A repository hosted on GitHub or GitHub Enterprise
+#[derive(Debug, Clone)]
+pub struct GitHubRepo {
+    api: String,
+    // "owner/name"
+    path: String,
+}
+
+impl GitHubRepo {
+    /// Recognises the remote URLs of github.com and of the GitHub Enterprise
+    /// hosts listed in the config. No other host is asked, so the token is
+    /// only ever sent to these.
+    pub fn from_remote(url: &str, enterprise_hosts: &[String]) -> Option<GitHubRepo> {
+        let (host, path) = forge::host_and_path(url)?;
+        let api = match host.as_str() {
+            "github.com" => "https://api.github.com".to_string(),
+            host if enterprise_hosts.iter().any(|listed| listed.eq_ignore_ascii_case(host)) => format!("https://{}/api/v3", host),
+            _ => return None,
+        };
+        Some(GitHubRepo { api, path })
+    }
+
+    /// `$XDG_CACHE_HOME/git-visualiser/pulls/owner-name.json`, falling back
+    /// to `~/.cache`
+    fn cache_path(&self) -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+        let name = format!("{}.json", self.path.replace('/', "-"));
+        Some(base.join("git-visualiser").join("pulls").join(name))
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiPull {
+    number: u64,
+    title: String,
+    state: String,
+    merged_at: Option<String>,
+    html_url: String,
+}
+
+enum FetchError {
+    // No answer at all, as when offline
+    Unreachable(String),
+    Failed(String),
+}
+
+/// Asks GitHub which pull requests contain the commit, preferring the one
+/// that merged it
+fn fetch_pull(agent: &ureq::Agent, repo: &GitHubRepo, token: Option<&str>, hash: &str) -> Result<Option<PullRequest>, FetchError> {
+    let url = format!("{}/repos/{}/commits/{}/pulls", repo.api, repo.path, hash);
+    let mut request = agent
+        .get(&url)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "git-visualiser");
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    let pulls: Vec<ApiPull> = match request.call() {
+        Ok(response) => response.into_json().map_err(|e| FetchError::Failed(e.to_string()))?,
+        // GitHub doesn't know commits that haven't been pushed
+        Err(ureq::Error::Status(422, _)) => return Ok(None),
+        Err(ureq::Error::Status(code, response)) => {
+            return Err(FetchError::Failed(format!("GitHub answered {} {}", code, response.status_text())));
+        },
+        Err(ureq::Error::Transport(e)) => return Err(FetchError::Unreachable(e.to_string())),
+    };
+    let pull = pulls
+        .iter()
+        .find(|pull| pull.merged_at.is_some())
+        .or(pulls.first());
+    Ok(pull.map(|pull| PullRequest {
+        number: pull.number,
+        title: pull.title.clone(),
+        state: if pull.merged_at.is_some() { "merged".to_string() } else { pull.state.clone() },
+        url: pull.html_url.clone(),
+    }))
+}
+
+fn load_cache(path: Option<&PathBuf>) -> HashMap<String, PullRequest> {
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(path: Option<&PathBuf>, cache: &HashMap<String, PullRequest>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => bail!("No cache directory"),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(cache)?).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub struct PullRequestEvent {
+    pub hash: String,
+    pub lookup: PullLookup,
+}
+
+/// Looks up pull requests on a background thread. Pull requests found are
+/// saved to disk, and shown from there when GitHub can't be reached.
+pub struct PullRequestWorker {
+    requests: Sender<String>,
+    events: Receiver<PullRequestEvent>,
+}
+
+impl PullRequestWorker {
+    pub fn spawn(repo: GitHubRepo, token: Option<String>) -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<String>();
+        let (event_tx, event_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build();
+            let cache_path = repo.cache_path();
+            let mut cache = load_cache(cache_path.as_ref());
+            let mut offline_until: Option<Instant> = None;
+
+            while let Ok(mut hash) = request_rx.recv() {
+                // Only the latest request matters when the selection moved on
+                while let Ok(newer) = request_rx.try_recv() {
+                    hash = newer;
+                }
+                let offline = offline_until.is_some_and(|until| Instant::now() < until);
+                let result = if offline {
+                    Err("GitHub is unreachable".to_string())
+                } else {
+                    match fetch_pull(&agent, &repo, token.as_deref(), &hash) {
+                        Ok(pull) => Ok(pull),
+                        Err(FetchError::Failed(e)) => Err(e),
+                        Err(FetchError::Unreachable(e)) => {
+                            offline_until = Some(Instant::now() + OFFLINE_RETRY);
+                            Err(format!("GitHub is unreachable: {}", e))
+                        },
+                    }
+                };
+                let lookup = match result {
+                    Ok(pull) => {
+                        if let Some(pull) = &pull {
+                            cache.insert(hash.clone(), pull.clone());
+                            let _ = save_cache(cache_path.as_ref(), &cache);
+                        }
+                        PullLookup::Found(pull)
+                    },
+                    Err(e) => match cache.get(&hash) {
+                        Some(pull) => PullLookup::Cached(pull.clone()),
+                        None => PullLookup::Failed(e),
+                    },
+                };
+                if event_tx.send(PullRequestEvent { hash, lookup }).is_err() {
+                    break;
+                }
+            }
+        });
+
+        PullRequestWorker { requests: request_tx, events: event_rx }
+    }
+
+    pub fn request(&self, hash: &str) {
+        let _ = self.requests.send(hash.to_string());
+    }
+
+    /// Returns the next finished lookup, if any, without blocking
+    pub fn try_recv(&self) -> Option<PullRequestEvent> {
+        match self.events.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+mod pixels;
+mod sixel;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ratatui::layout::Rect;
+use std::env;
+use std::io::{self, Write};
+use crate::config::ImageProtocol;
+
+/// Images bigger than this are described but never drawn, as they'd have
+/// to be sent to the terminal whole every time they're placed
+pub const MAX_DRAWN_SIZE: usize = 8 * 1024 * 1024;
+
+/// Cell size assumed when the terminal won't say, in pixels
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+/// A way of drawing images a terminal understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Kitty,
+    Iterm,
+    Sixel,
+}
+
+/// The protocol to draw images with, if any, going by the setting or else
+/// by what the environment says the terminal is
+pub fn detect(setting: ImageProtocol) -> Option<Protocol> {
+    match setting {
+        ImageProtocol::Kitty => return Some(Protocol::Kitty),
+        ImageProtocol::Iterm => return Some(Protocol::Iterm),
+        ImageProtocol::Sixel => return Some(Protocol::Sixel),
+        ImageProtocol::None => return None,
+        ImageProtocol::Auto => {},
+    }
+    // Multiplexers swallow or misplace the escape sequences
+    if env::var_os("TMUX").is_some() || env::var("TERM").is_ok_and(|term| term.starts_with("screen")) {
+        return None;
+    }
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    if env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || program == "ghostty" {
+        Some(Protocol::Kitty)
+    } else if program == "iTerm.app" || program == "WezTerm" {
+        Some(Protocol::Iterm)
+    } else if term.starts_with("foot") || term == "mlterm" || term.contains("sixel") {
+        Some(Protocol::Sixel)
+    } else {
+        None
+    }
+}
+
+/// What an image's header says about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Whether the path looks like an image going by its extension
+pub fn is_image(path: &str) -> bool {
+    let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    matches!(extension.as_str(), "png" | "gif" | "jpg" | "jpeg" | "bmp" | "webp")
+}
+
+/// Reads the format and dimensions from the header of an image
+pub fn probe(bytes: &[u8]) -> Option<ImageInfo> {
+    let be16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32);
+    let le16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let be32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let le32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let info = |format, width, height| Some(ImageInfo { format, width, height });
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        info("PNG", be32(16)?, be32(20)?)
+    } else if bytes.starts_with(b"GIF8") {
+        info("GIF", le16(6)?, le16(8)?)
+    } else if bytes.starts_with(b"BM") {
+        // Bottom-up bitmaps have a negative height
+        info("BMP", le32(18)?, (le32(22)? as i32).unsigned_abs())
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        match bytes.get(12..16)? {
+            b"VP8 " => info("WebP", le16(26)? & 0x3fff, le16(28)? & 0x3fff),
+            b"VP8L" => {
+                let bits = le32(21)?;
+                info("WebP", (bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1)
+            },
+            b"VP8X" => {
+                let size = |at: usize| Some(le32(at)? & 0xff_ffff);
+                info("WebP", size(24)? + 1, size(27)? + 1)
+            },
+            _ => None,
+        }
+    } else if bytes.starts_with(b"\xff\xd8") {
+        // The size is in the first start-of-frame segment
+        let mut at = 2;
+        while *bytes.get(at)? == 0xff {
+            let marker = *bytes.get(at + 1)?;
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                return info("JPEG", be16(at + 7)?, be16(at + 5)?);
+            }
+            at += 2 + be16(at + 2)? as usize;
+        }
+        None
+    } else {
+        None
+    }
+}
+
+/// Whether the image can be drawn with the protocol, as opposed to only
+/// described
+pub fn can_draw(protocol: Protocol, bytes: &[u8]) -> bool {
+    let format = match probe(bytes) {
+        Some(info) if bytes.len() <= MAX_DRAWN_SIZE => info.format,
+        _ => return false,
+    };
+    // Kitty is only sent PNGs and sixels are only made from them
+    protocol == Protocol::Iterm || format == "PNG"
+}
+
+/// The terminal's cell size in pixels
+fn cell_size() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width as u32 / size.columns as u32, size.height as u32 / size.rows as u32)
+        },
+        _ => (CELL_WIDTH, CELL_HEIGHT),
+    }
+}
+
+/// The size in pixels to draw an image at so it fits the area without
+/// being stretched or made bigger than it is
+fn fit(info: ImageInfo, area: Rect, cell: (u32, u32)) -> (u32, u32) {
+    let (max_width, max_height) = (area.width as u32 * cell.0, area.height as u32 * cell.1);
+    let scale = (max_width as f64 / info.width as f64).min(max_height as f64 / info.height as f64).min(1.0);
+    (((info.width as f64 * scale) as u32).max(1), ((info.height as f64 * scale) as u32).max(1))
+}
+
+/// The escape sequence drawing the image in the area from its top-left
+/// corner, where the cursor should be
+pub fn encode(protocol: Protocol, bytes: &[u8], area: Rect) -> Option<String> {
+    let info = probe(bytes)?;
+    let cell = cell_size();
+    let (width, height) = fit(info, area, cell);
+    let (columns, rows) = (width.div_ceil(cell.0).max(1), height.div_ceil(cell.1).max(1));
+    match protocol {
+        Protocol::Kitty => {
+            // Sent in chunks, each but the last saying more follow
+            let data = STANDARD.encode(bytes);
+            let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+            let mut out = String::new();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = (i + 1 < chunks.len()) as u8;
+                let chunk = std::str::from_utf8(chunk).ok()?;
+                if i == 0 {
+                    out.push_str(&format!("\x1b_Ga=T,f=100,c={},r={},q=2,C=1,m={};{}\x1b\\", columns, rows, more, chunk));
+                } else {
+                    out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+                }
+            }
+            Some(out)
+        },
+        Protocol::Iterm => Some(format!(
+            "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+            bytes.len(),
+            columns,
+            rows,
+            STANDARD.encode(bytes)
+        )),
+        Protocol::Sixel => {
+            let pixels = pixels::decode(bytes)?;
+            Some(sixel::encode(&pixels, width as usize, height as usize))
+        },
+    }
+}
+
+/// Draws images over what ratatui has drawn, keeping track of where they
+/// are so they're only sent again once they move or change
+pub struct ImageRenderer {
+    pub protocol: Protocol,
+    // Each image's key and where it was drawn
+    shown: Vec<(String, Rect)>,
+}
+
+impl ImageRenderer {
+    pub fn new(protocol: Protocol) -> Self {
+        ImageRenderer { protocol, shown: Vec::new() }
+    }
+
+    /// Whether the images drawn are the ones that should be
+    pub fn is_showing(&self, placements: &[(String, Rect, &[u8])]) -> bool {
+        self.shown.len() == placements.len()
+            && self.shown.iter().zip(placements).all(|(shown, (key, area, _))| shown.0 == *key && shown.1 == *area)
+    }
+
+    /// Whether some images are drawn that only a full redraw would remove
+    pub fn needs_redraw(&self, placements: &[(String, Rect, &[u8])]) -> bool {
+        self.protocol != Protocol::Kitty && !self.shown.is_empty() && !self.is_showing(placements)
+    }
+
+    /// Forgets the images, once the screen they were on has been cleared
+    pub fn forget(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Replaces the images drawn with the ones placed
+    pub fn show(&mut self, out: &mut impl Write, placements: &[(String, Rect, &[u8])]) -> io::Result<()> {
+        if self.is_showing(placements) {
+            return Ok(());
+        }
+        if self.protocol == Protocol::Kitty && !self.shown.is_empty() {
+            // Kitty images sit on a layer of their own, so go on their own
+            write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        self.shown.clear();
+        for (key, area, bytes) in placements {
+            if let Some(sequence) = encode(self.protocol, bytes, *area) {
+                crossterm::queue!(out, crossterm::cursor::MoveTo(area.x, area.y))?;
+                out.write_all(sequence.as_bytes())?;
+            }
+            self.shown.push((key.clone(), *area));
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,44 @@
+use png::{ColorType, Decoder, Transformations};
+
+/// The largest image decoded, in pixels, so a huge one can't eat the memory
+const MAX_PIXELS: usize = 16 * 1024 * 1024;
+
+/// Decoded pixels, four bytes of red, green, blue and alpha each, row by row
+pub struct Pixels {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Pixels {
+    pub fn get(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [self.rgba[i], self.rgba[i + 1], self.rgba[i + 2], self.rgba[i + 3]]
+    }
+}
+
+/// Decodes a PNG of any colour type and bit depth, or None for a corrupt
+/// one or one too big to hold
+pub fn decode(bytes: &[u8]) -> Option<Pixels> {
+    let mut decoder = Decoder::new(bytes);
+    // Palettes and small depths come out as 8 bits a channel, 16 bits keep their high byte
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let (width, height) = (reader.info().width as usize, reader.info().height as usize);
+    if width == 0 || height == 0 || width.checked_mul(height)? > MAX_PIXELS {
+        return None;
+    }
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).ok()?;
+    let samples = &buffer[..frame.buffer_size()];
+    let rgba = match frame.color_type {
+        ColorType::Rgba => samples.to_vec(),
+        ColorType::Rgb => samples.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => samples.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        ColorType::Grayscale => samples.iter().flat_map(|&grey| [grey, grey, grey, 255]).collect(),
+        // Expanding turns palettes into colours
+        ColorType::Indexed => return None,
+    };
+    Some(Pixels { width, height, rgba })
+}
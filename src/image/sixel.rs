@@ -0,0 +1,56 @@
+use super::pixels::Pixels;
+
+/// Encodes the pixels as a sixel image `width` by `height` pixels, scaled
+/// to fit and reduced to a 6x6x6 colour cube. Transparent parts are laid
+/// over black.
+pub fn encode(pixels: &Pixels, width: usize, height: usize) -> String {
+    let level = |value: u8| (value as usize * 5 + 127) / 255;
+    let colour = |x: usize, y: usize| {
+        let [r, g, b, a] = pixels.get(x * pixels.width / width, y * pixels.height / height);
+        let blend = |value: u8| (value as usize * a as usize / 255) as u8;
+        level(blend(r)) * 36 + level(blend(g)) * 6 + level(blend(b))
+    };
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for index in 0..216 {
+        let percent = |level: usize| level * 20;
+        out.push_str(&format!("#{};2;{};{};{}", index, percent(index / 36), percent(index / 6 % 6), percent(index % 6)));
+    }
+
+    // Each band is six rows, drawn once per colour it uses
+    let mut band = vec![0usize; width * 6];
+    for top in (0..height).step_by(6) {
+        let rows = (height - top).min(6);
+        let mut used = [false; 216];
+        for row in 0..rows {
+            for x in 0..width {
+                let index = colour(x, top + row);
+                band[row * width + x] = index;
+                used[index] = true;
+            }
+        }
+        for (index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            out.push_str(&format!("#{}", index));
+            let bits = |x: usize| (0..rows).filter(|&row| band[row * width + x] == index).fold(0, |bits, row| bits | 1 << row);
+            let mut x = 0;
+            while x < width {
+                let value = bits(x);
+                let mut run = 1;
+                while x + run < width && bits(x + run) == value {
+                    run += 1;
+                }
+                let symbol = char::from(63 + value as u8);
+                if run > 3 {
+                    out.push_str(&format!("!{}{}", run, symbol));
+                } else {
+                    out.extend(std::iter::repeat_n(symbol, run));
+                }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
@@ -0,0 +1,362 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use crate::config::KeymapConfig;
+
+/// Everything a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Confirm,
+    Back,
+    Search,
+    NextMatch,
+    PrevMatch,
+    AuthorFilter,
+    TypeFilter,
+    BranchSelector,
+    TagSelector,
+    ToggleDiff,
+    ToggleSplitDiff,
+    Refresh,
+    NextPane,
+    PrevPane,
+    ShrinkList,
+    GrowList,
+    ToggleMaximise,
+    MessageLog,
+    FileHistory,
+    Blame,
+    ViewFile,
+    FileTree,
+    Status,
+    Stage,
+    Discard,
+    Commit,
+    StageHunk,
+    Stashes,
+    Pop,
+    Reflog,
+    Mark,
+    CherryPick,
+    Revert,
+    Checkout,
+    Tag,
+    Fetch,
+    Pull,
+    Push,
+    Compare,
+    RangeSelect,
+    Parent,
+    MergeParent,
+    Child,
+    MergeDiff,
+    DateFormat,
+    Stats,
+    Export,
+    Changelog,
+    YankHash,
+    YankShortHash,
+    YankMessage,
+    YankPatch,
+    OpenInBrowser,
+    Bisect,
+    Pickaxe,
+    Grep,
+    DateFilter,
+    PathFilter,
+    Filters,
+    ToggleMerges,
+    FirstParent,
+    MarkedDiff,
+    FormatPatch,
+    ApplyPatch,
+    RepoSelector,
+    Unshallow,
+    Columns,
+    ScrollLeft,
+    ScrollRight,
+    WrapDiff,
+    Theme,
+    Debug,
+    FullDiff,
+    RenameDetection,
+    IgnoreWhitespace,
+    IgnoreWhitespaceChange,
+    IgnoreBlankLines,
+    LessContext,
+    MoreContext,
+    FullFileContext,
+}
+
+impl Action {
+    /// Whether the action reads or changes the working tree or index,
+    /// which a bare repository doesn't have
+    pub fn needs_work_tree(self) -> bool {
+        matches!(
+            self,
+            Action::Status
+                | Action::Discard
+                | Action::Commit
+                | Action::StageHunk
+                | Action::Stashes
+                | Action::Pop
+                | Action::CherryPick
+                | Action::Revert
+                | Action::Checkout
+                | Action::Pull
+                | Action::ApplyPatch
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Default,
+    Vim,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character for printable keys
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Key { code, modifiers }
+    }
+
+    fn from_event(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+
+    fn parse(name: &str) -> Option<Key> {
+        let (modifiers, name) = if let Some(rest) = name.strip_prefix("ctrl-") {
+            (KeyModifiers::CONTROL, rest)
+        } else if let Some(rest) = name.strip_prefix("alt-") {
+            (KeyModifiers::ALT, rest)
+        } else {
+            (KeyModifiers::NONE, name)
+        };
+
+        let code = match name {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "pgup" => KeyCode::PageUp,
+            "pgdn" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return None,
+                }
+            },
+        };
+        Some(Key::new(code, modifiers))
+    }
+}
+
+/// Parses a binding such as `q`, `ctrl-c`, `pgdn` or the sequence `gg`
+fn parse_binding(binding: &str) -> Option<Vec<Key>> {
+    if let Some(key) = Key::parse(binding) {
+        return Some(vec![key]);
+    }
+    // Anything else is a sequence of plain characters pressed one after another
+    if binding.chars().all(|c| !c.is_whitespace()) {
+        return Some(binding.chars().map(|c| Key::new(KeyCode::Char(c), KeyModifiers::NONE)).collect());
+    }
+    None
+}
+
+fn default_bindings(profile: Profile) -> Vec<(&'static str, Action)> {
+    let mut bindings = vec![
+        ("q", Action::Quit),
+        ("up", Action::Up),
+        ("down", Action::Down),
+        ("left", Action::Left),
+        ("right", Action::Right),
+        ("pgup", Action::PageUp),
+        ("pgdn", Action::PageDown),
+        ("home", Action::First),
+        ("end", Action::Last),
+        ("enter", Action::Confirm),
+        ("esc", Action::Back),
+        ("/", Action::Search),
+        ("n", Action::NextMatch),
+        ("N", Action::PrevMatch),
+        ("a", Action::AuthorFilter),
+        ("!", Action::TypeFilter),
+        ("b", Action::BranchSelector),
+        ("t", Action::TagSelector),
+        ("d", Action::ToggleDiff),
+        ("|", Action::ToggleSplitDiff),
+        ("r", Action::Refresh),
+        ("tab", Action::NextPane),
+        ("backtab", Action::PrevPane),
+        ("<", Action::ShrinkList),
+        (">", Action::GrowList),
+        ("z", Action::ToggleMaximise),
+        ("e", Action::MessageLog),
+        ("f", Action::FileHistory),
+        ("B", Action::Blame),
+        ("v", Action::ViewFile),
+        ("F", Action::FileTree),
+        ("s", Action::Status),
+        ("space", Action::Stage),
+        ("x", Action::Discard),
+        ("c", Action::Commit),
+        ("H", Action::StageHunk),
+        ("w", Action::Stashes),
+        ("p", Action::Pop),
+        ("g", Action::Reflog),
+        ("m", Action::Mark),
+        ("C", Action::CherryPick),
+        ("u", Action::Revert),
+        ("K", Action::Checkout),
+        ("T", Action::Tag),
+        ("ctrl-f", Action::Fetch),
+        ("L", Action::Pull),
+        ("P", Action::Push),
+        ("=", Action::Compare),
+        ("V", Action::RangeSelect),
+        ("[", Action::Parent),
+        ("{", Action::MergeParent),
+        ("]", Action::Child),
+        ("M", Action::MergeDiff),
+        ("D", Action::DateFormat),
+        ("S", Action::Stats),
+        ("E", Action::Export),
+        ("W", Action::Changelog),
+        ("yy", Action::YankHash),
+        ("ys", Action::YankShortHash),
+        ("ym", Action::YankMessage),
+        ("yp", Action::YankPatch),
+        ("o", Action::OpenInBrowser),
+        ("I", Action::Bisect),
+        ("ctrl-s", Action::Pickaxe),
+        ("ctrl-g", Action::Grep),
+        ("ctrl-t", Action::DateFilter),
+        ("ctrl-p", Action::PathFilter),
+        ("&", Action::Filters),
+        ("^", Action::ToggleMerges),
+        ("~", Action::FirstParent),
+        ("+", Action::MarkedDiff),
+        ("ctrl-e", Action::FormatPatch),
+        ("A", Action::ApplyPatch),
+        ("O", Action::RepoSelector),
+        ("U", Action::Unshallow),
+        ("#", Action::Columns),
+        ("(", Action::ScrollLeft),
+        (")", Action::ScrollRight),
+        ("ctrl-w", Action::WrapDiff),
+        ("%", Action::Theme),
+        ("`", Action::Debug),
+        ("ctrl-l", Action::FullDiff),
+        ("R", Action::RenameDetection),
+        ("alt-w", Action::IgnoreWhitespace),
+        ("alt-b", Action::IgnoreWhitespaceChange),
+        ("alt-l", Action::IgnoreBlankLines),
+        (",", Action::LessContext),
+        (".", Action::MoreContext),
+        ("alt-f", Action::FullFileContext),
+    ];
+    if profile == Profile::Vim {
+        bindings.extend([
+            ("k", Action::Up),
+            ("j", Action::Down),
+            ("h", Action::Left),
+            ("l", Action::Right),
+            ("ctrl-u", Action::PageUp),
+            ("ctrl-d", Action::PageDown),
+            ("gg", Action::First),
+            ("G", Action::Last),
+        ]);
+        // A lone `g` would fire before `gg` could be typed
+        bindings.retain(|(binding, _)| *binding != "g");
+        bindings.push(("gr", Action::Reflog));
+    }
+    bindings
+}
+
+/// Translates key presses into actions, including multi-key sequences
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+    pending: Vec<Key>,
+}
+
+impl Keymap {
+    pub fn new(profile: Profile) -> Self {
+        let bindings = default_bindings(profile)
+            .into_iter()
+            .filter_map(|(binding, action)| Some((parse_binding(binding)?, action)))
+            .collect();
+        Keymap { bindings, pending: Vec::new() }
+    }
+
+    /// Starts from the configured profile, then replaces the keys of every
+    /// action the config lists
+    pub fn from_config(config: &KeymapConfig) -> Result<Self> {
+        let mut keymap = Keymap::new(config.profile);
+        for (action, bindings) in &config.bindings {
+            keymap.bindings.retain(|(_, a)| a != action);
+            for binding in bindings {
+                let keys = parse_binding(binding)
+                    .ok_or_else(|| anyhow!("Unknown key '{}' for action {:?}", binding, action))?;
+                keymap.bindings.push((keys, *action));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Feeds a key press in, returning an action once a binding completes
+    pub fn handle(&mut self, event: KeyEvent) -> Option<Action> {
+        self.pending.push(Key::from_event(event));
+
+        if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| *keys == self.pending) {
+            self.pending.clear();
+            return Some(*action);
+        }
+        if self.bindings.iter().any(|(keys, _)| keys.starts_with(&self.pending)) {
+            return None;
+        }
+
+        // The sequence went nowhere, so treat the latest key on its own
+        let key = self.pending.pop();
+        self.pending.clear();
+        let key = key?;
+        if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| keys.as_slice() == [key]) {
+            return Some(*action);
+        }
+        if self.bindings.iter().any(|(keys, _)| keys.len() > 1 && keys[0] == key) {
+            self.pending.push(key);
+        }
+        None
+    }
+}
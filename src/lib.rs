@@ -0,0 +1,21 @@
+//! The history and diff engine behind git-visualiser, for other tools to
+//! build on.
+//!
+//! [`RepoExplorer`] opens a repository and reads its branches, tags and
+//! statistics. Its history comes as a [`CommitStream`], which loads commits
+//! a page at a time as it's iterated, so a caller that only needs the latest
+//! few never walks the whole branch. A commit's changes, or those of a range
+//! of commits, come as a [`DiffModel`]: the patch text and each changed
+//! file's hunks and lines.
+//!
+//! Everything is read through [`git::GitManager`], which caches commits,
+//! branches and statistics between calls; [`RepoExplorer::manager`] gives
+//! access to the rest of what it can do, from blame to cherry-picks.
+
+mod cache;
+mod explorer;
+pub mod git;
+pub mod models;
+pub mod pathspec;
+
+pub use self::explorer::{CommitStream, DiffModel, RepoExplorer};
@@ -1,176 +1,2401 @@
 mod ui;
-mod git;
-mod models;
+mod changelog;
+mod cli;
+mod clipboard;
+mod commands;
+mod config;
+mod conventional;
+mod export;
+mod forge;
+mod github;
+mod image;
+mod keymap;
+mod recent;
+mod terminal;
+mod workspace;
 
 use anyhow::{Result, Context};
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{
-    backend::CrosstermBackend,
-    Terminal,
-};
-use std::io;
-use std::collections::VecDeque;
+use git_visualiser::{git, models, pathspec};
+use clap::Parser;
+use regex::RegexBuilder;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
-use crate::ui::App;
-use crate::git::GitManager;
+use crate::cli::Args;
+use crate::config::Config;
+use crate::export::ExportFormat;
+use crate::forge::Forge;
+use crate::github::{GitHubRepo, PullRequestWorker};
+use crate::image::ImageRenderer;
+use crate::terminal::TerminalGuard;
+use crate::keymap::{Action, Keymap};
+use crate::pathspec::PathFilter;
+use crate::ui::{App, CommitDialog, CompareView, DialogEvent, FilterKind, Focus, FileView, GrepView, ImagePreview, ImageSide, Level, LineHistoryView, PendingAction, Prompt, PromptAction, ReflogView, StashView, StatsPage, StatsView, StatusView, Theme, TreeView};
+use crate::git::{GitManager, PickOutcome, COMMIT_PAGE_SIZE};
+use crate::models::{Bisect, BisectStep, ChangeStatus, CommitDiff, CommitInfo, DiffInfo, FilterSet, RefLabel, HeadState, MergeView, PickaxeResults, PullLookup, SearchResults, StatusSection};
+use crate::git::loader::CommitLoader;
+use crate::git::remote::{RemoteEvent, RemoteOp, RemoteTask};
+use crate::git::history_search::{HistorySearch, SearchEvent};
+use crate::git::pickaxe::PickaxeQuery;
+use crate::git::stats::StatsWorker;
+use crate::git::watcher::RepoWatcher;
 
 fn main() -> Result<()> {
-    // Setup terminal
-    enable_raw_mode().context("Failed to enable raw mode")?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-        .context("Failed to enter alternate screen")?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).context("Failed to create terminal")?;
-
-    // Initialize Git manager
-    let current_dir = std::env::current_dir().context("Failed to get current directory")?;
-    
-    if !current_dir.join(".git").exists() {
-        println!("Error: Not a Git repository. Please run this application from a Git repository.");
+    let args = Args::parse();
+    if let Some(command) = &args.command {
+        // Scripts need to see failures in the exit status
+        if let Err(e) = commands::run(command) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
         return Ok(());
     }
-    
-    let git_manager = match GitManager::new(&current_dir) {
-        Ok(manager) => manager,
+
+    let config = match Config::load() {
+        Ok(config) => config,
         Err(e) => {
-            println!("Error: Failed to open Git repository: {}", e);
+            println!("Error: {:#}", e);
             return Ok(());
         }
     };
-    
-    // Get current branch
-    let branches = match git_manager.get_branches() {
-        Ok(branches) => branches,
+    let mut keymap = match Keymap::from_config(&config.keymap) {
+        Ok(keymap) => keymap,
         Err(e) => {
-            println!("Error: Failed to get branches: {}", e);
+            println!("Error: Invalid keymap in config: {}", e);
             return Ok(());
         }
     };
-    
-    // Try to find a valid branch to use
-    let current_branch = if branches.is_empty() {
-        // Try 'main' first, then 'master' as fallback
-        if git_manager.branch_exists("main") {
-            "main".to_string()
-        } else if git_manager.branch_exists("master") {
-            "master".to_string()
-        } else {
-            println!("Error: No valid branches found in the repository.");
-            return Ok(());
+    Theme::set(config.theme);
+
+    // Every repository to switch between, the first shown at the start
+    let mut repo_paths = args.paths.clone();
+    if let Some(dir) = &args.workspace {
+        match workspace::find_repositories(dir) {
+            Ok(found) if found.is_empty() => {
+                println!("Error: No repositories found in {}", dir.display());
+                return Ok(());
+            },
+            Ok(found) => repo_paths.extend(found),
+            Err(e) => {
+                println!("Error: {:#}", e);
+                return Ok(());
+            }
         }
-    } else {
-        branches[0].clone()
-    };
-    
-    println!("Using branch: {}", current_branch);
-    
-    // Get commits
-    let commits = match git_manager.get_commits(&current_branch) {
-        Ok(commits) => {
-            if commits.is_empty() {
-                println!("No commits found in the repository.");
+    }
+    if repo_paths.is_empty() {
+        repo_paths.push(std::env::current_dir().context("Failed to get current directory")?);
+    }
+    let mut repos: Vec<std::path::PathBuf> = Vec::new();
+    for path in &repo_paths {
+        match GitManager::new(path) {
+            Ok(manager) if !repos.contains(&manager.root()) => repos.push(manager.root()),
+            Ok(_) => {},
+            Err(e) => {
+                println!("Error: Not a Git repository ({}): {}", path.display(), e);
                 return Ok(());
             }
-            commits
-        },
+        }
+    }
+
+    if let (Some(format), Some(out)) = (args.export, &args.out) {
+        let export = || -> Result<()> {
+            let mut git_manager = GitManager::new(&repos[0])?;
+            let branch = head_branch(&git_manager.head_state().context("Failed to read HEAD")?);
+            let path_filter = cli_path_filter(&git_manager, &args.pathspecs)?;
+            export_history(&mut git_manager, &branch, args.follow.as_deref(), path_filter.as_ref(), format, out)
+        };
+        // Scripts need to see failures in the exit status
+        if let Err(e) = export() {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Only the first repository is opened now, the rest when switched to
+    let mut sessions: Vec<Option<Session>> = repos.iter().map(|_| None).collect();
+    let session = match open_session(&repos, 0, &config, args.follow.as_deref(), &args.pathspecs) {
+        Ok(session) => session,
         Err(e) => {
-            println!("Error: Failed to get commits: {}", e);
+            println!("Error: {:#}", e);
             return Ok(());
         }
     };
-    
-    // Create app state
-    let mut app = App {
-        commits: VecDeque::from(commits),
-        selected_index: 0,
-        current_branch,
-        branches,
-        show_author_filter: false,
-        show_branch_selector: false,
-        branch_selector_index: 0,
-    };
+    let Session { mut app, mut git_manager, mut loader, mut watcher, mut remote_task, mut stats_worker, mut pickaxe_search, mut message_search, mut pull_worker } = session;
+
+    // Terminals without a UTF-8 locale get plain ASCII symbols
+    let ascii = args.ascii || ui::needs_ascii();
+
+    // Setup terminal
+    let mut guard = TerminalGuard::new()?;
+    let terminal = &mut guard.terminal;
+
+    // Pictures of changed image files, where the terminal can draw them
+    let mut images = image::detect(config.image_protocol).map(ImageRenderer::new);
 
     // Main loop
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(250);
 
     loop {
-        terminal.draw(|f| ui::draw_ui(f, &app)).context("Failed to draw UI")?;
+        sync_range_diff(&mut app, &git_manager);
+        sync_marked_diff(&mut app, &git_manager);
+        sync_merge_diff(&mut app, &git_manager);
+        sync_commit_diff(&mut app, &mut git_manager);
+        if app.debug.is_some() {
+            app.debug = Some(git_manager.diff_cache_stats());
+        }
+        sync_pull_request(&mut app, pull_worker.as_ref());
+        sync_image_preview(&mut app, &git_manager);
+        app.prepare_diff_view();
+        terminal
+            .draw(|f| {
+                ui::draw_ui(f, &mut app);
+                if ascii {
+                    ui::to_ascii(f.buffer_mut());
+                }
+            })
+            .context("Failed to draw UI")?;
+        if let Some(images) = images.as_mut() {
+            // Anything drawn over the main view would be hidden by pictures
+            let covered = app.popup_open() || app.commit_dialog.is_some() || app.prompt.is_some() || app.confirmation.is_some();
+            let placements = match &app.image_preview {
+                Some(preview) if !covered => preview.placements(),
+                _ => Vec::new(),
+            };
+            if images.needs_redraw(&placements) {
+                // Sixel and iTerm pictures are part of the screen, so it's
+                // cleared and drawn again to get rid of them
+                terminal.clear().context("Failed to clear the screen")?;
+                images.forget();
+                continue;
+            }
+            images.show(terminal.backend_mut(), &placements).context("Failed to draw images")?;
+        }
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if event::poll(timeout).context("Failed to poll for events")? {
-            if let Event::Key(key) = event::read().context("Failed to read event")? {
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Char('a') => app.toggle_author_filter(),
-                    KeyCode::Char('b') => app.toggle_branch_selector(),
-                    KeyCode::Up => {
-                        if app.show_branch_selector {
-                            app.navigate_branch_selector(-1);
-                        } else if app.show_author_filter {
-                            // To-do
-                        } else {
-                            // Simply navigate to the previous commit
-                            app.navigate_up();
-                        }
-                    },
-                    KeyCode::Down => {
-                        if app.show_branch_selector {
-                            app.navigate_branch_selector(1);
-                        } else if app.show_author_filter {
-                            // To-do
-                        } else {
-                            // Simply navigate to the next commit
-                            app.navigate_down();
-                        }
-                    },
-                    KeyCode::Enter => {
-                        if app.show_branch_selector {
-                            if app.select_branch(app.branch_selector_index) {
-                                // Update commits for the new branch
-                                match git_manager.get_commits(&app.current_branch) {
-                                    Ok(new_commits) => {
-                                        app.commits = VecDeque::from(new_commits);
-                                        app.selected_index = 0;
-                                    },
-                                    Err(e) => {
-                                        println!("Error: Failed to get commits for branch {}: {}", app.current_branch, e);
-                                    }
+            let event = event::read().context("Failed to read event")?;
+            if let Event::Resize(_, _) = event {
+                app.resize();
+            }
+            if let Event::Key(key) = event {
+                if let Some(confirmation) = app.confirmation.take() {
+                    // A pending question swallows the next key as its answer
+                    if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                        run_confirmed(&mut app, &mut git_manager, &loader, confirmation.action);
+                    }
+                } else if let Some(dialog) = &mut app.commit_dialog {
+                    // The commit message editor takes every key while open
+                    match dialog.handle_key(key) {
+                        DialogEvent::Submit => submit_commit(&mut app, &mut git_manager, &loader),
+                        DialogEvent::Cancel => app.commit_dialog = None,
+                        DialogEvent::ToggleAmend => toggle_amend(&mut app, &git_manager),
+                        DialogEvent::Nothing => {},
+                    }
+                } else if let Some(prompt) = &mut app.prompt {
+                    match prompt.handle_key(key) {
+                        DialogEvent::Submit => submit_prompt(&mut app, &mut git_manager, &loader),
+                        DialogEvent::Cancel => app.prompt = None,
+                        _ => {},
+                    }
+                } else if app.show_branch_selector && app.branch_typing {
+                    // Typing narrows the branches down until Enter or Esc
+                    match key.code {
+                        KeyCode::Char(c) => app.branch_query_input(c),
+                        KeyCode::Backspace => app.branch_query_backspace(),
+                        KeyCode::Up => app.navigate_branch_selector(-1),
+                        KeyCode::Down => app.navigate_branch_selector(1),
+                        KeyCode::Enter => switch_to_selected_branch(&mut app, &loader),
+                        KeyCode::Esc => app.cancel_branch_query(),
+                        _ => {},
+                    }
+                } else if app.show_branch_selector && matches!(key.code, KeyCode::Char('n' | 'd' | 'R')) {
+                    // Branch management keys only mean something in the selector
+                    handle_branch_key(&mut app, &git_manager, key.code);
+                } else if app.show_tag_selector && key.code == KeyCode::Char('d') {
+                    if let Some(tag) = app.tags.get(app.tag_selector_index) {
+                        let message = format!("Delete tag {}?", tag.name);
+                        let name = tag.name.clone();
+                        app.confirm(message, PendingAction::DeleteTag(name));
+                    }
+                } else if app.show_filter_panel && matches!(key.code, KeyCode::Char('d') | KeyCode::Delete) {
+                    let kind = app.selected_filter();
+                    app.clear_filter(kind);
+                } else if app.patch_preview.is_some() && !app.popup_open() && key.code == KeyCode::Char('i') {
+                    apply_patch(&mut app, &git_manager, git2::ApplyLocation::Index);
+                } else if app.bisect.is_some() && !app.popup_open() && matches!(key.code, KeyCode::Char('g' | 'b')) {
+                    // Marking commits takes over g and b until the bisect ends
+                    mark_bisect(&mut app, &git_manager, key.code == KeyCode::Char('g'));
+                } else if app.search_mode {
+                    // While typing a search every key goes to the search box
+                    match key.code {
+                        KeyCode::Enter => app.confirm_search(),
+                        KeyCode::Esc => app.cancel_search(),
+                        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.toggle_search_regex(),
+                        KeyCode::Backspace => app.search_backspace(),
+                        KeyCode::Up => app.navigate_up(),
+                        KeyCode::Down => app.navigate_down(),
+                        KeyCode::Char(c) => app.search_input(c),
+                        _ => {}
+                    }
+                } else if let Some(action) = keymap.handle(key) {
+                    match action {
+                        Action::Quit => break,
+                        action if app.bare && action.needs_work_tree() => {
+                            app.notify(Level::Warning, "This repository is bare, so it has no working tree to change".to_string());
+                        },
+                        // The tree browser takes over navigation while it's open
+                        Action::Up if app.tree.is_some() => tree_view(&mut app).move_cursor(-1),
+                        Action::Down if app.tree.is_some() => tree_view(&mut app).move_cursor(1),
+                        Action::Confirm | Action::Right if app.tree.is_some() => {
+                            open_tree_row(&mut app, &git_manager);
+                        },
+                        Action::Left if app.tree.is_some() => tree_view(&mut app).collapse(),
+                        Action::PageUp if app.tree.is_some() => {
+                            let page = page_height(&app);
+                            tree_view(&mut app).scroll_viewer(-page);
+                        },
+                        Action::PageDown if app.tree.is_some() => {
+                            let page = page_height(&app);
+                            tree_view(&mut app).scroll_viewer(page);
+                        },
+                        Action::First if app.tree.is_some() => tree_view(&mut app).scroll_viewer(i32::MIN),
+                        Action::Last if app.tree.is_some() => tree_view(&mut app).scroll_viewer(i32::MAX),
+                        Action::Back | Action::FileTree if app.tree.is_some() => app.tree = None,
+                        // Then the grep results, which the tree opens on top of
+                        Action::Up if app.grep.is_some() => grep_view(&mut app).move_cursor(-1),
+                        Action::Down if app.grep.is_some() => grep_view(&mut app).move_cursor(1),
+                        Action::PageUp if app.grep.is_some() => {
+                            let page = page_height(&app);
+                            grep_view(&mut app).move_cursor(-page);
+                        },
+                        Action::PageDown if app.grep.is_some() => {
+                            let page = page_height(&app);
+                            grep_view(&mut app).move_cursor(page);
+                        },
+                        Action::First if app.grep.is_some() => grep_view(&mut app).move_cursor(i32::MIN),
+                        Action::Last if app.grep.is_some() => grep_view(&mut app).move_cursor(i32::MAX),
+                        Action::Confirm | Action::Right if app.grep.is_some() => open_grep_match(&mut app, &git_manager),
+                        Action::Back | Action::Grep if app.grep.is_some() => app.grep = None,
+                        // Then the history of some lines, opened over a file or its blame
+                        Action::Up if app.line_history.is_some() => line_history(&mut app).scroll(-1),
+                        Action::Down if app.line_history.is_some() => line_history(&mut app).scroll(1),
+                        Action::PageUp if app.line_history.is_some() => {
+                            let page = page_height(&app);
+                            line_history(&mut app).scroll(-page);
+                        },
+                        Action::PageDown if app.line_history.is_some() => {
+                            let page = page_height(&app);
+                            line_history(&mut app).scroll(page);
+                        },
+                        Action::First if app.line_history.is_some() => line_history(&mut app).scroll(i32::MIN),
+                        Action::Last if app.line_history.is_some() => line_history(&mut app).scroll(i32::MAX),
+                        Action::NextMatch if app.line_history.is_some() => line_history(&mut app).jump(true),
+                        Action::PrevMatch if app.line_history.is_some() => line_history(&mut app).jump(false),
+                        Action::Confirm | Action::Right if app.line_history.is_some() => app.jump_to_line_change(),
+                        Action::Back | Action::FileHistory if app.line_history.is_some() => app.line_history = None,
+                        Action::FileTree if !app.popup_open() => open_tree(&mut app, &git_manager),
+                        // So does the status view
+                        Action::Up if app.status.is_some() => {
+                            status_view(&mut app).move_cursor(-1);
+                            load_status_diff(&mut app, &git_manager);
+                        },
+                        Action::Down if app.status.is_some() => {
+                            status_view(&mut app).move_cursor(1);
+                            load_status_diff(&mut app, &git_manager);
+                        },
+                        Action::PageUp if app.status.is_some() => {
+                            let page = page_height(&app);
+                            status_view(&mut app).scroll_diff(-page);
+                        },
+                        Action::PageDown if app.status.is_some() => {
+                            let page = page_height(&app);
+                            status_view(&mut app).scroll_diff(page);
+                        },
+                        Action::Left if app.status.is_some() => status_view(&mut app).move_hunk(-1),
+                        Action::Right if app.status.is_some() => status_view(&mut app).move_hunk(1),
+                        Action::StageHunk if app.status.is_some() => stage_hunk(&mut app, &git_manager),
+                        Action::Refresh if app.status.is_some() => open_status(&mut app, &git_manager),
+                        Action::Stage | Action::Confirm if app.status.is_some() => {
+                            toggle_staged(&mut app, &git_manager);
+                        },
+                        Action::Commit if app.status.is_some() => app.commit_dialog = Some(CommitDialog::new()),
+                        Action::Discard if app.status.is_some() => {
+                            if let Some(file) = status_view(&mut app).selected().cloned() {
+                                let message = match file.section {
+                                    StatusSection::Untracked => format!("Delete untracked file {}?", file.path),
+                                    _ => format!("Discard all unstaged changes to {}?", file.path),
+                                };
+                                app.confirm(message, PendingAction::Discard(file));
+                            }
+                        },
+                        Action::Back | Action::Status if app.status.is_some() => app.status = None,
+                        // And the stash list
+                        Action::Up if app.stash.is_some() => {
+                            stash_view(&mut app).move_cursor(-1);
+                            load_stash_diff(&mut app, &git_manager);
+                        },
+                        Action::Down if app.stash.is_some() => {
+                            stash_view(&mut app).move_cursor(1);
+                            load_stash_diff(&mut app, &git_manager);
+                        },
+                        Action::PageUp if app.stash.is_some() => {
+                            let page = page_height(&app);
+                            stash_view(&mut app).scroll_diff(-page);
+                        },
+                        Action::PageDown if app.stash.is_some() => {
+                            let page = page_height(&app);
+                            stash_view(&mut app).scroll_diff(page);
+                        },
+                        Action::Refresh if app.stash.is_some() => open_stashes(&mut app, &mut git_manager),
+                        Action::Confirm | Action::Pop | Action::Discard if app.stash.is_some() => {
+                            if let Some(entry) = stash_view(&mut app).selected() {
+                                let name = format!("stash@{{{}}}", entry.index);
+                                let (message, pending) = match action {
+                                    Action::Confirm => (format!("Apply {} to the working tree?", name), PendingAction::StashApply(entry.index)),
+                                    Action::Pop => (format!("Apply {} and drop it?", name), PendingAction::StashPop(entry.index)),
+                                    _ => (format!("Drop {}? Its changes will be lost.", name), PendingAction::StashDrop(entry.index)),
+                                };
+                                app.confirm(message, pending);
+                            }
+                        },
+                        Action::Back | Action::Stashes if app.stash.is_some() => app.stash = None,
+                        Action::Stashes if !app.popup_open() => open_stashes(&mut app, &mut git_manager),
+                        // And the reflog
+                        Action::Up if app.reflog.is_some() => reflog_view(&mut app).move_cursor(-1),
+                        Action::Down if app.reflog.is_some() => reflog_view(&mut app).move_cursor(1),
+                        Action::PageUp if app.reflog.is_some() => {
+                            let page = page_height(&app);
+                            reflog_view(&mut app).move_cursor(-page);
+                        },
+                        Action::PageDown if app.reflog.is_some() => {
+                            let page = page_height(&app);
+                            reflog_view(&mut app).move_cursor(page);
+                        },
+                        Action::Confirm if app.reflog.is_some() => {
+                            if let Some(entry) = reflog_view(&mut app).selected() {
+                                // History is loaded from the commit itself, as a detached view
+                                app.current_branch = entry.hash.clone();
+                                app.reflog = None;
+                                reload_commits(&mut app, &loader);
+                            }
+                        },
+                        Action::Back | Action::Reflog if app.reflog.is_some() => app.reflog = None,
+                        // And the comparison of two refs
+                        Action::Up if app.compare.is_some() => compare_view(&mut app).move_cursor(-1),
+                        Action::Down if app.compare.is_some() => compare_view(&mut app).move_cursor(1),
+                        Action::Left | Action::Right | Action::NextPane | Action::PrevPane if app.compare.is_some() => {
+                            compare_view(&mut app).switch_side();
+                        },
+                        Action::PageUp if app.compare.is_some() => {
+                            let page = page_height(&app);
+                            compare_view(&mut app).scroll_files(-page);
+                        },
+                        Action::PageDown if app.compare.is_some() => {
+                            let page = page_height(&app);
+                            compare_view(&mut app).scroll_files(page);
+                        },
+                        Action::Back | Action::Compare if app.compare.is_some() => app.compare = None,
+                        Action::Up if stats_page(&app) == Some(StatsPage::Churn) => stats_view(&mut app).zoom(true),
+                        Action::Down if stats_page(&app) == Some(StatsPage::Churn) => stats_view(&mut app).zoom(false),
+                        Action::Left | Action::Right if stats_page(&app) == Some(StatsPage::Churn) => {
+                            stats_view(&mut app).pan(action == Action::Right);
+                        },
+                        Action::Confirm if stats_page(&app) == Some(StatsPage::Churn) => {
+                            let window = stats_view(&mut app).window_times();
+                            app.stats = None;
+                            app.set_date_filter(window);
+                        },
+                        Action::Up if stats_page(&app) == Some(StatsPage::HotFiles) => stats_view(&mut app).move_hot_cursor(-1),
+                        Action::Down if stats_page(&app) == Some(StatsPage::HotFiles) => stats_view(&mut app).move_hot_cursor(1),
+                        Action::Left | Action::Right if stats_page(&app) == Some(StatsPage::HotFiles) => {
+                            stats_view(&mut app).change_hot_window(action == Action::Right);
+                        },
+                        Action::Confirm if stats_page(&app) == Some(StatsPage::HotFiles) => {
+                            if let Some(path) = stats_view(&mut app).selected_hot_file().map(|p| p.to_string()) {
+                                app.stats = None;
+                                app.file_history = Some(path);
+                                reload_commits(&mut app, &loader);
+                            }
+                        },
+                        Action::Up if stats_page(&app) == Some(StatsPage::Ownership) => stats_view(&mut app).ownership.move_cursor(-1),
+                        Action::Down if stats_page(&app) == Some(StatsPage::Ownership) => stats_view(&mut app).ownership.move_cursor(1),
+                        Action::Right | Action::Confirm if stats_page(&app) == Some(StatsPage::Ownership) => {
+                            stats_view(&mut app).ownership.enter();
+                        },
+                        Action::Left if stats_page(&app) == Some(StatsPage::Ownership) => stats_view(&mut app).ownership.leave(),
+                        Action::NextPane | Action::PrevPane if app.stats.is_some() => stats_view(&mut app).switch_page(),
+                        Action::Up if app.stats.is_some() => stats_view(&mut app).scroll_files(-1),
+                        Action::Down if app.stats.is_some() => stats_view(&mut app).scroll_files(1),
+                        Action::PageUp if app.stats.is_some() => {
+                            let page = page_height(&app);
+                            stats_view(&mut app).scroll_files(-page);
+                        },
+                        Action::PageDown if app.stats.is_some() => {
+                            let page = page_height(&app);
+                            stats_view(&mut app).scroll_files(page);
+                        },
+                        Action::Back | Action::Stats if app.stats.is_some() => app.stats = None,
+                        Action::Stats if !app.popup_open() => {
+                            app.stats = Some(StatsView::new(app.current_branch.clone()));
+                            stats_worker.request(&app.current_branch);
+                        },
+                        Action::Back | Action::Debug if app.debug.is_some() => app.debug = None,
+                        Action::Debug if !app.popup_open() => app.debug = Some(git_manager.diff_cache_stats()),
+                        Action::Reflog if !app.popup_open() => match git_manager.get_reflog() {
+                            Ok(entries) => app.reflog = Some(ReflogView::new(entries)),
+                            Err(e) => app.notify(Level::Error, format!("Failed to read the reflog: {}", e)),
+                        },
+                        Action::Status if !app.popup_open() => open_status(&mut app, &git_manager),
+                        Action::AuthorFilter => {
+                            if !app.show_author_filter {
+                                load_authors(&mut app, &mut git_manager);
+                            }
+                            app.toggle_author_filter();
+                        },
+                        Action::DateFilter if !app.popup_open() || app.show_date_filter => app.toggle_date_filter(),
+                        Action::TypeFilter if !app.popup_open() || app.show_type_filter => app.toggle_type_filter(),
+                        Action::BranchSelector => {
+                            if !app.show_branch_selector {
+                                load_branches(&mut app, &mut git_manager);
+                            }
+                            app.toggle_branch_selector();
+                        },
+                        Action::TagSelector => {
+                            if !app.show_tag_selector {
+                                load_refs(&mut app, &git_manager);
+                            }
+                            app.toggle_tag_selector();
+                        },
+                        Action::RepoSelector if !app.popup_open() || app.show_repo_selector => app.toggle_repo_selector(),
+                        Action::Refresh => refresh(&mut app, &mut git_manager, &loader),
+                        Action::Search if app.show_branch_selector => app.start_branch_query(),
+                        Action::Search if app.file_view.is_some() && !app.popup_open() => {
+                            let title = "Search the file for (text, or /regex/)".to_string();
+                            app.prompt = Some(Prompt::new(title, String::new(), PromptAction::FileSearch));
+                        },
+                        Action::NextMatch if app.file_view.is_some() && !app.popup_open() => file_view(&mut app).jump_to_match(true),
+                        Action::PrevMatch if app.file_view.is_some() && !app.popup_open() => file_view(&mut app).jump_to_match(false),
+                        Action::Search => app.start_search(),
+                        Action::NextMatch => app.jump_to_match(true),
+                        Action::PrevMatch => app.jump_to_match(false),
+                        Action::Up => {
+                            if app.show_branch_selector {
+                                app.navigate_branch_selector(-1);
+                            } else if app.show_author_filter {
+                                app.navigate_author_selector(-1);
+                            } else if app.show_type_filter {
+                                app.navigate_type_selector(-1);
+                            } else if app.show_date_filter {
+                                app.navigate_date_selector(-1);
+                            } else if app.show_filter_panel {
+                                app.navigate_filter_panel(-1);
+                            } else if app.show_tag_selector {
+                                app.navigate_tag_selector(-1);
+                            } else if app.show_repo_selector {
+                                app.navigate_repo_selector(-1);
+                            } else if app.show_column_picker {
+                                app.navigate_column_picker(-1);
+                            } else {
+                                match app.focus {
+                                    Focus::Commits => app.navigate_up(),
+                                    Focus::Details => app.scroll_details(-1),
+                                    Focus::Files => app.navigate_left(),
+                                }
+                            }
+                        },
+                        Action::Down => {
+                            if app.show_branch_selector {
+                                app.navigate_branch_selector(1);
+                            } else if app.show_author_filter {
+                                app.navigate_author_selector(1);
+                            } else if app.show_type_filter {
+                                app.navigate_type_selector(1);
+                            } else if app.show_date_filter {
+                                app.navigate_date_selector(1);
+                            } else if app.show_filter_panel {
+                                app.navigate_filter_panel(1);
+                            } else if app.show_tag_selector {
+                                app.navigate_tag_selector(1);
+                            } else if app.show_repo_selector {
+                                app.navigate_repo_selector(1);
+                            } else if app.show_column_picker {
+                                app.navigate_column_picker(1);
+                            } else {
+                                match app.focus {
+                                    Focus::Commits => app.navigate_down(),
+                                    Focus::Details => app.scroll_details(1),
+                                    Focus::Files => app.navigate_right(),
+                                }
+                            }
+                        },
+                        Action::ShrinkList | Action::GrowList if !app.popup_open() => {
+                            app.resize_list(if action == Action::GrowList { 5 } else { -5 });
+                            if let Err(e) = Config::save_value("list_width", app.list_width.into()) {
+                                app.notify(Level::Error, format!("Failed to save the list width: {:#}", e));
+                            }
+                        },
+                        Action::DateFormat if !app.popup_open() => {
+                            app.date_format = app.date_format.next();
+                            let value = serde_json::to_value(app.date_format).unwrap_or_default();
+                            if let Err(e) = Config::save_value("date_format", value) {
+                                app.notify(Level::Error, format!("Failed to save the date format: {:#}", e));
+                            }
+                        },
+                        Action::Theme if !app.popup_open() => {
+                            let name = app.cycle_theme();
+                            app.notify(Level::Info, format!("Theme: {}", name.label()));
+                            let value = serde_json::to_value(name).unwrap_or_default();
+                            if let Err(e) = Config::save_value("theme", value) {
+                                app.notify(Level::Error, format!("Failed to save the theme: {:#}", e));
+                            }
+                        },
+                        Action::Columns if !app.popup_open() || app.show_column_picker => app.toggle_column_picker(),
+                        Action::Confirm | Action::Mark | Action::Stage if app.show_column_picker => {
+                            app.toggle_column();
+                            let value = serde_json::to_value(app.shown_columns()).unwrap_or_default();
+                            if let Err(e) = Config::save_value("columns", value) {
+                                app.notify(Level::Error, format!("Failed to save the columns: {:#}", e));
+                            }
+                        },
+                        Action::FileHistory if (app.file_view.is_some() || app.blame.is_some()) && !app.popup_open() => {
+                            open_line_history(&mut app, &git_manager);
+                        },
+                        Action::FileHistory if !app.popup_open() => {
+                            if app.toggle_file_history() {
+                                reload_commits(&mut app, &loader);
+                            } else {
+                                app.notify(Level::Warning, "Pick a file with ←/→ to see its history".to_string());
+                            }
+                        },
+                        Action::Blame if !app.popup_open() => open_blame(&mut app, &git_manager),
+                        Action::RangeSelect if app.file_view.is_some() && !app.popup_open() => file_view(&mut app).toggle_anchor(),
+                        Action::RangeSelect if app.blame.is_some() && !app.popup_open() => {
+                            if let Some(blame) = &mut app.blame {
+                                blame.toggle_anchor();
+                            }
+                        },
+                        Action::ViewFile if !app.popup_open() => {
+                            if app.file_view.is_some() {
+                                app.file_view = None;
+                            } else {
+                                open_file_view(&mut app, &git_manager);
+                            }
+                        },
+                        Action::MessageLog if !app.popup_open() || app.show_message_log => {
+                            app.toggle_message_log();
+                        },
+                        Action::ToggleMaximise if !app.popup_open() => app.toggle_maximise(),
+                        Action::Checkout if app.show_branch_selector => {
+                            if let Some(branch) = app.selected_branch() {
+                                let name = branch.name.clone();
+                                let message = match branch.remote {
+                                    Some(_) => format!("Check out {} as a detached HEAD?", name),
+                                    None => format!("Check out {}?", name),
+                                };
+                                confirm_checkout(&mut app, &git_manager, message, PendingAction::CheckoutBranch(name));
+                            }
+                        },
+                        Action::Checkout if !app.popup_open() => {
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let message = format!("Check out {} as a detached HEAD?", &commit.hash[..7]);
+                                let hash = commit.hash.clone();
+                                confirm_checkout(&mut app, &git_manager, message, PendingAction::CheckoutCommit(hash));
+                            }
+                        },
+                        Action::Compare if !app.popup_open() => {
+                            let base = git_manager.default_compare_base(&app.current_branch);
+                            let input = format!("{}...{}", base, app.branch_label());
+                            app.prompt = Some(Prompt::new("Compare (base...target)".to_string(), input, PromptAction::Compare));
+                        },
+                        Action::Export if !app.popup_open() => {
+                            let title = "Export the commits shown, or those marked, to (.json, .csv, .dot, .html or .patch)".to_string();
+                            app.prompt = Some(Prompt::new(title, "commits.json".to_string(), PromptAction::Export));
+                        },
+                        Action::FormatPatch if !app.popup_open() => {
+                            let what = match app.marked.len() {
+                                0 => "the selected commit".to_string(),
+                                count => format!("the {} marked commits", count),
+                            };
+                            let title = format!("Write {} as patch files to the directory", what);
+                            app.prompt = Some(Prompt::new(title, "patches".to_string(), PromptAction::FormatPatch));
+                        },
+                        Action::ApplyPatch if !app.popup_open() => {
+                            let title = "Apply the patch or mbox file".to_string();
+                            app.prompt = Some(Prompt::new(title, String::new(), PromptAction::ApplyPatch));
+                        },
+                        Action::Changelog if !app.popup_open() => {
+                            let input = match git_manager.latest_tag(&app.current_branch) {
+                                Some(tag) => format!("{}..{}", tag, app.branch_label()),
+                                None => format!("..{}", app.branch_label()),
+                            };
+                            app.prompt = Some(Prompt::new("Changelog (from..to)".to_string(), input, PromptAction::Changelog));
+                        },
+                        Action::YankHash | Action::YankShortHash if !app.marked.is_empty() && !app.popup_open() => {
+                            // Marked commits are copied oldest first, ready to paste after `git cherry-pick`
+                            let hashes: Vec<String> = app
+                                .marked_commits()
+                                .iter()
+                                .map(|commit| if action == Action::YankHash { commit.hash.clone() } else { commit.hash[..7].to_string() })
+                                .collect();
+                            let what = format!("{} marked hashes", hashes.len());
+                            yank(&mut app, &hashes.join(" "), &what);
+                        },
+                        Action::YankHash | Action::YankShortHash | Action::YankMessage if !app.popup_open() => {
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let (text, what) = match action {
+                                    Action::YankHash => (commit.hash.clone(), "hash"),
+                                    Action::YankShortHash => (commit.hash[..7].to_string(), "short hash"),
+                                    _ => (commit.message.trim_end().to_string(), "message"),
+                                };
+                                let what = format!("the {} of {}", what, &commit.hash[..7]);
+                                yank(&mut app, &text, &what);
+                            }
+                        },
+                        Action::YankPatch if !app.popup_open() && app.diff_cut_short() => {
+                            // What's shown leaves out some of large files, so the whole diff is read
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let (hash, paths) = (commit.hash.clone(), commit.paths.clone());
+                                match git_manager.commit_diff(&hash, &paths, true) {
+                                    Ok(diff) => yank(&mut app, &diff.patch, "the patch shown"),
+                                    Err(e) => app.notify(Level::Error, format!("Failed to diff {}: {}", &hash[..7], e)),
                                 }
-                                app.show_branch_selector = false;
-                            }
-                        }
-                    },
-                    KeyCode::Esc => {
-                        app.show_branch_selector = false;
-                        app.show_author_filter = false;
-                    },
-                    _ => {}
+                            }
+                        },
+                        Action::YankPatch if !app.popup_open() => match app.shown_diff() {
+                            // A range or merge view copies the patch being shown
+                            Some((_, patch, _)) if !patch.is_empty() => {
+                                let patch = patch.to_string();
+                                yank(&mut app, &patch, "the patch shown");
+                            },
+                            _ => app.notify(Level::Warning, "There is no patch to copy".to_string()),
+                        },
+                        Action::Grep if !app.popup_open() => {
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let title = format!("Grep the files at {} for (text, or /regex/)", &commit.hash[..7]);
+                                let action = PromptAction::Grep(commit.hash.clone());
+                                app.prompt = Some(Prompt::new(title, String::new(), action));
+                            }
+                        },
+                        Action::Pickaxe if !app.popup_open() => edit_filter(&mut app, &mut git_manager, FilterKind::Pickaxe),
+                        Action::PathFilter if !app.popup_open() => edit_filter(&mut app, &mut git_manager, FilterKind::Paths),
+                        Action::ToggleMerges if !app.popup_open() => app.toggle_merges(),
+                        Action::FirstParent if !app.popup_open() => app.toggle_first_parent(),
+                        Action::Filters if !app.popup_open() || app.show_filter_panel => app.toggle_filter_panel(),
+                        Action::Bisect if !app.popup_open() => {
+                            if app.bisect.take().is_some() {
+                                app.notify(Level::Info, "Ended the bisect".to_string());
+                            } else {
+                                app.bisect = Some(Bisect::default());
+                                app.notify(Level::Info, "Bisecting: mark a bad commit with b and a good one with g".to_string());
+                            }
+                        },
+                        Action::OpenInBrowser if !app.popup_open() => open_in_forge(&mut app, &git_manager, &config),
+                        Action::Fetch if !app.popup_open() => {
+                            let remotes = git_manager.remote_names().unwrap_or_default();
+                            if remotes.is_empty() {
+                                app.notify(Level::Warning, "This repository has no remotes".to_string());
+                            } else {
+                                let default = match git_manager.head_state() {
+                                    Ok(HeadState::Branch(name)) => git_manager.default_remote(&name),
+                                    _ => "origin".to_string(),
+                                };
+                                let title = format!("Fetch from ({})", remotes.join(", "));
+                                app.prompt = Some(Prompt::new(title, default, PromptAction::Fetch));
+                            }
+                        },
+                        Action::Pull if !app.popup_open() => match git_manager.head_state() {
+                            Ok(HeadState::Branch(branch)) => app.remote_request = Some(RemoteOp::Pull { branch }),
+                            _ => app.notify(Level::Warning, "Check out a branch to pull into".to_string()),
+                        },
+                        Action::Push if !app.popup_open() => confirm_push(&mut app, &git_manager),
+                        Action::Unshallow if !app.popup_open() => {
+                            if app.shallow.is_empty() {
+                                app.notify(Level::Info, "This clone already has the whole history".to_string());
+                            } else {
+                                let remote = git_manager.default_remote(&app.current_branch);
+                                let message = format!("Fetch the rest of the history from {}?", remote);
+                                app.confirm(message, PendingAction::Unshallow(remote));
+                            }
+                        },
+                        Action::Tag if !app.popup_open() => {
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let title = format!("New tag at {}", &commit.hash[..7]);
+                                let action = PromptAction::TagName(commit.hash.clone());
+                                app.prompt = Some(Prompt::new(title, String::new(), action));
+                            }
+                        },
+                        Action::Revert if !app.popup_open() => {
+                            if let Some(commit) = app.commits.get(app.selected_index) {
+                                let message = format!(
+                                    "Revert {} \"{}\" with a new commit?",
+                                    &commit.hash[..7],
+                                    commit.message.lines().next().unwrap_or("")
+                                );
+                                let hash = commit.hash.clone();
+                                app.confirm(message, PendingAction::Revert(hash));
+                            }
+                        },
+                        // Space stages in the status view and marks in the list
+                        Action::Mark | Action::Stage if !app.popup_open() => app.toggle_mark(),
+                        Action::MarkedDiff if !app.popup_open() => app.toggle_marked_diff(),
+                        Action::RangeSelect if !app.popup_open() => app.toggle_range(),
+                        Action::Parent if !app.popup_open() => app.jump_to_parent(0),
+                        Action::MergeParent if !app.popup_open() => app.jump_to_parent(1),
+                        Action::Child if !app.popup_open() => app.jump_to_child(),
+                        Action::MergeDiff if !app.popup_open() => app.cycle_merge_view(),
+                        Action::CherryPick if !app.popup_open() => {
+                            let picks = app.commits_to_pick();
+                            if !picks.is_empty() {
+                                // Preview what will be applied, in order
+                                let target = match git_manager.head_state() {
+                                    Ok(HeadState::Branch(name)) => name,
+                                    _ => "HEAD".to_string(),
+                                };
+                                let mut message = format!("Cherry-pick onto {}?", target);
+                                for commit in &picks {
+                                    message.push_str(&format!("\n{} {}", &commit.hash[..7], commit.message.lines().next().unwrap_or("")));
+                                }
+                                let hashes = picks.iter().map(|c| c.hash.clone()).collect();
+                                app.confirm(message, PendingAction::CherryPick(hashes));
+                            }
+                        },
+                        Action::NextPane if !app.popup_open() => app.focus_next(),
+                        Action::PrevPane if !app.popup_open() => app.focus_prev(),
+                        Action::First if !app.popup_open() => match app.focus {
+                            Focus::Commits => app.navigate_first(),
+                            Focus::Details => app.scroll_details(i32::MIN),
+                            Focus::Files => app.select_file(None),
+                        },
+                        Action::Last if !app.popup_open() => match app.focus {
+                            Focus::Commits => app.navigate_last(),
+                            Focus::Details => app.scroll_details(i32::MAX),
+                            Focus::Files => app.select_last_file(),
+                        },
+                        Action::Confirm if app.show_branch_selector => {
+                            switch_to_selected_branch(&mut app, &loader);
+                        },
+                        Action::Confirm if app.show_author_filter => {
+                            app.select_author(app.author_selector_index);
+                        },
+                        Action::Confirm if app.show_type_filter => {
+                            app.select_type(app.type_selector_index);
+                        },
+                        Action::Confirm if app.show_date_filter => app.select_date_range(),
+                        Action::Confirm if app.show_filter_panel => {
+                            app.show_filter_panel = false;
+                            let kind = app.selected_filter();
+                            edit_filter(&mut app, &mut git_manager, kind);
+                        },
+                        Action::Confirm if app.show_repo_selector => app.select_repo(),
+                        Action::Confirm if app.show_tag_selector => {
+                            switch_to_selected_tag(&mut app, &loader);
+                        },
+                        Action::Confirm if app.patch_preview.is_some() && !app.popup_open() => {
+                            apply_patch(&mut app, &git_manager, git2::ApplyLocation::WorkDir);
+                        },
+                        Action::Confirm if app.blame.is_some() && app.focus == Focus::Details => {
+                            app.jump_to_blamed_commit();
+                        },
+                        Action::Confirm | Action::ToggleDiff if !app.popup_open() => {
+                            app.toggle_diff_view();
+                        },
+                        Action::Left if !app.popup_open() => {
+                            app.navigate_left();
+                        },
+                        Action::Right if !app.popup_open() => {
+                            app.navigate_right();
+                        },
+                        Action::ToggleSplitDiff if app.show_diff => app.toggle_split_diff(),
+                        Action::WrapDiff if app.show_diff => app.toggle_wrap_diff(),
+                        Action::FullDiff if app.show_diff => app.load_full_diff(),
+                        Action::IgnoreWhitespace if app.show_diff => {
+                            app.whitespace.ignore_all = !app.whitespace.ignore_all;
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::IgnoreWhitespaceChange if app.show_diff => {
+                            app.whitespace.ignore_change = !app.whitespace.ignore_change;
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::IgnoreBlankLines if app.show_diff => {
+                            app.whitespace.ignore_blank_lines = !app.whitespace.ignore_blank_lines;
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::LessContext if app.show_diff => {
+                            app.diff_context = app.diff_context.less();
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::MoreContext if app.show_diff => {
+                            app.diff_context = app.diff_context.more();
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::FullFileContext if app.show_diff => {
+                            app.diff_context.full_file = !app.diff_context.full_file;
+                            apply_diff_options(&mut app, &mut git_manager);
+                        },
+                        Action::RenameDetection if !app.popup_open() => toggle_renames(&mut app, &mut git_manager, &config),
+                        Action::ScrollLeft if app.show_diff => app.scroll_diff_sideways(-8),
+                        Action::ScrollRight if app.show_diff => app.scroll_diff_sideways(8),
+                        Action::PageUp if !app.popup_open() => match app.focus {
+                            Focus::Commits => app.page_commits(false),
+                            Focus::Details => app.scroll_details(-page_height(&app)),
+                            Focus::Files => {},
+                        },
+                        Action::PageDown if !app.popup_open() => match app.focus {
+                            Focus::Commits => app.page_commits(true),
+                            Focus::Details => app.scroll_details(page_height(&app)),
+                            Focus::Files => {},
+                        },
+                        Action::Back if app.file_view.is_some() && !app.popup_open() => app.file_view = None,
+                        Action::Back if app.blame.is_some() && !app.popup_open() => app.close_blame(),
+                        Action::Back if app.show_diff && !app.popup_open() => {
+                            app.toggle_diff_view();
+                        },
+                        Action::Back if app.patch_preview.is_some() && !app.popup_open() => app.close_patch_preview(),
+                        Action::Back if app.show_marked_diff && !app.popup_open() => app.toggle_marked_diff(),
+                        Action::Back if app.range_anchor.is_some() && !app.popup_open() => app.toggle_range(),
+                        Action::Back => {
+                            if !app.popup_open() {
+                                app.cancel_search();
+                                app.clear_type_filter();
+                                app.set_pickaxe(None);
+                                app.set_filters(FilterSet::default());
+                            }
+                            app.show_branch_selector = false;
+                            app.show_author_filter = false;
+                            app.show_type_filter = false;
+                            app.show_date_filter = false;
+                            app.show_filter_panel = false;
+                            app.show_tag_selector = false;
+                            app.show_repo_selector = false;
+                            app.show_column_picker = false;
+                            app.show_message_log = false;
+                        },
+                        _ => {}
+                    }
                 }
             }
         }
 
+        // Put the current repository aside as it is and bring in the other
+        if let Some(index) = app.repo_switch.take() {
+            let next = match sessions[index].take() {
+                Some(session) => Ok(session),
+                None => open_session(&repos, index, &config, None, &[]),
+            };
+            match next {
+                Ok(next) => {
+                    // The layout, columns and date style follow along, being settings
+                    let (current, list_width, date_format) = (app.repo_index, app.list_width, app.date_format);
+                    let columns = std::mem::take(&mut app.columns);
+                    sessions[current] = Some(Session { app, git_manager, loader, watcher, remote_task, stats_worker, pickaxe_search, message_search, pull_worker });
+                    Session { app, git_manager, loader, watcher, remote_task, stats_worker, pickaxe_search, message_search, pull_worker } = next;
+                    app.list_width = list_width;
+                    app.date_format = date_format;
+                    app.columns = columns;
+                },
+                Err(e) => app.notify(Level::Error, format!("Failed to open {}: {:#}", repos[index].display(), e)),
+            }
+        }
+
+        if let Some(op) = app.remote_request.take() {
+            start_remote(&mut app, &mut remote_task, &git_manager, op);
+        }
+        poll_remote(&mut app, &mut remote_task, &mut git_manager, &loader);
+        while let Some(event) = stats_worker.try_recv() {
+            if let Some(stats) = app.stats.as_mut().filter(|stats| stats.branch == event.branch) {
+                match event.result {
+                    Ok(result) => stats.set_stats(result),
+                    Err(e) => stats.error = Some(e),
+                }
+            }
+        }
+
+        sync_pickaxe(&mut app, &mut pickaxe_search, &git_manager);
+        while let Some(event) = pickaxe_search.as_ref().and_then(|search| search.try_recv()) {
+            let pickaxe = match app.pickaxe.as_mut() {
+                Some(pickaxe) => pickaxe,
+                None => break,
+            };
+            match event {
+                SearchEvent::Found(hash) => {
+                    pickaxe.matches.insert(hash);
+                    app.refresh_visible();
+                },
+                SearchEvent::Progress(scanned) => pickaxe.scanned = scanned,
+                SearchEvent::Done(scanned) => {
+                    pickaxe.scanned = scanned;
+                    pickaxe.done = true;
+                },
+                SearchEvent::Failed(e) => {
+                    pickaxe.done = true;
+                    app.notify(Level::Error, format!("Diff search failed: {}", e));
+                },
+            }
+        }
+        sync_message_search(&mut app, &mut message_search, &git_manager);
+        while let Some(event) = message_search.as_ref().and_then(|search| search.try_recv()) {
+            let results = match app.search_results.as_mut() {
+                Some(results) => results,
+                None => break,
+            };
+            match event {
+                SearchEvent::Found(hash) => results.matches.push(hash),
+                SearchEvent::Progress(scanned) => results.scanned = scanned,
+                SearchEvent::Done(scanned) => {
+                    results.scanned = scanned;
+                    results.done = true;
+                },
+                SearchEvent::Failed(e) => {
+                    results.done = true;
+                    app.notify(Level::Error, format!("Search failed: {}", e));
+                },
+            }
+        }
+        while let Some(event) = pull_worker.as_ref().and_then(|worker| worker.try_recv()) {
+            app.pull_requests.insert(event.hash, event.lookup);
+        }
+
+        if watcher.as_mut().is_some_and(|watcher| watcher.poll()) {
+            reload_changed_refs(&mut app, &mut git_manager, &loader);
+        }
+
+        // Merge any pages the loader has finished since the last frame
+        while let Some(event) = loader.try_recv() {
+            app.handle_load_event(event);
+        }
+
+        if app.needs_more_commits() {
+            loader.request(&app.current_branch, app.file_history.as_deref(), &app.filters, app.commits.len(), COMMIT_PAGE_SIZE);
+            app.loading = true;
+        }
+
         if last_tick.elapsed() >= tick_rate {
+            app.on_tick();
             last_tick = Instant::now();
         }
     }
 
-    // Cleanup
-    disable_raw_mode().context("Failed to disable raw mode")?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    ).context("Failed to leave alternate screen")?;
-    terminal.show_cursor().context("Failed to show cursor")?;
+    // Dropping the guard puts the terminal back, on error returns too
+    drop(guard);
+
+    Ok(())
+}
+
+/// What's kept for each open repository, so switching away and back
+/// finds it as it was left, along with its cached history
+struct Session {
+    app: App,
+    git_manager: GitManager,
+    loader: CommitLoader,
+    watcher: Option<RepoWatcher>,
+    // At most one fetch, pull or push runs at a time
+    remote_task: Option<RemoteTask>,
+    stats_worker: StatsWorker,
+    pickaxe_search: Option<HistorySearch>,
+    message_search: Option<HistorySearch>,
+    pull_worker: Option<PullRequestWorker>,
+}
+
+/// The branch name HEAD is on, or `HEAD` when it's detached
+fn head_branch(state: &HeadState) -> String {
+    match state {
+        HeadState::Branch(name) | HeadState::Unborn(name) => name.clone(),
+        HeadState::Detached(_) => "HEAD".to_string(),
+    }
+}
+
+/// Opens the `index`th repository and starts loading its history.
+/// `follow` and `pathspecs` from the command line only apply to the first.
+fn open_session(repos: &[std::path::PathBuf], index: usize, config: &Config, follow: Option<&std::path::Path>, pathspecs: &[String]) -> Result<Session> {
+    let mut git_manager = GitManager::new(&repos[index])
+        .with_context(|| format!("Not a Git repository ({})", repos[index].display()))?;
+    git_manager.set_diff_cache_budget(config.diff_cache_mb.saturating_mul(1024 * 1024));
+    git_manager.set_rename_detection(config.detect_renames.then_some(config.rename_similarity.clamp(1, 100)));
+    let branches = git_manager.get_branches().context("Failed to get branches")?;
+
+    // Start from whatever HEAD points at, even if that isn't a branch
+    let head_state = git_manager.head_state().context("Failed to read HEAD")?;
+    let path_filter = cli_path_filter(&git_manager, pathspecs)?;
+
+    // Load commits in the background so the UI can draw straight away
+    let loader = CommitLoader::spawn(git_manager.root()).context("Failed to start commit loader")?;
+
+    let mut app = App::new(head_branch(&head_state), branches);
+    app.list_width = config.list_width.clamp(10, 90);
+    app.date_format = config.date_format;
+    app.image_protocol = image::detect(config.image_protocol);
+    app.set_columns(&config.columns);
+    app.repo_root = git_manager.root().display().to_string();
+    app.recent_branches = recent::load(&app.repo_root);
+    app.bare = git_manager.is_bare();
+    app.repos = repos.iter().map(|path| path.display().to_string()).collect();
+    app.repo_index = index;
+    if let Some(path) = follow {
+        match git_manager.repo_path(path) {
+            Ok(path) => app.file_history = Some(path),
+            Err(e) => app.notify(Level::Error, format!("Failed to follow {}: {}", path.display(), e)),
+        }
+    }
+    app.filters.paths = path_filter;
+    match &head_state {
+        // There is no history to load until the first commit is made
+        HeadState::Unborn(_) => {},
+        HeadState::Detached(short_hash) => {
+            app.detached_head = Some(short_hash.clone());
+            loader.request(&app.current_branch, app.file_history.as_deref(), &app.filters, 0, COMMIT_PAGE_SIZE);
+            app.loading = true;
+        },
+        HeadState::Branch(_) => {
+            loader.request(&app.current_branch, app.file_history.as_deref(), &app.filters, 0, COMMIT_PAGE_SIZE);
+            app.loading = true;
+        },
+    }
+    load_refs(&mut app, &git_manager);
+
+    // Reload whenever another process moves HEAD or a ref. Without a
+    // watcher the view still works, it just needs a manual refresh
+    let mut watcher = None;
+    if config.auto_refresh {
+        match RepoWatcher::new(&git_manager.git_dir()) {
+            Ok(repo_watcher) => watcher = Some(repo_watcher),
+            Err(e) => app.notify(Level::Warning, format!("Auto-refresh is off, failed to watch the repository: {}", e)),
+        }
+    }
+
+    let stats_worker = StatsWorker::spawn(git_manager.root());
+    // Pull requests are only looked up for repositories on GitHub
+    let pull_worker = if config.pull_requests {
+        let remote = git_manager.default_remote(&app.current_branch);
+        git_manager
+            .remote_url(&remote)
+            .ok()
+            .and_then(|url| GitHubRepo::from_remote(&url, &config.github_hosts))
+            .map(|repo| {
+                let token = std::env::var("GITHUB_TOKEN").ok().or_else(|| config.github_token.clone());
+                PullRequestWorker::spawn(repo, token)
+            })
+    } else {
+        None
+    };
+
+    Ok(Session { app, git_manager, loader, watcher, remote_task: None, stats_worker, pickaxe_search: None, message_search: None, pull_worker })
+}
+
+fn switch_to_selected_branch(app: &mut App, loader: &CommitLoader) {
+    if app.select_branch() {
+        save_recent_branches(app);
+        reload_commits(app, loader);
+    }
+}
+
+/// Remembers the branches picked lately in this repository for next time
+fn save_recent_branches(app: &mut App) {
+    if let Err(e) = recent::save(&app.repo_root, &app.recent_branches) {
+        app.notify(Level::Error, format!("Failed to save the recent branches: {:#}", e));
+    }
+}
+
+fn switch_to_selected_tag(app: &mut App, loader: &CommitLoader) {
+    if app.select_tag(app.tag_selector_index) {
+        reload_commits(app, loader);
+    }
+}
+
+/// Clears the list and starts loading history for `app.current_branch`
+fn reload_commits(app: &mut App, loader: &CommitLoader) {
+    app.set_commits(Vec::new());
+    app.loading = true;
+    loader.request(&app.current_branch, app.file_history.as_deref(), &app.filters, 0, COMMIT_PAGE_SIZE);
+    app.show_branch_selector = false;
+    app.show_tag_selector = false;
+}
+
+/// Picks up changes made outside the tool, only reloading history if the
+/// tip being viewed has moved
+fn reload_changed_refs(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader) {
+    load_branches(app, git_manager);
+    load_refs(app, git_manager);
+
+    let tip = git_manager.tip(&app.current_branch);
+    if tip.as_deref() != app.commits.front().map(|c| c.hash.as_str()) {
+        app.set_commits(Vec::new());
+        app.loading = tip.is_some();
+        if tip.is_some() {
+            loader.request(&app.current_branch, app.file_history.as_deref(), &app.filters, 0, COMMIT_PAGE_SIZE);
+        }
+    }
+}
+
+/// Rereads branches, refs and history straight from the repository,
+/// bypassing every cache
+fn refresh(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader) {
+    git_manager.clear_cache();
+    app.pull_requests.clear();
+    load_branches(app, git_manager);
+    load_refs(app, git_manager);
+    if app.show_author_filter {
+        load_authors(app, git_manager);
+    }
+
+    app.set_commits(Vec::new());
+    // An unborn branch has no history to load yet
+    app.loading = git_manager.tip(&app.current_branch).is_some();
+    if app.loading {
+        loader.refresh(&app.current_branch, app.file_history.as_deref(), &app.filters, 0, COMMIT_PAGE_SIZE);
+    }
+    app.notify(Level::Info, "Reloaded from the repository".to_string());
+}
+
+/// Blames the picked file as of the selected commit
+fn open_blame(app: &mut App, git_manager: &GitManager) {
+    let (commit, path) = match (app.commits.get(app.selected_index), app.selected_file_change()) {
+        (Some(commit), Some(file)) => (commit.hash.clone(), file.path().to_string()),
+        _ => {
+            app.notify(Level::Warning, "Pick a file with ←/→ to blame it".to_string());
+            return;
+        }
+    };
+    match git_manager.blame(&commit, &path) {
+        Ok(lines) => app.open_blame(path, lines),
+        Err(e) => app.notify(Level::Error, format!("Failed to blame {}: {}", path, e)),
+    }
+}
+
+/// Opens the picked file whole as of the selected commit, or as it was
+/// before if the commit deleted it
+fn open_file_view(app: &mut App, git_manager: &GitManager) {
+    let (commit, file) = match (app.commits.get(app.selected_index), app.selected_file_change()) {
+        (Some(commit), Some(file)) => (commit, file),
+        _ => {
+            app.notify(Level::Warning, "Pick a file with ←/→ to view it".to_string());
+            return;
+        }
+    };
+    let (hash, path, note) = match (file.status, commit.parents.first()) {
+        (ChangeStatus::Deleted, Some(parent)) => (parent.clone(), file.path().to_string(), Some("before it was deleted".to_string())),
+        _ => (commit.hash.clone(), file.path().to_string(), None),
+    };
+    match git_manager.file_content(&hash, &path) {
+        Ok(content) => {
+            app.file_view = Some(FileView::new(path, hash, note, &content));
+            app.focus = Focus::Details;
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to view {}: {:#}", path, e)),
+    }
+}
+
+/// Opens the history of the lines picked in the file view, or else in the
+/// blame
+fn open_line_history(app: &mut App, git_manager: &GitManager) {
+    let (commit, path, (first, last)) = match (&app.file_view, &app.blame) {
+        (Some(view), _) => (view.commit.clone(), view.path.clone(), view.selection()),
+        (None, Some(blame)) => (blame.commit.clone(), blame.path.clone(), blame.selection()),
+        (None, None) => return,
+    };
+    let range = (first + 1, last + 1);
+    match git_manager.line_history(&commit, &path, range.0, range.1) {
+        Ok(changes) => app.line_history = Some(LineHistoryView::new(path, range, changes)),
+        Err(e) => app.notify(Level::Error, format!("Failed to follow lines {}-{} of {}: {:#}", range.0, range.1, path, e)),
+    }
+}
+
+fn line_history(app: &mut App) -> &mut LineHistoryView {
+    app.line_history.as_mut().expect("line history is open")
+}
+
+fn file_view(app: &mut App) -> &mut FileView {
+    app.file_view.as_mut().expect("file view is open")
+}
+
+fn tree_view(app: &mut App) -> &mut TreeView {
+    app.tree.as_mut().expect("tree browser is open")
+}
+
+/// Opens the tree browser on the selected commit
+fn open_tree(app: &mut App, git_manager: &GitManager) {
+    let commit = match app.commits.get(app.selected_index) {
+        Some(commit) => commit.hash.clone(),
+        None => return,
+    };
+    match git_manager.tree_entries(&commit, "") {
+        Ok(entries) => app.open_tree(entries),
+        Err(e) => app.notify(Level::Error, format!("Failed to read the tree of {}: {}", &commit[..7], e)),
+    }
+}
+
+/// Expands or folds the directory under the cursor, or shows the file
+fn open_tree_row(app: &mut App, git_manager: &GitManager) {
+    let tree = tree_view(app);
+    let (path, is_dir, expanded) = match tree.selected() {
+        Some(row) => (row.path.clone(), row.is_dir, row.expanded),
+        None => return,
+    };
+    let commit = tree.commit.clone();
+
+    if is_dir && expanded {
+        tree.collapse();
+    } else if is_dir {
+        match git_manager.tree_entries(&commit, &path) {
+            Ok(entries) => tree_view(app).expand(entries),
+            Err(e) => app.notify(Level::Error, format!("Failed to read {}: {}", path, e)),
+        }
+    } else {
+        match git_manager.file_content(&commit, &path) {
+            Ok(content) => app.open_tree_file(path, &content),
+            Err(e) => app.notify(Level::Error, format!("Failed to open {}: {}", path, e)),
+        }
+    }
+}
+
+fn grep_view(app: &mut App) -> &mut GrepView {
+    app.grep.as_mut().expect("grep results are open")
+}
+
+/// The tree at `commit` unfolded down to `path`, with the cursor on it
+fn reveal_in_tree(git_manager: &GitManager, commit: &str, path: &str) -> anyhow::Result<TreeView> {
+    let mut tree = TreeView::new(commit.to_string(), git_manager.tree_entries(commit, "")?);
+    for (end, _) in path.match_indices('/') {
+        tree.select_path(&path[..end]);
+        tree.expand(git_manager.tree_entries(commit, &path[..end])?);
+    }
+    tree.select_path(path);
+    Ok(tree)
+}
+
+/// Opens the file of the grep match under the cursor at its line
+fn open_grep_match(app: &mut App, git_manager: &GitManager) {
+    let grep = grep_view(app);
+    let (commit, path, line) = match grep.selected() {
+        Some(found) => (grep.commit.clone(), found.path.clone(), found.line),
+        None => return,
+    };
+    let opened = reveal_in_tree(git_manager, &commit, &path)
+        .and_then(|tree| git_manager.file_content(&commit, &path).map(|content| (tree, content)));
+    match opened {
+        Ok((tree, content)) => {
+            app.tree = Some(tree);
+            // With a few lines above the match for context
+            app.open_tree_file_at(path, &content, line.saturating_sub(4));
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to open {}: {:#}", path, e)),
+    }
+}
+
+fn status_view(app: &mut App) -> &mut StatusView {
+    app.status.as_mut().expect("status view is open")
+}
+
+/// Opens the status view, or rereads it if it's already open
+fn open_status(app: &mut App, git_manager: &GitManager) {
+    match git_manager.get_status() {
+        Ok(files) => {
+            // Stay on the same file across a reload where possible, or else
+            // on the same row
+            let (cursor, selected) = match &app.status {
+                Some(status) => (status.cursor, status.selected().cloned()),
+                None => (0, None),
+            };
+            let cursor = selected
+                .and_then(|old| files.iter().position(|f| f.path == old.path && f.section == old.section))
+                .unwrap_or(cursor);
+            let mut status = StatusView::new(files);
+            status.move_cursor(cursor as i32);
+            app.status = Some(status);
+            load_status_diff(app, git_manager);
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to read the working tree status: {}", e)),
+    }
+}
+
+/// Stages the picked hunk of an unstaged file, or unstages it from a staged one
+fn stage_hunk(app: &mut App, git_manager: &GitManager) {
+    let status = status_view(app);
+    let file = match status.selected() {
+        Some(file) => file.clone(),
+        None => return,
+    };
+    if status.hunks.is_empty() {
+        return;
+    }
+    let hunk = status.hunk;
+    match git_manager.apply_hunk(&file, hunk) {
+        Ok(()) => {
+            open_status(app, git_manager);
+            // Carry on with the hunk that took the place of the one just moved
+            let status = status_view(app);
+            if status.selected().is_some_and(|f| f.path == file.path && f.section == file.section) {
+                status.move_hunk(hunk as i32);
+            }
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to apply the hunk to {}: {}", file.path, e)),
+    }
+}
+
+/// Stages an unstaged or untracked file, or unstages a staged one
+fn toggle_staged(app: &mut App, git_manager: &GitManager) {
+    let file = match status_view(app).selected() {
+        Some(file) => file.clone(),
+        None => return,
+    };
+    let result = match file.section {
+        StatusSection::Staged => git_manager.unstage(&file),
+        StatusSection::Unstaged | StatusSection::Untracked => git_manager.stage(&file),
+    };
+    match result {
+        Ok(()) => open_status(app, git_manager),
+        Err(e) => app.notify(Level::Error, format!("Failed to update the index for {}: {}", file.path, e)),
+    }
+}
+
+fn toggle_amend(app: &mut App, git_manager: &GitManager) {
+    let dialog = match &mut app.commit_dialog {
+        Some(dialog) => dialog,
+        None => return,
+    };
+    dialog.amend = !dialog.amend;
+    // Start from the message being amended rather than a blank page
+    if dialog.amend && dialog.message().trim().is_empty() {
+        if let Some(message) = git_manager.head_message() {
+            dialog.set_message(&message);
+        }
+    }
+}
+
+fn submit_commit(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader) {
+    let dialog = match &app.commit_dialog {
+        Some(dialog) => dialog,
+        None => return,
+    };
+    match git_manager.create_commit(&dialog.message(), dialog.amend, dialog.sign_off) {
+        Ok(oid) => {
+            let verb = if dialog.amend { "Amended" } else { "Created" };
+            app.notify(Level::Info, format!("{} commit {}", verb, &oid.to_string()[..7]));
+            app.commit_dialog = None;
+            open_status(app, git_manager);
+            reload_changed_refs(app, git_manager, loader);
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to commit: {}", e)),
+    }
+}
+
+fn run_confirmed(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader, action: PendingAction) {
+    match action {
+        PendingAction::Discard(file) => match git_manager.discard(&file) {
+            Ok(()) => {
+                app.notify(Level::Info, format!("Discarded changes to {}", file.path));
+                open_status(app, git_manager);
+            },
+            Err(e) => app.notify(Level::Error, format!("Failed to discard {}: {}", file.path, e)),
+        },
+        PendingAction::StashApply(index) => {
+            let result = git_manager.stash_apply(index);
+            finish_stash_action(app, git_manager, result, "Applied", index);
+        },
+        PendingAction::StashPop(index) => {
+            let result = git_manager.stash_pop(index);
+            finish_stash_action(app, git_manager, result, "Popped", index);
+        },
+        PendingAction::StashDrop(index) => {
+            let result = git_manager.stash_drop(index);
+            finish_stash_action(app, git_manager, result, "Dropped", index);
+        },
+        PendingAction::CherryPick(hashes) => {
+            cherry_pick(app, git_manager, &hashes);
+            reload_changed_refs(app, git_manager, loader);
+        },
+        PendingAction::Revert(hash) => {
+            revert(app, git_manager, &hash);
+            reload_changed_refs(app, git_manager, loader);
+        },
+        PendingAction::CheckoutBranch(name) => {
+            let result = git_manager.checkout_branch(&name);
+            finish_checkout(app, git_manager, loader, result, &name);
+        },
+        PendingAction::CheckoutCommit(hash) => {
+            let result = git_manager.checkout_commit(&hash);
+            finish_checkout(app, git_manager, loader, result, &hash[..7]);
+        },
+        PendingAction::DeleteBranch(name) => match git_manager.delete_branch(&name) {
+            Ok(()) => {
+                app.notify(Level::Info, format!("Deleted branch {}", name));
+                load_branches(app, git_manager);
+                load_refs(app, git_manager);
+            },
+            Err(e) => app.notify(Level::Error, format!("Failed to delete {}: {}", name, e)),
+        },
+        PendingAction::Push { remote, branch } => app.remote_request = Some(RemoteOp::Push { remote, branch }),
+        PendingAction::Unshallow(remote) => app.remote_request = Some(RemoteOp::Unshallow { remote }),
+        PendingAction::DeleteTag(name) => match git_manager.delete_tag(&name) {
+            Ok(()) => {
+                app.notify(Level::Info, format!("Deleted tag {}", name));
+                load_refs(app, git_manager);
+            },
+            Err(e) => app.notify(Level::Error, format!("Failed to delete tag {}: {}", name, e)),
+        },
+    }
+}
+
+/// Previews how the checked-out branch compares to its upstream before
+/// pushing it
+fn confirm_push(app: &mut App, git_manager: &GitManager) {
+    let branch = match git_manager.head_state() {
+        Ok(HeadState::Branch(branch)) => branch,
+        _ => {
+            app.notify(Level::Warning, "Check out a branch to push".to_string());
+            return;
+        },
+    };
+    let remote = git_manager.default_remote(&branch);
+    let mut message = format!("Push {} to {}?", branch, remote);
+    match git_manager.ahead_behind(&branch) {
+        Ok(Some(status)) => {
+            message.push_str(&format!(
+                "\n{} commit(s) ahead and {} behind {}.",
+                status.ahead, status.behind, status.upstream
+            ));
+            if status.behind > 0 {
+                message.push_str("\nThe push will be rejected until you pull.");
+            }
+        },
+        Ok(None) => message.push_str(&format!("\nIt will be set to track {}/{}.", remote, branch)),
+        Err(e) => app.notify(Level::Warning, format!("Failed to compare {} with its upstream: {}", branch, e)),
+    }
+    app.confirm(message, PendingAction::Push { remote, branch });
+}
+
+fn describe(op: &RemoteOp) -> String {
+    match op {
+        RemoteOp::Fetch { remote } => format!("Fetching from {}", remote),
+        RemoteOp::Pull { branch } => format!("Pulling {}", branch),
+        RemoteOp::Push { remote, branch } => format!("Pushing {} to {}", branch, remote),
+        RemoteOp::Unshallow { remote } => format!("Fetching the rest of the history from {}", remote),
+    }
+}
+
+/// Runs the fetch, pull or push asked for, one at a time
+fn start_remote(app: &mut App, remote_task: &mut Option<RemoteTask>, git_manager: &GitManager, op: RemoteOp) {
+    if let Some(task) = remote_task {
+        app.notify(Level::Warning, format!("{} is still running", describe(&task.op)));
+        return;
+    }
+    app.remote_progress = Some(format!("{}...", describe(&op)));
+    *remote_task = Some(RemoteTask::spawn(git_manager.root(), op));
+}
+
+/// Shows the running operation's progress, and reloads once it's done
+/// Recomputes the combined diff whenever either end of the range selection moves
+fn sync_range_diff(app: &mut App, git_manager: &GitManager) {
+    let (newest, oldest) = match app.range_ends() {
+        Some(ends) => ends,
+        None => {
+            app.range_diff = None;
+            return;
+        },
+    };
+    let (newest, oldest) = (app.commits[newest].hash.clone(), app.commits[oldest].hash.clone());
+    if app.range_diff.as_ref().is_some_and(|range| range.newest == newest && range.oldest == oldest) {
+        return;
+    }
+    match git_manager.range_diff(&oldest, &newest) {
+        Ok(range) => app.range_diff = Some(range),
+        Err(e) => {
+            app.range_anchor = None;
+            app.range_diff = None;
+            app.notify(Level::Error, format!("Failed to diff the range: {}", e));
+        },
+    }
+}
+
+/// Recomputes the marked commits' combined diff while it's shown and the
+/// marks change
+fn sync_marked_diff(app: &mut App, git_manager: &GitManager) {
+    if !app.show_marked_diff {
+        return;
+    }
+    let hashes: Vec<String> = app.marked_commits().iter().map(|commit| commit.hash.clone()).collect();
+    if app.marked_diff.as_ref().is_some_and(|marked| marked.hashes == hashes) {
+        return;
+    }
+    match git_manager.marked_diff(&hashes) {
+        Ok(marked) => app.marked_diff = Some(marked),
+        Err(e) => {
+            app.show_marked_diff = false;
+            app.marked_diff = None;
+            app.notify(Level::Error, format!("Failed to combine the marked commits: {:#}", e));
+        },
+    }
+}
+
+/// Diffs the selected merge commit against the parent picked with `M`
+fn sync_merge_diff(app: &mut App, git_manager: &GitManager) {
+    let view = match app.active_merge_view() {
+        Some(view) => view,
+        None => return,
+    };
+    let hash = app.commits[app.selected_index].hash.clone();
+    if app.merge_diff.as_ref().is_some_and(|merge| merge.hash == hash && merge.view == view) {
+        return;
+    }
+    match git_manager.merge_diff(&hash, view) {
+        Ok(merge) => app.merge_diff = Some(merge),
+        Err(e) => {
+            app.merge_view = MergeView::Parent(0);
+            app.merge_diff = None;
+            app.notify(Level::Error, format!("Failed to diff the merge: {}", e));
+        },
+    }
+}
+
+/// Reads the selected commit's diff once it's selected
+fn sync_commit_diff(app: &mut App, git_manager: &mut GitManager) {
+    let commit = match app.commits.get(app.selected_index) {
+        Some(commit) => commit,
+        None => return,
+    };
+    let full = app.full_diff.as_ref() == Some(&commit.hash);
+    let loaded = |diff: &CommitDiff| diff.hash == commit.hash && diff.paths == commit.paths && !(full && diff.cut_short);
+    if app.commit_diff.as_ref().is_some_and(loaded) {
+        return;
+    }
+    let (hash, paths) = (commit.hash.clone(), commit.paths.clone());
+    match git_manager.commit_diff(&hash, &paths, full) {
+        Ok(diff) => {
+            // Counted along with the diff, so this doesn't read it again
+            app.commit_stat = git_manager.get_diffstat(&hash, &paths).ok();
+            app.commit_diff = Some(diff);
+        },
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to diff {}: {}", &hash[..7], e));
+            // An empty diff stands in, so it isn't tried again every frame
+            app.commit_diff = Some(CommitDiff { hash, paths, patch: String::new(), info: DiffInfo::default(), cut_short: false });
+        },
+    }
+}
+
+/// Turns pairing up files as renames and copies on or off for the rest of
+/// the session, reading the diffs shown again
+fn toggle_renames(app: &mut App, git_manager: &mut GitManager, config: &Config) {
+    let similarity = match git_manager.rename_detection() {
+        Some(_) => None,
+        None => Some(config.rename_similarity.clamp(1, 100)),
+    };
+    git_manager.set_rename_detection(similarity);
+    app.reload_diffs();
+    match similarity {
+        Some(similarity) => app.notify(Level::Info, format!("Finding renames and copies ({}% alike)", similarity)),
+        None => app.notify(Level::Info, "Not finding renames and copies".to_string()),
+    }
+}
+
+/// Reads the diffs shown again with the whitespace the diff pane now
+/// leaves out and the context it shows, staying on the same file
+fn apply_diff_options(app: &mut App, git_manager: &mut GitManager) {
+    git_manager.set_whitespace(app.whitespace);
+    git_manager.set_diff_context(app.diff_context);
+    let file = app.selected_file;
+    app.reload_diffs();
+    app.selected_file = file;
+}
+
+/// Reads the selected image file's content before and after the commit
+/// once it's picked in the diff
+fn sync_image_preview(app: &mut App, git_manager: &GitManager) {
+    let (commit, file) = match (app.commits.get(app.selected_index), app.image_change()) {
+        (Some(commit), Some(file)) => (commit, file),
+        _ => {
+            app.image_preview = None;
+            return;
+        },
+    };
+    if app.image_preview.as_ref().is_some_and(|preview| preview.hash == commit.hash && preview.path == file.path()) {
+        return;
+    }
+    // Files too big to draw are only sized up, not read
+    let side = |commit: &str, path: &str| {
+        let size = git_manager.file_size(commit, path).ok()?;
+        let bytes = match size as usize <= image::MAX_DRAWN_SIZE {
+            true => git_manager.file_bytes(commit, path).ok(),
+            false => None,
+        };
+        Some(ImageSide { size, bytes })
+    };
+    // Added files have no before and deleted files no after
+    let old = file.old_path.as_deref().zip(commit.parents.first()).and_then(|(path, parent)| side(parent, path));
+    let new = file.new_path.as_deref().and_then(|path| side(&commit.hash, path));
+    app.image_preview = Some(ImagePreview::new(commit.hash.clone(), file.path().to_string(), old, new));
+}
+
+/// Starts searching the whole history when a regex search is typed or the
+/// branch, file or filters change under one, and stops once it's cleared
+fn sync_message_search(app: &mut App, search: &mut Option<HistorySearch>, git_manager: &GitManager) {
+    let (results, pattern) = match (app.search_results.as_mut(), &app.search_pattern) {
+        (Some(results), Some(pattern)) => (results, pattern),
+        _ => {
+            *search = None;
+            return;
+        },
+    };
+    let unchanged = search.as_ref().is_some_and(|search| {
+        search.branch == app.current_branch && search.query == results.query && search.path == app.file_history && search.filters == app.filters
+    });
+    if unchanged {
+        return;
+    }
+    *results = SearchResults { query: results.query.clone(), ..Default::default() };
+    let path = app.file_history.as_deref();
+    *search = Some(HistorySearch::messages(git_manager.root(), &app.current_branch, path, &app.filters, &results.query, pattern.clone()));
+}
+
+/// Starts searching diffs when a search is asked for or the branch
+/// changes under one, and stops once it's cleared
+fn sync_pickaxe(app: &mut App, search: &mut Option<HistorySearch>, git_manager: &GitManager) {
+    let pickaxe = match app.pickaxe.as_mut() {
+        Some(pickaxe) => pickaxe,
+        None => {
+            *search = None;
+            return;
+        },
+    };
+    if search.as_ref().is_some_and(|search| search.branch == app.current_branch && search.query == pickaxe.query) {
+        return;
+    }
+    // Checked when the prompt was submitted
+    let query = match PickaxeQuery::parse(&pickaxe.query) {
+        Ok(query) => query,
+        Err(_) => return,
+    };
+    *pickaxe = PickaxeResults { query: pickaxe.query.clone(), ..Default::default() };
+    *search = Some(HistorySearch::pickaxe(git_manager.root(), &app.current_branch, &pickaxe.query, query));
+    app.refresh_visible();
+}
+
+/// Asks for the selected commit's pull request the first time it's shown
+fn sync_pull_request(app: &mut App, worker: Option<&PullRequestWorker>) {
+    let worker = match worker {
+        Some(worker) => worker,
+        None => return,
+    };
+    let hash = match app.commits.get(app.selected_index) {
+        Some(commit) => commit.hash.clone(),
+        None => return,
+    };
+    if app.pull_requests.contains_key(&hash) {
+        return;
+    }
+    // The worker skips lookups overtaken by this one, so those are asked
+    // for again if their commit is selected later
+    app.pull_requests.retain(|_, lookup| !matches!(lookup, PullLookup::Loading));
+    app.pull_requests.insert(hash.clone(), PullLookup::Loading);
+    worker.request(&hash);
+}
+
+fn poll_remote(app: &mut App, remote_task: &mut Option<RemoteTask>, git_manager: &mut GitManager, loader: &CommitLoader) {
+    let task = match remote_task {
+        Some(task) => task,
+        None => return,
+    };
+    let mut finished = false;
+    while let Some(event) = task.try_recv() {
+        match event {
+            RemoteEvent::Progress(message) => app.remote_progress = Some(format!("{}: {}", describe(&task.op), message)),
+            RemoteEvent::Done(message) => {
+                app.notify(Level::Info, message);
+                finished = true;
+                break;
+            },
+            RemoteEvent::Failed(message) => {
+                app.notify(Level::Error, format!("{} failed: {}", describe(&task.op), message));
+                finished = true;
+                break;
+            },
+        }
+    }
+    if finished {
+        // Unshallowing leaves the tips where they were but adds history below
+        let unshallowed = matches!(task.op, RemoteOp::Unshallow { .. });
+        *remote_task = None;
+        app.remote_progress = None;
+        if unshallowed {
+            refresh(app, git_manager, loader);
+        } else {
+            reload_changed_refs(app, git_manager, loader);
+        }
+    }
+}
+
+/// `n`, `d` and `R` in the branch selector: create a branch at the selected
+/// commit, or delete or rename the highlighted one
+fn handle_branch_key(app: &mut App, git_manager: &GitManager, code: KeyCode) {
+    if code == KeyCode::Char('n') {
+        if let Some(commit) = app.commits.get(app.selected_index) {
+            let title = format!("New branch at {}", &commit.hash[..7]);
+            let action = PromptAction::CreateBranch(commit.hash.clone());
+            app.prompt = Some(Prompt::new(title, String::new(), action));
+        }
+        return;
+    }
 
+    let branch = match app.selected_branch() {
+        Some(branch) if branch.remote.is_none() => branch.name.clone(),
+        Some(_) => {
+            app.notify(Level::Warning, "Only local branches can be deleted or renamed".to_string());
+            return;
+        },
+        None => return,
+    };
+    if code == KeyCode::Char('R') {
+        let title = format!("Rename {}", branch);
+        app.prompt = Some(Prompt::new(title, branch.clone(), PromptAction::RenameBranch(branch)));
+        return;
+    }
+    let message = match git_manager.is_branch_merged(&branch) {
+        Ok(true) => format!("Delete branch {}?", branch),
+        Ok(false) => format!("{} is not merged into HEAD, and its commits may be lost.\nDelete it anyway?", branch),
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to read {}: {}", branch, e));
+            return;
+        },
+    };
+    app.confirm(message, PendingAction::DeleteBranch(branch));
+}
+
+/// Opens whatever changes one kind of filter: a selector or a prompt, or
+/// for merges, the next setting
+fn edit_filter(app: &mut App, git_manager: &mut GitManager, kind: FilterKind) {
+    match kind {
+        FilterKind::Author => {
+            load_authors(app, git_manager);
+            app.toggle_author_filter();
+        },
+        FilterKind::Dates => app.toggle_date_filter(),
+        FilterKind::Paths => {
+            let title = "Only commits touching (paths or globs like src/**/*.rs, space-separated; empty for all)".to_string();
+            let input = app.filters.paths.as_ref().map(|paths| paths.describe()).unwrap_or_default();
+            app.prompt = Some(Prompt::new(title, input, PromptAction::PathFilter));
+        },
+        FilterKind::Message => {
+            let title = "Only commits whose message matches (regex, ignoring case; empty for all)".to_string();
+            let input = app.filters.message.as_ref().map(|message| message.as_str().to_string()).unwrap_or_default();
+            app.prompt = Some(Prompt::new(title, input, PromptAction::MessageFilter));
+        },
+        FilterKind::Merges => {
+            let merges = app.filters.merges.next();
+            app.set_filters(FilterSet { merges, ..app.filters.clone() });
+        },
+        FilterKind::FirstParent => app.toggle_first_parent(),
+        FilterKind::Type => app.toggle_type_filter(),
+        FilterKind::Pickaxe => {
+            let title = "Find commits whose diffs add or remove (text, or /regex/ for changed lines)".to_string();
+            let input = app.pickaxe.as_ref().map(|pickaxe| pickaxe.query.clone()).unwrap_or_default();
+            app.prompt = Some(Prompt::new(title, input, PromptAction::Pickaxe));
+        },
+    }
+}
+
+/// Turns `--path` pathspecs, which are relative to where the viewer was
+/// started like git's, into a filter relative to the repository root
+fn cli_path_filter(git_manager: &GitManager, specs: &[String]) -> Result<Option<PathFilter>> {
+    if specs.is_empty() {
+        return Ok(None);
+    }
+    let specs = specs
+        .iter()
+        .map(|spec| git_manager.repo_path(std::path::Path::new(spec)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(PathFilter::new(specs)?))
+}
+
+/// Writes the whole of `branch`'s history, or just the commits that
+/// changed `follow` or match `paths`, to `out` for `--export`
+fn export_history(git_manager: &mut GitManager, branch: &str, follow: Option<&std::path::Path>, paths: Option<&PathFilter>, format: ExportFormat, out: &std::path::Path) -> Result<()> {
+    let path = follow.map(|path| git_manager.repo_path(path)).transpose()?;
+    let filters = FilterSet { paths: paths.cloned(), ..Default::default() };
+    let commits = git_manager.get_history(branch, path.as_deref(), &filters, None)?;
+    let labels = git_manager.get_ref_map()?;
+    let title = match &path {
+        Some(path) => format!("History of {} on {}", path, branch),
+        None => format!("History of {}", branch),
+    };
+    write_export(git_manager, &commits.iter().collect::<Vec<_>>(), &labels, &title, format, out)?;
+    println!("Exported {} commits to {}", commits.len(), out.display());
     Ok(())
 }
+
+/// Applies the previewed patch file, closing the preview once it's in
+fn apply_patch(app: &mut App, git_manager: &GitManager, location: git2::ApplyLocation) {
+    let preview = match &app.patch_preview {
+        Some(preview) => preview,
+        None => return,
+    };
+    let target = match location {
+        git2::ApplyLocation::Index => "the index",
+        _ => "the working tree",
+    };
+    match git_manager.apply_patch(preview, location) {
+        Ok(count) => {
+            let message = format!("Applied {} to {}", preview.path, target);
+            let message = if count > 1 { format!("{} ({} patches)", message, count) } else { message };
+            app.notify(Level::Info, message);
+            app.close_patch_preview();
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to apply the patch to {}: {:#}", target, e)),
+    }
+}
+
+/// Writes the marked commits, or the selected one, to `dir` as numbered
+/// patch files like `git format-patch -o dir`
+fn write_patch_files(app: &mut App, git_manager: &GitManager, dir: &str) {
+    let hashes: Vec<&str> = app.commits_to_pick().iter().map(|commit| commit.hash.as_str()).collect();
+    let write = || -> Result<usize> {
+        let patches = git_manager.format_patches(&hashes)?;
+        if patches.is_empty() {
+            return Ok(0);
+        }
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir))?;
+        for (name, patch) in &patches {
+            let path = std::path::Path::new(dir).join(name);
+            std::fs::write(&path, patch).with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(patches.len())
+    };
+    match write() {
+        Ok(0) => app.notify(Level::Warning, "Merge commits can't be written as patches".to_string()),
+        Ok(count) => {
+            let skipped = hashes.len() - count;
+            let note = if skipped > 0 { format!(", leaving out {} merge{}", skipped, if skipped == 1 { "" } else { "s" }) } else { String::new() };
+            app.notify(Level::Info, format!("Wrote {} patch{} to {}{}", count, if count == 1 { "" } else { "es" }, dir, note));
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to write patches: {:#}", e)),
+    }
+}
+
+/// Writes `commits`, newest first as listed, to `out` in `format`. A patch
+/// series is made from the repository, oldest first so `git am` can apply it.
+fn write_export(
+    git_manager: &mut GitManager,
+    commits: &[&CommitInfo],
+    labels: &HashMap<String, Vec<RefLabel>>,
+    title: &str,
+    format: ExportFormat,
+    out: &std::path::Path,
+) -> Result<()> {
+    if format == ExportFormat::Patch {
+        let hashes: Vec<&str> = commits.iter().rev().map(|commit| commit.hash.as_str()).collect();
+        let series: String = git_manager.format_patches(&hashes)?.into_iter().map(|(_, patch)| patch).collect();
+        return std::fs::write(out, series).with_context(|| format!("Failed to write {}", out.display()));
+    }
+    export::write_commits(commits.iter().copied(), labels, title, format, out, &mut |commit| {
+        git_manager.get_diffstat(&commit.hash, &commit.paths)
+    })
+}
+
+fn submit_prompt(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader) {
+    let prompt = match app.prompt.take() {
+        Some(prompt) => prompt,
+        None => return,
+    };
+    let input = prompt.input.trim().to_string();
+    // Only a tag message or a filter may be left empty
+    if input.is_empty() && !matches!(prompt.action, PromptAction::TagMessage { .. } | PromptAction::PathFilter | PromptAction::MessageFilter) {
+        return;
+    }
+    match prompt.action {
+        PromptAction::CreateBranch(hash) => match git_manager.create_branch(&input, &hash) {
+            Ok(()) => app.notify(Level::Info, format!("Created branch {} at {}", input, &hash[..7])),
+            Err(e) => app.notify(Level::Error, format!("Failed to create {}: {}", input, e)),
+        },
+        PromptAction::RenameBranch(name) => match git_manager.rename_branch(&name, &input) {
+            Ok(()) => {
+                app.notify(Level::Info, format!("Renamed {} to {}", name, input));
+                // Keep showing the same history under its new name
+                if app.current_branch == name {
+                    app.current_branch = input;
+                    reload_commits(app, loader);
+                    app.show_branch_selector = true;
+                }
+            },
+            Err(e) => app.notify(Level::Error, format!("Failed to rename {}: {}", name, e)),
+        },
+        PromptAction::Fetch => {
+            app.remote_request = Some(RemoteOp::Fetch { remote: input });
+            return;
+        },
+        PromptAction::Compare => {
+            open_compare(app, git_manager, &input);
+            return;
+        },
+        PromptAction::Export => {
+            let out = std::path::PathBuf::from(&input);
+            // Marked commits or a range selection are exported on their own,
+            // otherwise whatever the list shows
+            let (indices, title): (Vec<usize>, String) = match app.range_ends() {
+                _ if !app.marked.is_empty() => (
+                    (0..app.commits.len()).filter(|&i| app.marked.contains(&app.commits[i].hash)).collect(),
+                    format!("Marked commits on {}", app.branch_label()),
+                ),
+                Some((newest, oldest)) => (
+                    (newest..=oldest).collect(),
+                    format!("{}..{} on {}", &app.commits[oldest].hash[..7], &app.commits[newest].hash[..7], app.branch_label()),
+                ),
+                None => (app.visible.clone(), format!("History of {}", app.branch_label())),
+            };
+            let commits: Vec<&CommitInfo> = indices.iter().map(|&i| &app.commits[i]).collect();
+            match write_export(git_manager, &commits, &app.ref_map, &title, ExportFormat::from_path(&out), &out) {
+                Ok(()) => {
+                    let whole_list = app.marked.is_empty() && app.range_anchor.is_none();
+                    let more = if app.has_more_commits && whole_list { " (only those loaded so far)" } else { "" };
+                    app.notify(Level::Info, format!("Exported {} commits to {}{}", indices.len(), input, more));
+                },
+                Err(e) => app.notify(Level::Error, format!("Failed to export: {:#}", e)),
+            }
+            return;
+        },
+        PromptAction::FormatPatch => {
+            write_patch_files(app, git_manager, &input);
+            return;
+        },
+        PromptAction::ApplyPatch => {
+            match git_manager.read_patch(std::path::Path::new(&input)) {
+                Ok(preview) => app.open_patch_preview(preview),
+                Err(e) => app.notify(Level::Error, format!("Failed to read the patch: {:#}", e)),
+            }
+            return;
+        },
+        PromptAction::FileSearch => {
+            if let Some(view) = &mut app.file_view {
+                if let Err(e) = view.search(&input) {
+                    app.notify(Level::Error, format!("Invalid regex: {}", e));
+                }
+            }
+            return;
+        },
+        PromptAction::Grep(commit) => {
+            let pattern = match input.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+                Some(pattern) if !pattern.is_empty() => regex::Regex::new(pattern),
+                _ => regex::Regex::new(&regex::escape(&input)),
+            };
+            let result = pattern
+                .context("Invalid regex")
+                .and_then(|pattern| git_manager.grep(&commit, &pattern).map(|matches| (pattern, matches)));
+            match result {
+                Ok((pattern, matches)) => app.grep = Some(GrepView::new(commit, input, pattern, matches)),
+                Err(e) => app.notify(Level::Error, format!("Failed to grep {}: {:#}", &commit[..7], e)),
+            }
+            return;
+        },
+        PromptAction::DateRange => {
+            app.set_date_range(&input);
+            return;
+        },
+        PromptAction::PathFilter => {
+            match PathFilter::parse(&input) {
+                Ok(paths) => {
+                    let paths = if paths.specs.is_empty() { None } else { Some(paths) };
+                    app.set_filters(FilterSet { paths, ..app.filters.clone() });
+                },
+                Err(e) => app.notify(Level::Error, format!("{:#}", e)),
+            }
+            return;
+        },
+        PromptAction::MessageFilter => {
+            match RegexBuilder::new(&input).case_insensitive(true).build() {
+                Ok(message) => {
+                    let message = if input.is_empty() { None } else { Some(message) };
+                    app.set_filters(FilterSet { message, ..app.filters.clone() });
+                },
+                Err(e) => app.notify(Level::Error, format!("Invalid regex {}: {}", input, e)),
+            }
+            return;
+        },
+        PromptAction::Pickaxe => {
+            // Checked here so a bad regex is reported before searching
+            match PickaxeQuery::parse(&input) {
+                Ok(_) => app.set_pickaxe(Some(PickaxeResults { query: input, ..Default::default() })),
+                Err(e) => app.notify(Level::Error, format!("{:#}", e)),
+            }
+            return;
+        },
+        PromptAction::Changelog => {
+            let (from, to) = match input.split_once("..") {
+                Some((from, to)) => (from.trim().to_string(), to.trim().trim_start_matches('.').to_string()),
+                None => (String::new(), input),
+            };
+            let to = if to.is_empty() { app.current_branch.clone() } else { to };
+            let title = format!("Write the changelog of {}..{} to (- for the clipboard)", from, to);
+            let action = PromptAction::ChangelogOut { from, to };
+            app.prompt = Some(Prompt::new(title, "CHANGELOG.md".to_string(), action));
+            return;
+        },
+        PromptAction::ChangelogOut { from, to } => {
+            write_changelog(app, git_manager, &from, &to, &input);
+            return;
+        },
+        PromptAction::TagName(hash) => {
+            let title = format!("Message for {}, empty for a lightweight tag", input);
+            let action = PromptAction::TagMessage { name: input, hash };
+            app.prompt = Some(Prompt::new(title, String::new(), action));
+            return;
+        },
+        PromptAction::TagMessage { name, hash } => {
+            let message = Some(input.as_str()).filter(|m| !m.is_empty());
+            match git_manager.create_tag(&name, &hash, message) {
+                Ok(()) => app.notify(Level::Info, format!("Tagged {} as {}", &hash[..7], name)),
+                Err(e) => app.notify(Level::Error, format!("Failed to create tag {}: {}", name, e)),
+            }
+        },
+    }
+    load_branches(app, git_manager);
+    load_refs(app, git_manager);
+}
+
+/// Asks before checking out, warning when there are local changes that
+/// will be carried across
+fn confirm_checkout(app: &mut App, git_manager: &GitManager, mut message: String, action: PendingAction) {
+    if git_manager.is_dirty().unwrap_or(false) {
+        message.push_str("\nThe working tree has uncommitted changes. They are kept, and the checkout stops if any would be overwritten.");
+    }
+    app.confirm(message, action);
+}
+
+/// Shows the history of whatever HEAD now points at
+fn finish_checkout(app: &mut App, git_manager: &mut GitManager, loader: &CommitLoader, result: anyhow::Result<()>, target: &str) {
+    if let Err(e) = result {
+        app.notify(Level::Error, format!("Failed to check out {}: {}", target, e));
+        return;
+    }
+    app.notify(Level::Info, format!("Checked out {}", target));
+    match git_manager.head_state() {
+        Ok(HeadState::Detached(short_hash)) => {
+            app.current_branch = "HEAD".to_string();
+            app.detached_head = Some(short_hash);
+        },
+        Ok(HeadState::Branch(name)) | Ok(HeadState::Unborn(name)) => {
+            app.current_branch = name;
+            app.detached_head = None;
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to read HEAD: {}", e)),
+    }
+    load_branches(app, git_manager);
+    load_refs(app, git_manager);
+    reload_commits(app, loader);
+}
+
+/// Whether the working tree is clean enough to commit on top of, telling
+/// the user what's in the way if not
+fn ensure_clean(app: &mut App, git_manager: &GitManager, doing: &str) -> bool {
+    match git_manager.is_dirty() {
+        Ok(false) => true,
+        Ok(true) => {
+            app.notify(Level::Warning, format!("Commit or stash your changes before {}", doing));
+            false
+        },
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to read the working tree status: {}", e));
+            false
+        },
+    }
+}
+
+fn revert(app: &mut App, git_manager: &GitManager, hash: &str) {
+    if !ensure_clean(app, git_manager, "reverting") {
+        return;
+    }
+    let short = &hash[..7];
+    match git_manager.revert(hash) {
+        Ok(PickOutcome::Committed(oid)) => {
+            app.notify(Level::Info, format!("Reverted {} in {}", short, &oid.to_string()[..7]));
+        },
+        Ok(PickOutcome::Conflicts(paths)) => app.notify(
+            Level::Error,
+            format!("Can't revert {}: it conflicts in {}. Nothing was changed", short, paths.join(", ")),
+        ),
+        Err(e) => app.notify(Level::Error, format!("Failed to revert {}: {}", short, e)),
+    }
+}
+
+/// Applies each commit in turn, stopping at the first that doesn't go
+/// through cleanly
+fn cherry_pick(app: &mut App, git_manager: &GitManager, hashes: &[String]) {
+    if !ensure_clean(app, git_manager, "cherry-picking") {
+        return;
+    }
+
+    for (i, hash) in hashes.iter().enumerate() {
+        let short = &hash[..7];
+        match git_manager.cherry_pick(hash) {
+            Ok(PickOutcome::Committed(oid)) => app.notify(
+                Level::Info,
+                format!("Picked {} as {} ({}/{})", short, &oid.to_string()[..7], i + 1, hashes.len()),
+            ),
+            Ok(PickOutcome::Conflicts(paths)) => {
+                app.notify(
+                    Level::Error,
+                    format!("Stopped at {}: it conflicts in {}. Nothing was changed for it", short, paths.join(", ")),
+                );
+                return;
+            },
+            Err(e) => {
+                app.notify(Level::Error, format!("Failed to cherry-pick {}: {}", short, e));
+                return;
+            },
+        }
+    }
+    app.marked.clear();
+}
+
+fn finish_stash_action(app: &mut App, git_manager: &mut GitManager, result: anyhow::Result<()>, verb: &str, index: usize) {
+    match result {
+        Ok(()) => {
+            app.notify(Level::Info, format!("{} stash@{{{}}}", verb, index));
+            open_stashes(app, git_manager);
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to use stash@{{{}}}: {}", index, e)),
+    }
+}
+
+fn compare_view(app: &mut App) -> &mut CompareView {
+    app.compare.as_mut().expect("comparison is open")
+}
+
+fn stats_view(app: &mut App) -> &mut StatsView {
+    app.stats.as_mut().expect("statistics are open")
+}
+
+/// The statistics page being shown, once there is something on it
+fn stats_page(app: &App) -> Option<StatsPage> {
+    app.stats.as_ref().filter(|stats| stats.stats.is_some()).map(|stats| stats.page)
+}
+
+/// Opens the selected commit on its forge's website, or the picked file as
+/// of that commit when there is one
+fn open_in_forge(app: &mut App, git_manager: &GitManager, config: &Config) {
+    let commit = match app.commits.get(app.selected_index) {
+        Some(commit) => commit,
+        None => return,
+    };
+    let remote = git_manager.default_remote(&app.current_branch);
+    let forge = git_manager
+        .remote_url(&remote)
+        .and_then(|url| Forge::from_remote(&url, &config.forges));
+    let forge = match forge {
+        Ok(forge) => forge,
+        Err(e) => {
+            app.notify(Level::Warning, format!("Can't open {} in a browser: {:#}", remote, e));
+            return;
+        },
+    };
+    // A deleted file no longer exists at the commit, so show the commit instead
+    let url = match app.selected_file_change() {
+        Some(file) if file.status != ChangeStatus::Deleted => forge.file_url(&commit.hash, file.path()),
+        _ => forge.commit_url(&commit.hash),
+    };
+    match forge::open_in_browser(&url) {
+        Ok(()) => app.notify(Level::Info, format!("Opened {}", url)),
+        Err(e) => app.notify(Level::Error, format!("Failed to open {}: {:#}", url, e)),
+    }
+}
+
+/// Marks the selected commit good or bad, then selects the next commit to
+/// test, or the first bad commit once there's only one left
+fn mark_bisect(app: &mut App, git_manager: &GitManager, good: bool) {
+    let hash = match app.commits.get(app.selected_index) {
+        Some(commit) => commit.hash.clone(),
+        None => return,
+    };
+    let bisect = app.bisect.as_mut().expect("bisecting");
+    let previous = bisect.clone();
+    if good {
+        bisect.good.retain(|other| *other != hash);
+        bisect.good.push(hash);
+    } else {
+        bisect.bad = Some(hash);
+    }
+    bisect.probe = None;
+    bisect.culprit = None;
+    let bad = match &bisect.bad {
+        Some(bad) if !bisect.good.is_empty() => bad.clone(),
+        Some(_) => {
+            app.notify(Level::Info, "Now mark a good commit with g".to_string());
+            return;
+        },
+        None => {
+            app.notify(Level::Info, "Now mark a bad commit with b".to_string());
+            return;
+        },
+    };
+    match git_manager.bisect_step(&bad, &bisect.good) {
+        Ok(BisectStep::Probe { hash, remaining }) => {
+            bisect.probe = Some(hash.clone());
+            bisect.remaining = remaining;
+            // Each test halves what's left
+            let steps = (usize::BITS - (remaining - 1).leading_zeros()).max(1);
+            app.notify(Level::Info, format!("Test {}: {} commits left, about {} steps", &hash[..7], remaining, steps));
+            app.jump_to_commit(hash);
+        },
+        Ok(BisectStep::Culprit(hash)) => {
+            bisect.culprit = Some(hash.clone());
+            bisect.remaining = 1;
+            app.notify(Level::Info, format!("{} is the first bad commit", &hash[..7]));
+            app.jump_to_commit(hash);
+        },
+        Err(e) => {
+            *bisect = previous;
+            app.notify(Level::Warning, format!("Can't bisect: {}", e));
+        },
+    }
+}
+
+/// Puts `text` on the clipboard, saying what was copied
+fn yank(app: &mut App, text: &str, what: &str) {
+    match clipboard::copy(text) {
+        Ok(via) => app.notify(Level::Info, format!("Copied {} using {}", what, via)),
+        Err(e) => app.notify(Level::Error, format!("Failed to copy {}: {:#}", what, e)),
+    }
+}
+
+/// Writes the changelog of `from..to` to `out`, or copies it when `out` is
+/// `-`; an empty `from` starts from the first commit
+fn write_changelog(app: &mut App, git_manager: &GitManager, from: &str, to: &str, out: &str) {
+    let commits = match git_manager.commits_since(Some(from).filter(|f| !f.is_empty()), to) {
+        Ok(commits) => commits,
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to list {}..{}: {:#}", from, to, e));
+            return;
+        },
+    };
+    let start = if from.is_empty() { "the start" } else { from };
+    let markdown = changelog::render(start, to, &commits);
+    let count = format!("{} commit{}", commits.len(), if commits.len() == 1 { "" } else { "s" });
+    let result = if out == "-" {
+        clipboard::copy(&markdown).map(|via| format!("Copied the changelog of {} using {}", count, via))
+    } else {
+        std::fs::write(out, &markdown)
+            .map(|()| format!("Wrote the changelog of {} to {}", count, out))
+            .with_context(|| format!("Failed to write {}", out))
+    };
+    match result {
+        Ok(message) => app.notify(Level::Info, message),
+        Err(e) => app.notify(Level::Error, format!("Failed to save the changelog: {:#}", e)),
+    }
+}
+
+/// Compares the refs in `base...target`; `..` or a space between them work too
+fn open_compare(app: &mut App, git_manager: &GitManager, input: &str) {
+    let refs = input
+        .split_once("...")
+        .or_else(|| input.split_once(".."))
+        .or_else(|| input.split_once(char::is_whitespace))
+        .map(|(base, target)| (base.trim(), target.trim()));
+    let (base, target) = match refs {
+        Some((base, target)) if !base.is_empty() && !target.is_empty() => (base, target),
+        _ => {
+            app.notify(Level::Warning, format!("Expected two refs like main...feature, got \"{}\"", input));
+            return;
+        },
+    };
+    match git_manager.compare(base, target) {
+        Ok(comparison) => app.compare = Some(CompareView::new(comparison)),
+        Err(e) => app.notify(Level::Error, format!("Failed to compare {}...{}: {:#}", base, target, e)),
+    }
+}
+
+fn reflog_view(app: &mut App) -> &mut ReflogView {
+    app.reflog.as_mut().expect("reflog is open")
+}
+
+fn stash_view(app: &mut App) -> &mut StashView {
+    app.stash.as_mut().expect("stash list is open")
+}
+
+/// Opens the stash list, or rereads it if it's already open
+fn open_stashes(app: &mut App, git_manager: &mut GitManager) {
+    match git_manager.get_stashes() {
+        Ok(stashes) => {
+            let cursor = app.stash.as_ref().map(|s| s.cursor).unwrap_or(0);
+            let mut stash = StashView::new(stashes);
+            stash.move_cursor(cursor as i32);
+            app.stash = Some(stash);
+            load_stash_diff(app, git_manager);
+        },
+        Err(e) => app.notify(Level::Error, format!("Failed to list stashes: {}", e)),
+    }
+}
+
+fn load_stash_diff(app: &mut App, git_manager: &GitManager) {
+    let entry = match stash_view(app).selected() {
+        Some(entry) => entry.clone(),
+        None => {
+            app.set_stash_diff("");
+            return;
+        }
+    };
+    match git_manager.stash_diff(&entry) {
+        Ok(patch) => app.set_stash_diff(&patch),
+        Err(e) => app.notify(Level::Error, format!("Failed to diff stash@{{{}}}: {}", entry.index, e)),
+    }
+}
+
+fn load_status_diff(app: &mut App, git_manager: &GitManager) {
+    let file = match status_view(app).selected() {
+        Some(file) => file.clone(),
+        None => {
+            app.set_status_diff("");
+            return;
+        }
+    };
+    match git_manager.working_diff(&file) {
+        Ok(patch) => app.set_status_diff(&patch),
+        Err(e) => app.notify(Level::Error, format!("Failed to diff {}: {}", file.path, e)),
+    }
+}
+
+fn load_branches(app: &mut App, git_manager: &mut GitManager) {
+    match git_manager.get_branches() {
+        Ok(branches) => app.set_branches(branches),
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to get branches: {}", e));
+        }
+    }
+}
+
+/// Reloads tags and the ref decorations shown next to commits
+fn load_refs(app: &mut App, git_manager: &GitManager) {
+    match git_manager.get_tags() {
+        Ok(tags) => app.set_tags(tags),
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to get tags: {}", e));
+        }
+    }
+    match git_manager.get_ref_map() {
+        Ok(ref_map) => app.ref_map = ref_map,
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to get refs: {}", e));
+        }
+    }
+    match git_manager.shallow_boundary() {
+        Ok(shallow) => app.shallow = shallow,
+        Err(e) => {
+            app.notify(Level::Error, format!("Failed to read the shallow clone's boundary: {}", e));
+        }
+    }
+}
+
+fn load_authors(app: &mut App, git_manager: &mut GitManager) {
+    match git_manager.get_authors(&app.current_branch) {
+        Ok(authors) => app.authors = authors,
+        Err(e) => {
+            app.authors.clear();
+            app.notify(Level::Error, format!("Failed to get authors for branch {}: {}", app.branch_label(), e));
+        }
+    }
+}
+
+/// Lines PgUp/PgDn scroll the details or diff by, i.e. the pane's height
+fn page_height(app: &App) -> i32 {
+    (app.details_height as i32).max(1)
+}
@@ -1,22 +1,601 @@
-use serde::{Serialize, Deserialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommitInfo {
-    pub hash: String,
-    pub message: String,
-    pub author: String,
-    pub date: String,
-    pub diff: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AuthorInfo {
-    pub name: String,
-    pub email: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BranchInfo {
-    pub name: String,
-    pub is_current: bool,
-} 
\ No newline at end of file
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use crate::pathspec::PathFilter;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub message: String,
+    pub author: String,
+    pub author_email: String,
+    // Seconds since the epoch the commit was authored
+    pub time: i64,
+    // Who made the commit and when, which differ from the author after a
+    // rebase, cherry-pick or applied patch
+    pub committer: String,
+    pub commit_time: i64,
+    pub parents: Vec<String>,
+    // In a file's history, the paths its diff is limited to: the file's
+    // name here and the one it was renamed from, if it was
+    pub paths: Vec<String>,
+}
+
+impl CommitInfo {
+    /// The hash, message and author, which are what searches look through
+    pub fn search_fields(&self) -> [&str; 3] {
+        [&self.hash, &self.message, &self.author]
+    }
+}
+
+/// A commit's changes, split into files, hunks and lines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffInfo {
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: ChangeStatus,
+    pub binary: bool,
+    // Sizes in bytes of each side, 0 for the side that doesn't exist
+    pub old_size: u64,
+    pub new_size: u64,
+    pub hunks: Vec<Hunk>,
+    // Lines of a large file's diff left out after the first few
+    pub omitted_lines: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    TypeChanged,
+}
+
+impl FileChange {
+    /// The path the file has after the change, or before it if it was deleted
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("")
+    }
+
+    /// Number of added and removed lines across all hunks
+    pub fn line_stats(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|h| &h.lines);
+        lines.fold((0, 0), |(added, removed), line| match line.kind {
+            LineKind::Added => (added + 1, removed),
+            LineKind::Removed => (added, removed + 1),
+            LineKind::Context => (added, removed),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hunk {
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorInfo {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_current: bool,
+    // Name of the remote for remote-tracking branches, e.g. "origin"
+    pub remote: Option<String>,
+    // Commits ahead of and behind the upstream, for local branches that
+    // track one
+    pub ahead_behind: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    // The commit the tag points at, after peeling annotated tags
+    pub target: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefKind {
+    Head,
+    LocalBranch,
+    RemoteBranch,
+    Tag,
+}
+
+/// A ref pointing at a commit, shown next to it like `git log --decorate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefLabel {
+    pub name: String,
+    pub kind: RefKind,
+}
+
+/// What HEAD points at when the tool starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadState {
+    Branch(String),
+    // HEAD points straight at a commit, stored as its short hash
+    Detached(String),
+    // A branch with no commits yet, e.g. in a freshly initialised repository
+    Unborn(String),
+}
+
+/// One line of a file with the commit that last changed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    pub hash: String,
+    pub author: String,
+    // Commit time in seconds since the epoch
+    pub time: i64,
+    pub content: String,
+}
+
+/// Which part of `git status` a working tree change belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StatusSection {
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+/// A file with uncommitted changes. One with both staged and unstaged
+/// changes is listed once in each section, as `git status` does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingFile {
+    pub path: String,
+    pub section: StatusSection,
+    pub status: ChangeStatus,
+}
+
+/// An entry of `git stash list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashInfo {
+    // Position in the stash list, as in `stash@{index}`
+    pub index: usize,
+    pub message: String,
+    pub hash: String,
+}
+
+/// One move of HEAD or a branch, as recorded in its reflog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReflogEntry {
+    // Shorthand of the ref, e.g. `HEAD` or `main`
+    pub ref_name: String,
+    // Position in that ref's reflog, as in `HEAD@{index}`
+    pub index: usize,
+    // The commit the ref pointed at after the move
+    pub hash: String,
+    pub message: String,
+    pub time: i64,
+    pub date: String,
+}
+
+/// What differs between two refs: the commits only on each side, and the
+/// files changed on `target` since it forked from `base`
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub base: String,
+    pub target: String,
+    // The newest of the commits only on each side, and how many there are in all
+    pub only_base: Vec<CommitInfo>,
+    pub only_target: Vec<CommitInfo>,
+    pub base_count: usize,
+    pub target_count: usize,
+    pub files: Vec<FileChange>,
+}
+
+/// The changes made by a run of commits taken together: from the parent
+/// of the oldest to the newest
+#[derive(Debug, Clone)]
+pub struct RangeDiff {
+    pub oldest: String,
+    pub newest: String,
+    pub patch: String,
+    pub info: DiffInfo,
+}
+
+/// What a hand-picked set of commits changes when applied one after
+/// another, as if squashed together
+#[derive(Debug, Clone)]
+pub struct MarkedDiff {
+    // Oldest first
+    pub hashes: Vec<String>,
+    pub patch: String,
+    pub info: DiffInfo,
+}
+
+/// A patch file read in to be previewed and applied. A series in mbox
+/// form is split into its patches, which are applied in turn.
+#[derive(Debug, Clone)]
+pub struct PatchPreview {
+    pub path: String,
+    // Each patch's text and, when it came as an email, its subject
+    pub patches: Vec<(Option<String>, String)>,
+    // The whole file, as shown in the diff pane
+    pub patch: String,
+    pub info: DiffInfo,
+}
+
+/// Which side of a merge commit its diff is taken against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeView {
+    Parent(usize),
+    // Only the files that differ from every parent
+    Combined,
+}
+
+/// A commit's diff against its first parent, worked out when the commit is
+/// looked at rather than while history is walked
+#[derive(Debug, Clone)]
+pub struct CommitDiff {
+    pub hash: String,
+    // The paths it's limited to, as in `CommitInfo::paths`
+    pub paths: Vec<String>,
+    pub patch: String,
+    pub info: DiffInfo,
+    // Whether large files' diffs were cut short
+    pub cut_short: bool,
+}
+
+/// How many lines a commit added and removed, in total and per file,
+/// counted without keeping the lines themselves
+#[derive(Debug, Clone, Default)]
+pub struct DiffStat {
+    pub hash: String,
+    pub paths: Vec<String>,
+    pub files: Vec<FileStat>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileStat {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: ChangeStatus,
+    pub binary: bool,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl FileStat {
+    /// The path the file has after the change, or before it if it was deleted
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("")
+    }
+}
+
+/// Whitespace differences left out of diffs, as git's `-w`, `-b` and
+/// `--ignore-blank-lines` do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Whitespace {
+    pub ignore_all: bool,
+    pub ignore_change: bool,
+    pub ignore_blank_lines: bool,
+}
+
+impl Whitespace {
+    /// What's being left out, for the diff pane's title, or None if nothing
+    pub fn label(self) -> Option<String> {
+        let ignored: Vec<&str> = [
+            (self.ignore_all, "all whitespace"),
+            (self.ignore_change && !self.ignore_all, "whitespace changes"),
+            (self.ignore_blank_lines, "blank lines"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, label)| *label)
+        .collect();
+        (!ignored.is_empty()).then(|| format!("ignoring {}", ignored.join(" and ")))
+    }
+}
+
+/// How much unchanged text diffs show around each change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffContext {
+    // Lines either side of a change, as git's `-U`
+    pub lines: u32,
+    // The whole of each changed file instead
+    pub full_file: bool,
+}
+
+/// Git's own default
+pub const CONTEXT_LINES: u32 = 3;
+/// Past this, the whole file is likelier what's wanted
+const MAX_CONTEXT_LINES: u32 = 100;
+
+impl Default for DiffContext {
+    fn default() -> Self {
+        DiffContext { lines: CONTEXT_LINES, full_file: false }
+    }
+}
+
+impl DiffContext {
+    /// A line more either side of each change
+    pub fn more(self) -> DiffContext {
+        DiffContext { lines: (self.lines + 1).min(MAX_CONTEXT_LINES), full_file: false }
+    }
+
+    /// A line fewer either side, down to only the changed lines
+    pub fn less(self) -> DiffContext {
+        DiffContext { lines: self.lines.saturating_sub(1), full_file: false }
+    }
+
+    /// How it differs from the usual, for the diff pane's title, or None if
+    /// it doesn't
+    pub fn label(self) -> Option<String> {
+        if self.full_file {
+            Some("whole files".to_string())
+        } else if self.lines != CONTEXT_LINES {
+            Some(format!("{} line{} of context", self.lines, if self.lines == 1 { "" } else { "s" }))
+        } else {
+            None
+        }
+    }
+}
+
+/// How the cache of recent commit diffs is doing, for the debug view
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffCacheStats {
+    // The most memory the cached diffs may take, in bytes
+    pub budget: usize,
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: usize,
+    pub misses: usize,
+    // Diffs dropped to make room, and how much they took
+    pub evictions: usize,
+    pub evicted_bytes: usize,
+    // Diffs too big to keep at all
+    pub rejected: usize,
+}
+
+/// A merge commit's diff against something other than its first parent
+#[derive(Debug, Clone)]
+pub struct MergeDiff {
+    pub hash: String,
+    pub view: MergeView,
+    pub patch: String,
+    pub info: DiffInfo,
+}
+
+/// Figures about a branch's whole history for the statistics view
+#[derive(Debug, Clone, Default)]
+pub struct RepoStats {
+    pub commits: usize,
+    // Commit counts by author name, most active first
+    pub authors: Vec<(String, usize)>,
+    // Oldest first, with empty weeks included
+    pub weeks: Vec<WeekStats>,
+    // Files changed by the most commits, busiest first
+    pub files: Vec<(String, usize)>,
+    // Every change to a file by a non-merge commit, newest first, for
+    // reports over a window of time
+    pub touches: Vec<FileTouch>,
+    // Paths and author names the touches point into
+    pub paths: Vec<String>,
+    pub author_names: Vec<String>,
+    // Totals over non-merge commits, for the average commit size
+    pub diffed_commits: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub files_changed: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeekStats {
+    // The Monday the week starts on, in seconds since the epoch
+    pub start: i64,
+    pub commits: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// One commit's change to one file
+#[derive(Debug, Clone, Copy)]
+pub struct FileTouch {
+    pub time: i64,
+    // Index into `RepoStats::paths`
+    pub file: usize,
+    // Index into `RepoStats::author_names`
+    pub author: usize,
+    // Tells apart the commits the touches came from
+    pub commit: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// The GitHub pull request a commit came in through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    // "open", "closed" or "merged"
+    pub state: String,
+    pub url: String,
+}
+
+/// Where looking up a commit's pull request has got to
+#[derive(Debug, Clone)]
+pub enum PullLookup {
+    Loading,
+    // None when the commit wasn't part of any pull request
+    Found(Option<PullRequest>),
+    // Looked up before and remembered, because GitHub couldn't be reached
+    Cached(PullRequest),
+    Failed(String),
+}
+
+/// A bisect session narrowing down the commit that introduced a bug
+#[derive(Debug, Clone, Default)]
+pub struct Bisect {
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    // The commit to test next
+    pub probe: Option<String>,
+    // The first bad commit, once it's been found
+    pub culprit: Option<String>,
+    // Commits that could still be the first bad one
+    pub remaining: usize,
+}
+
+/// What bisecting between a bad commit and the good ones leads to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BisectStep {
+    Probe { hash: String, remaining: usize },
+    Culprit(String),
+}
+
+/// Commits found so far whose diffs match a pickaxe search
+#[derive(Debug, Clone, Default)]
+pub struct PickaxeResults {
+    // As typed: plain text, or a regex between slashes
+    pub query: String,
+    pub matches: HashSet<String>,
+    pub scanned: usize,
+    pub done: bool,
+}
+
+/// Commits matching a regex search of the whole history, found on a
+/// worker thread and listed in the order the history is
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub query: String,
+    pub matches: Vec<String>,
+    pub scanned: usize,
+    pub done: bool,
+}
+
+/// A line of a file matching a grep of a commit's tree
+#[derive(Debug, Clone)]
+pub struct GrepMatch {
+    pub path: String,
+    // Counted from 1
+    pub line: usize,
+    pub text: String,
+}
+
+/// A commit that changed some lines of a file, with the hunks touching them
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    pub commit: CommitInfo,
+    // The file's path and the lines' first and last numbers as of the commit
+    pub path: String,
+    pub range: (usize, usize),
+    pub hunks: Vec<Hunk>,
+}
+
+/// Whether merge commits are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeFilter {
+    #[default]
+    All,
+    Only,
+    Exclude,
+}
+
+impl MergeFilter {
+    /// Steps through all commits, merges only and no merges
+    pub fn next(self) -> MergeFilter {
+        match self {
+            MergeFilter::All => MergeFilter::Only,
+            MergeFilter::Only => MergeFilter::Exclude,
+            MergeFilter::Exclude => MergeFilter::All,
+        }
+    }
+}
+
+/// Which commits the history is limited to. The loader applies these while
+/// walking history, so each page it returns is made up of matches.
+#[derive(Debug, Clone, Default)]
+pub struct FilterSet {
+    pub author: Option<AuthorInfo>,
+    // Seconds since the epoch, inclusive at both ends
+    pub dates: Option<(i64, i64)>,
+    pub paths: Option<PathFilter>,
+    // Matched against the whole commit message
+    pub message: Option<Regex>,
+    pub merges: MergeFilter,
+    // Walk only the first parent of each commit, like `--first-parent`
+    pub first_parent: bool,
+}
+
+impl FilterSet {
+    pub fn is_empty(&self) -> bool {
+        !self.checks_commits() && !self.first_parent
+    }
+
+    /// Whether each commit has to be looked at, as opposed to the filters
+    /// only changing how history is walked
+    pub fn checks_commits(&self) -> bool {
+        self.author.is_some()
+            || self.dates.is_some()
+            || self.paths.is_some()
+            || self.message.is_some()
+            || self.merges != MergeFilter::All
+    }
+
+    /// Checks everything but the paths, which need the commit's diff
+    pub fn matches_summary(&self, commit: &CommitInfo) -> bool {
+        let merge = commit.parents.len() > 1;
+        self.author.as_ref().is_none_or(|author| commit.author_email.eq_ignore_ascii_case(&author.email))
+            && self.dates.is_none_or(|(from, to)| (from..=to).contains(&commit.time))
+            && self.message.as_ref().is_none_or(|message| message.is_match(&commit.message))
+            && match self.merges {
+                MergeFilter::All => true,
+                MergeFilter::Only => merge,
+                MergeFilter::Exclude => !merge,
+            }
+    }
+
+    /// Identifies the filters for caching pages of history
+    pub fn key(&self) -> String {
+        format!(
+            "{:?}|{:?}|{:?}|{:?}|{:?}|{}",
+            self.author.as_ref().map(|author| author.email.to_lowercase()),
+            self.dates,
+            self.paths.as_ref().map(|paths| &paths.specs),
+            self.message.as_ref().map(|message| message.as_str()),
+            self.merges,
+            self.first_parent,
+        )
+    }
+}
+
+impl PartialEq for FilterSet {
+    fn eq(&self, other: &FilterSet) -> bool {
+        self.key() == other.key()
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Pathspecs relative to the repository root, like those after `--` in
+/// `git log`. A plain path matches the file or everything under the
+/// directory, and `*`, `?`, `[...]` and `**` glob as in git, where `*` also
+/// crosses directories.
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    pub specs: Vec<String>,
+    globs: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(specs: Vec<String>) -> Result<PathFilter> {
+        let specs: Vec<String> = specs
+            .iter()
+            .map(|spec| spec.trim_start_matches("./").trim_end_matches('/').to_string())
+            .filter(|spec| !spec.is_empty())
+            .collect();
+        let mut builder = GlobSetBuilder::new();
+        for spec in &specs {
+            for pattern in [spec.clone(), format!("{}/**", spec)] {
+                let glob = GlobBuilder::new(&pattern)
+                    .literal_separator(false)
+                    .build()
+                    .with_context(|| format!("Invalid pathspec {}", spec))?;
+                builder.add(glob);
+            }
+        }
+        Ok(PathFilter { specs, globs: builder.build()? })
+    }
+
+    /// Splits whitespace-separated pathspecs as typed into a prompt
+    pub fn parse(input: &str) -> Result<PathFilter> {
+        PathFilter::new(input.split_whitespace().map(|spec| spec.to_string()).collect())
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.globs.is_match(path)
+    }
+
+    pub fn describe(&self) -> String {
+        self.specs.join(" ")
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How many repositories have their recent branches remembered; the ones
+/// opened longest ago are forgotten first
+const MAX_REPOS: usize = 50;
+
+/// The branches picked lately in one repository, most recent first
+#[derive(Deserialize, Serialize)]
+struct RecentBranches {
+    repo: String,
+    branches: Vec<String>,
+}
+
+/// `$XDG_STATE_HOME/git-visualiser/recent_branches.json`, falling back to
+/// `~/.local/state`. Kept apart from the config, which is the user's to edit.
+fn path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+    Some(base.join("git-visualiser").join("recent_branches.json"))
+}
+
+/// Every repository's recent branches, the repository used last first. A
+/// missing or unreadable file just means there are none.
+fn load_all() -> Vec<RecentBranches> {
+    path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// The branches picked lately in the repository at `repo`
+pub fn load(repo: &str) -> Vec<String> {
+    load_all()
+        .into_iter()
+        .find(|recent| recent.repo == repo)
+        .map(|recent| recent.branches)
+        .unwrap_or_default()
+}
+
+/// Remembers the branches picked lately in the repository at `repo`
+pub fn save(repo: &str, branches: &[String]) -> Result<()> {
+    let path = path().context("No state directory")?;
+    let mut all = load_all();
+    all.retain(|recent| recent.repo != repo);
+    all.insert(0, RecentBranches { repo: repo.to_string(), branches: branches.to_vec() });
+    all.truncate(MAX_REPOS);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&all)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
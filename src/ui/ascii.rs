@@ -0,0 +1,69 @@
+use ratatui::buffer::Buffer;
+
+/// Whether the terminal is unlikely to draw Unicode symbols properly: the
+/// locale isn't UTF-8, or it's the Linux console, whose font has no emoji
+/// or arrows
+pub fn needs_ascii() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .map(|name| var(name))
+        .find(|value| !value.is_empty());
+    let is_utf8 = |locale: &str| {
+        let locale = locale.to_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    };
+    var("TERM") == "linux" || locale.is_some_and(|locale| !is_utf8(&locale))
+}
+
+/// The ASCII stand-in for a symbol drawn by the UI or by ratatui's widgets,
+/// or None for anything else, such as text from commits
+fn ascii_symbol(symbol: &str) -> Option<&'static str> {
+    let c = symbol.chars().next().filter(|c| !c.is_ascii())?;
+    let replacement = match c {
+        '─' | '━' | '═' | '┄' | '┈' | '╌' => "-",
+        '│' | '┃' | '║' | '┆' | '┊' | '╎' => "|",
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╔' | '╗' | '╚' | '╝' | '╠' | '╣' | '╦' | '╩' | '╬' => "+",
+        '╭' | '╮' => ".",
+        '╰' | '╯' => "'",
+        '↑' | '▲' => "^",
+        '↓' | '▼' | '▾' => "v",
+        '←' | '◀' => "<",
+        '→' | '▶' | '▸' => ">",
+        '●' | '•' => "*",
+        '✓' | '✔' => "x",
+        '…' => "~",
+        '█' | '▇' | '▆' | '▓' => "#",
+        '▅' | '▄' | '▃' | '▒' => "=",
+        '▂' | '▁' | '░' => "_",
+        // Blank braille, as charts fill their background with it
+        '\u{2800}' => " ",
+        '\u{2801}'..='\u{28ff}' => ".",
+        '🌿' => "*",
+        '🌐' => "@",
+        '🏷' => "#",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+/// Swaps every box drawing character, arrow and emoji on the screen for a
+/// plain ASCII one. Wide emoji keep their second column as a space, so
+/// nothing after them moves.
+pub fn to_ascii(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        if let Some(replacement) = ascii_symbol(&cell.symbol) {
+            cell.set_symbol(replacement);
+        }
+    }
+}
+
+/// The same swap for text printed outside the UI
+pub fn ascii_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match ascii_symbol(c.encode_utf8(&mut [0; 4])) {
+            Some(replacement) => replacement.to_string(),
+            None => c.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,125 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use crate::models::BlameLine;
+use super::{App, Focus, Theme};
+
+/// A file annotated line by line, as of one commit
+pub struct BlameView {
+    pub path: String,
+    pub commit: String,
+    pub lines: Vec<BlameLine>,
+    pub cursor: usize,
+    // Where a range of lines picked with the cursor starts
+    pub anchor: Option<usize>,
+}
+
+impl BlameView {
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.lines.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    /// Starts picking a range of lines at the cursor, or stops
+    pub fn toggle_anchor(&mut self) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some(self.cursor),
+        };
+    }
+
+    /// The first and last lines picked, counted from 0
+    pub fn selection(&self) -> (usize, usize) {
+        let anchor = self.anchor.unwrap_or(self.cursor);
+        (anchor.min(self.cursor), anchor.max(self.cursor))
+    }
+
+    /// The commit that last changed the line under the cursor
+    pub fn selected_commit(&self) -> Option<&str> {
+        self.lines.get(self.cursor).map(|line| line.hash.as_str())
+    }
+}
+
+/// Rough age like "3 days", enough to tell old code from new at a glance
+fn relative_age(seconds: i64) -> String {
+    let (value, unit) = match seconds.max(0) {
+        s if s < 3600 => (s / 60, "min"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s if s < 86400 * 30 => (s / 86400, "day"),
+        s if s < 86400 * 365 => (s / (86400 * 30), "month"),
+        s => (s / (86400 * 365), "year"),
+    };
+    if value == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", value, unit)
+    }
+}
+
+/// Shades from dim grey for the oldest line in the file to bright orange
+/// for the newest
+fn age_color(time: i64, oldest: i64, newest: i64) -> Color {
+    let t = if newest > oldest {
+        (time - oldest) as f64 / (newest - oldest) as f64
+    } else {
+        1.0
+    };
+    let mix = |from: f64, to: f64| (from + (to - from) * t) as u8;
+    Color::Rgb(mix(90.0, 255.0), mix(90.0, 170.0), mix(110.0, 50.0))
+}
+
+pub fn draw_blame(f: &mut Frame, app: &App, blame: &BlameView, area: Rect) {
+    let title = format!(
+        "Blame of {} @ {}{} (↑/↓ to move, Enter to jump to the commit, V to pick lines, f for their history, Esc to close)",
+        blame.path,
+        &blame.commit[..7.min(blame.commit.len())],
+        match blame.anchor {
+            Some(_) => format!(", lines {}-{} picked", blame.selection().0 + 1, blame.selection().1 + 1),
+            None => String::new(),
+        }
+    );
+    let block = super::pane_block(title, app.focus == Focus::Details);
+    let theme = Theme::current();
+
+    let now = chrono::Local::now().timestamp();
+    let oldest = blame.lines.iter().map(|l| l.time).min().unwrap_or(0);
+    let newest = blame.lines.iter().map(|l| l.time).max().unwrap_or(0);
+    let number_width = blame.lines.len().to_string().len();
+    let (first, last) = blame.selection();
+
+    // Keep the cursor on screen, drawing only the lines that fit
+    let height = area.height.saturating_sub(2) as usize;
+    let offset = (blame.cursor + 1).saturating_sub(height);
+    let lines: Vec<Line> = blame
+        .lines
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, line)| {
+            let annotation = theme.colour(age_color(line.time, oldest, newest));
+            let code = if i == blame.cursor {
+                theme.selected
+            } else if (first..=last).contains(&i) {
+                theme.selected_unfocused
+            } else {
+                Style::default()
+            };
+            let author: String = line.author.chars().take(14).collect();
+            Line::from(vec![
+                Span::styled(
+                    format!("{} {:<14} {:>9} ", &line.hash[..7.min(line.hash.len())], author, relative_age(now - line.time)),
+                    annotation,
+                ),
+                Span::styled(format!("{:>width$} ", i + 1, width = number_width), theme.muted),
+                Span::styled(line.content.clone(), code),
+            ])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -0,0 +1,153 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use super::confirm::centered_rect;
+use super::Theme;
+
+/// What a key press in the dialog asks main to do
+pub enum DialogEvent {
+    Nothing,
+    Submit,
+    Cancel,
+    ToggleAmend,
+}
+
+/// A small multi-line editor for the commit message
+pub struct CommitDialog {
+    pub lines: Vec<String>,
+    // Cursor position as (line, character) indices
+    pub row: usize,
+    pub col: usize,
+    pub amend: bool,
+    pub sign_off: bool,
+}
+
+impl CommitDialog {
+    pub fn new() -> Self {
+        CommitDialog { lines: vec![String::new()], row: 0, col: 0, amend: false, sign_off: false }
+    }
+
+    pub fn message(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replaces the message, leaving the cursor at its end
+    pub fn set_message(&mut self, message: &str) {
+        self.lines = message.lines().map(|l| l.to_string()).collect();
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.row = self.lines.len() - 1;
+        self.col = self.lines[self.row].chars().count();
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.col).map(|(i, _)| i).unwrap_or(line.len())
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> DialogEvent {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return DialogEvent::Cancel,
+            KeyCode::Char('s') if ctrl => return DialogEvent::Submit,
+            KeyCode::Char('a') if ctrl => return DialogEvent::ToggleAmend,
+            KeyCode::Char('o') if ctrl => self.sign_off = !self.sign_off,
+            KeyCode::Char(c) if !ctrl => {
+                let at = self.byte_index();
+                self.lines[self.row].insert(at, c);
+                self.col += 1;
+            },
+            KeyCode::Enter => {
+                let at = self.byte_index();
+                let rest = self.lines[self.row].split_off(at);
+                self.row += 1;
+                self.lines.insert(self.row, rest);
+                self.col = 0;
+            },
+            KeyCode::Backspace if self.col > 0 => {
+                self.col -= 1;
+                let at = self.byte_index();
+                self.lines[self.row].remove(at);
+            },
+            KeyCode::Backspace if self.row > 0 => {
+                // Join this line onto the end of the previous one
+                let line = self.lines.remove(self.row);
+                self.row -= 1;
+                self.col = self.line_len(self.row);
+                self.lines[self.row].push_str(&line);
+            },
+            KeyCode::Left if self.col > 0 => self.col -= 1,
+            KeyCode::Right if self.col < self.line_len(self.row) => self.col += 1,
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.col = self.col.min(self.line_len(self.row));
+            },
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.col = self.col.min(self.line_len(self.row));
+            },
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(self.row),
+            _ => {},
+        }
+        DialogEvent::Nothing
+    }
+}
+
+pub fn draw_commit_dialog(f: &mut Frame, dialog: &CommitDialog, area: Rect) {
+    let area = centered_rect(80, 16, area);
+    let theme = Theme::current();
+    let checkbox = |on: bool| if on { "[x]" } else { "[ ]" };
+    let title = format!(
+        "{} (Ctrl-S to commit, Esc to cancel) {} amend (Ctrl-A) {} sign-off (Ctrl-O)",
+        if dialog.amend { "Amend Commit" } else { "Commit" },
+        checkbox(dialog.amend),
+        checkbox(dialog.sign_off),
+    );
+
+    // Keep the cursor's line in view for long messages
+    let height = area.height.saturating_sub(2) as usize;
+    let offset = (dialog.row + 1).saturating_sub(height);
+    let cursor_style = theme.cursor;
+    let lines: Vec<Line> = dialog
+        .lines
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(row, line)| {
+            // The summary line is what shows up in the log, so make it stand out
+            let style = if row == 0 {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            if row != dialog.row {
+                return Line::from(Span::styled(line.clone(), style));
+            }
+            let before: String = line.chars().take(dialog.col).collect();
+            let under: String = line.chars().nth(dialog.col).map(|c| c.to_string()).unwrap_or_else(|| " ".to_string());
+            let after: String = line.chars().skip(dialog.col + 1).collect();
+            Line::from(vec![
+                Span::styled(before, style),
+                Span::styled(under, cursor_style),
+                Span::styled(after, style),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title(title).borders(Borders::ALL).border_style(theme.border));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
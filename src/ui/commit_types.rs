@@ -0,0 +1,97 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+use crate::conventional::{self, Conventional};
+use crate::models::CommitInfo;
+use super::{App, Theme};
+
+// Which of the theme's lane colours a type is shown in: green for
+// features, red for fixes and so on
+fn type_lane(kind: &str) -> Option<usize> {
+    match kind {
+        "fix" => Some(0),
+        "feat" => Some(1),
+        "test" => Some(2),
+        "perf" => Some(3),
+        "refactor" => Some(4),
+        "docs" => Some(5),
+        _ => None,
+    }
+}
+
+/// The commit's type in lower case, when its message follows
+/// Conventional Commits
+pub fn commit_type(commit: &CommitInfo) -> Option<String> {
+    conventional::parse(&commit.message).map(|parsed| parsed.kind.to_lowercase())
+}
+
+/// `feat(scope)` on the type's colour, followed by a red `!` when the
+/// change is breaking
+pub fn type_badge(parsed: &Conventional) -> Vec<Span<'static>> {
+    let kind = parsed.kind.to_lowercase();
+    let text = match parsed.scope {
+        Some(scope) => format!("{}({})", kind, scope),
+        None => kind.clone(),
+    };
+    let theme = Theme::current();
+    let style = match type_lane(&kind) {
+        Some(lane) => theme.badge(lane),
+        None => theme.cursor,
+    };
+    let mut spans = vec![Span::styled(text, style)];
+    if parsed.breaking {
+        spans.push(Span::styled("!", theme.error.add_modifier(Modifier::BOLD)));
+    }
+    spans.push(Span::raw(" "));
+    spans
+}
+
+/// Every type among the loaded commits with how many use it, most
+/// common first
+pub fn commit_types<'a>(commits: impl Iterator<Item = &'a CommitInfo>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for kind in commits.filter_map(commit_type) {
+        *counts.entry(kind).or_default() += 1;
+    }
+    let mut types: Vec<(String, usize)> = counts.into_iter().collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    types
+}
+
+pub fn draw_type_filter(f: &mut Frame, app: &App, area: Rect) {
+    if app.commit_types.is_empty() {
+        let paragraph = Paragraph::new("None of the loaded commits follow Conventional Commits.")
+            .block(Block::default().title("Type Filter").borders(Borders::ALL));
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let theme = Theme::current();
+    let items: Vec<ListItem> = app
+        .commit_types
+        .iter()
+        .enumerate()
+        .map(|(i, (kind, count))| {
+            let style = if i == app.type_selector_index {
+                theme.selected
+            } else if app.type_filter.as_ref() == Some(kind) {
+                theme.current
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<12}", kind), style.patch(type_lane(kind).map(|lane| theme.lane(lane)).unwrap_or_default()).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} commit{}", count, if *count == 1 { "" } else { "s" }), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Filter by Type (↑/↓ to navigate, Enter to select, Esc to cancel)").borders(Borders::ALL));
+    f.render_widget(list, area);
+}
@@ -0,0 +1,147 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::models::{ChangeStatus, CommitInfo, Comparison};
+use super::Theme;
+
+/// Two refs side by side: what each has that the other doesn't, and the
+/// files changed between them
+pub struct CompareView {
+    pub comparison: Comparison,
+    // 0 for the base's column, 1 for the target's
+    pub side: usize,
+    pub cursors: [usize; 2],
+    pub files_scroll: usize,
+}
+
+impl CompareView {
+    pub fn new(comparison: Comparison) -> Self {
+        CompareView { comparison, side: 1, cursors: [0, 0], files_scroll: 0 }
+    }
+
+    fn commits(&self, side: usize) -> &[CommitInfo] {
+        if side == 0 {
+            &self.comparison.only_base
+        } else {
+            &self.comparison.only_target
+        }
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.commits(self.side).len().saturating_sub(1) as i64;
+        let cursor = &mut self.cursors[self.side];
+        *cursor = (*cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    pub fn switch_side(&mut self) {
+        self.side = 1 - self.side;
+    }
+
+    pub fn scroll_files(&mut self, delta: i32) {
+        let max = self.comparison.files.len().saturating_sub(1) as i64;
+        self.files_scroll = (self.files_scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+/// A column's title with how many commits are only on its side, and how
+/// many of them are listed when that's not all
+fn column_title(name: &str, listed: usize, total: usize) -> String {
+    match listed < total {
+        true => format!("Only in {} ({}, newest {} listed)", name, total, listed),
+        false => format!("Only in {} ({})", name, total),
+    }
+}
+
+fn draw_column(f: &mut Frame, view: &CompareView, side: usize, title: String, area: Rect) {
+    let commits = view.commits(side);
+    let cursor = view.cursors[side];
+    let height = area.height.saturating_sub(2) as usize;
+    let offset = (cursor + 1).saturating_sub(height);
+    let theme = Theme::current();
+
+    let mut lines: Vec<Line> = commits
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, commit)| {
+            let style = if i == cursor && side == view.side {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", &commit.hash[..7]), theme.hash),
+                Span::styled(commit.message.lines().next().unwrap_or("").to_string(), style),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("Nothing", theme.muted)));
+    }
+
+    let border = if side == view.side { theme.border } else { Style::default() };
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title(title).borders(Borders::ALL).border_style(border));
+    f.render_widget(paragraph, area);
+}
+
+pub fn draw_compare_view(f: &mut Frame, view: &CompareView, area: Rect) {
+    let comparison = &view.comparison;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    let base_title = column_title(&comparison.base, comparison.only_base.len(), comparison.base_count);
+    let target_title = column_title(&comparison.target, comparison.only_target.len(), comparison.target_count);
+    draw_column(f, view, 0, base_title, columns[0]);
+    draw_column(f, view, 1, target_title, columns[1]);
+
+    let theme = Theme::current();
+    let (mut added, mut removed) = (0, 0);
+    let width = comparison.files.iter().map(|file| file.path().chars().count()).max().unwrap_or(0);
+    let lines: Vec<Line> = comparison
+        .files
+        .iter()
+        .map(|file| {
+            let (file_added, file_removed) = file.line_stats();
+            added += file_added;
+            removed += file_removed;
+            let (marker, marker_style) = match file.status {
+                ChangeStatus::Added => ("A", theme.added),
+                ChangeStatus::Deleted => ("D", theme.removed),
+                ChangeStatus::Renamed | ChangeStatus::Copied => ("R", theme.renamed),
+                _ => ("M", theme.modified),
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", marker), marker_style),
+                Span::raw(format!("{:<width$} ", file.path(), width = width)),
+                Span::styled(format!("+{}", file_added), theme.added),
+                Span::raw(" "),
+                Span::styled(format!("-{}", file_removed), theme.removed),
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        "{}...{}: {} files changed, +{} -{} (↑/↓ to move, ←/→ to switch side, PgUp/PgDn to scroll, Esc to close)",
+        comparison.base,
+        comparison.target,
+        comparison.files.len(),
+        added,
+        removed
+    );
+    let files = Paragraph::new(lines)
+        .scroll((view.files_scroll as u16, 0))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(files, rows[1]);
+}
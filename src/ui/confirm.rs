@@ -0,0 +1,65 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use crate::models::WorkingFile;
+use super::Theme;
+
+/// Something destructive that waits for a yes before it happens
+pub enum PendingAction {
+    Discard(WorkingFile),
+    // Stashes are referred to by their position in the list
+    StashApply(usize),
+    StashPop(usize),
+    StashDrop(usize),
+    // Commits to replay onto HEAD, oldest first
+    CherryPick(Vec<String>),
+    Revert(String),
+    CheckoutBranch(String),
+    CheckoutCommit(String),
+    DeleteBranch(String),
+    DeleteTag(String),
+    Push { remote: String, branch: String },
+    Unshallow(String),
+}
+
+pub struct Confirmation {
+    pub message: String,
+    pub action: PendingAction,
+}
+
+/// A box in the middle of `area`, `width` columns wide and `height` rows tall
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+pub fn draw_confirm(f: &mut Frame, confirmation: &Confirmation, area: Rect) {
+    // Messages can run over several lines, e.g. to list what will happen
+    let mut lines: Vec<Line> = confirmation.message.lines().map(|line| Line::from(line.to_string())).collect();
+    let width = 60.min(area.width).saturating_sub(2).max(1) as usize;
+    let rows: usize = confirmation.message.lines().map(|line| line.chars().count().max(1).div_ceil(width)).sum();
+    let area = centered_rect(60, (rows as u16 + 4).max(6), area);
+    let theme = Theme::current();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("y", theme.added.add_modifier(Modifier::BOLD)),
+        Span::raw(" to confirm, any other key to cancel"),
+    ]));
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().title("Confirm").borders(Borders::ALL).border_style(theme.error));
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use crate::config::DateFormat;
+
+const DAY: i64 = 24 * 3600;
+
+/// The date filter's presets, with how far back each reaches
+pub const DATE_PRESETS: [(&str, i64); 5] = [
+    ("Last 24 hours", DAY),
+    ("Last week", 7 * DAY),
+    ("Last month", 30 * DAY),
+    ("Last 3 months", 90 * DAY),
+    ("Last year", 365 * DAY),
+];
+
+fn local(time: i64) -> DateTime<Local> {
+    match Local.timestamp_opt(time, 0) {
+        chrono::LocalResult::Single(dt) => dt,
+        chrono::LocalResult::Ambiguous(dt, _) => dt,
+        chrono::LocalResult::None => Local::now(),
+    }
+}
+
+/// "just now", "5 minutes ago", "3 days ago", ... rounded down to the
+/// largest whole unit
+pub fn relative(time: i64, now: i64) -> String {
+    let seconds = (now - time).max(0);
+    let units = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    for (size, name) in units {
+        let count = seconds / size;
+        if count > 0 {
+            return format!("{} {}{} ago", count, name, if count == 1 { "" } else { "s" });
+        }
+    }
+    "just now".to_string()
+}
+
+pub fn day(time: i64) -> String {
+    local(time).format("%Y-%m-%d").to_string()
+}
+
+/// The full timestamp, in ISO-8601 when that format is picked
+pub fn exact(time: i64, format: DateFormat) -> String {
+    match format {
+        DateFormat::Iso => local(time).to_rfc3339(),
+        _ => local(time).format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+pub fn format(time: i64, format: DateFormat) -> String {
+    match format {
+        DateFormat::Relative => relative(time, Local::now().timestamp()),
+        _ => exact(time, format),
+    }
+}
+
+fn from_local(time: NaiveDateTime) -> Option<i64> {
+    Local.from_local_datetime(&time).earliest().map(|dt| dt.timestamp())
+}
+
+/// A point in time typed by hand: `2024-05-01`, `2024-05-01 14:30`,
+/// `today`, `yesterday`, or `3 days ago` in minutes, hours, days, weeks,
+/// months or years. A day on its own means its start, or its end when
+/// it closes a range.
+pub fn parse(input: &str, now: DateTime<Local>, end_of_day: bool) -> Option<i64> {
+    let input = input.trim().to_lowercase();
+    let day_time = if end_of_day {
+        NaiveTime::from_hms_opt(23, 59, 59)?
+    } else {
+        NaiveTime::MIN
+    };
+    let today = now.date_naive();
+    match input.as_str() {
+        "now" => return Some(now.timestamp()),
+        "today" => return from_local(today.and_time(day_time)),
+        "yesterday" => return from_local(today.pred_opt()?.and_time(day_time)),
+        _ => {},
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(&input, "%Y-%m-%d %H:%M") {
+        return from_local(time);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return from_local(date.and_time(day_time));
+    }
+
+    let words: Vec<&str> = input.trim_end_matches(" ago").split_whitespace().collect();
+    let (count, unit) = match words.as_slice() {
+        [count, unit] => (count.parse::<i64>().ok()?, unit.trim_end_matches('s')),
+        _ => return None,
+    };
+    let seconds = match unit {
+        "minute" | "min" => 60,
+        "hour" => 3600,
+        "day" => DAY,
+        "week" => 7 * DAY,
+        "month" => 30 * DAY,
+        "year" => 365 * DAY,
+        _ => return None,
+    };
+    Some((now - Duration::seconds(count.checked_mul(seconds)?)).timestamp())
+}
+
+/// `since..until`, either of which may be left out for no limit; a single
+/// point in time on its own is taken as since
+pub fn parse_range(input: &str, now: DateTime<Local>) -> Result<(i64, i64), String> {
+    let (since, until) = input.split_once("..").unwrap_or((input, ""));
+    let bound = |text: &str, end_of_day: bool, unbounded: i64| {
+        if text.trim().is_empty() {
+            Ok(unbounded)
+        } else {
+            parse(text, now, end_of_day).ok_or_else(|| format!("Can't read \"{}\" as a date", text.trim()))
+        }
+    };
+    let range = (bound(since, false, i64::MIN)?, bound(until, true, i64::MAX)?);
+    if range.0 > range.1 {
+        return Err("The range ends before it starts".to_string());
+    }
+    Ok(range)
+}
+
+/// "2024-05-01 to 2024-06-01", "since 2024-05-01" or "until 2024-06-01"
+pub fn describe_range((from, to): (i64, i64)) -> String {
+    match (from, to) {
+        (i64::MIN, i64::MAX) => "any time".to_string(),
+        (i64::MIN, to) => format!("until {}", day(to)),
+        (from, i64::MAX) => format!("since {}", day(from)),
+        (from, to) => format!("{} to {}", day(from), day(to)),
+    }
+}
@@ -0,0 +1,43 @@
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::git::format_size;
+use crate::models::DiffCacheStats;
+use super::Theme;
+
+fn percent(part: usize, whole: usize) -> f64 {
+    part as f64 * 100.0 / whole.max(1) as f64
+}
+
+/// How the caches behind the views are doing, for tuning their budgets
+pub fn draw_debug_view(f: &mut Frame, diffs: &DiffCacheStats, area: Rect) {
+    let theme = Theme::current();
+    let row = |label: &str, value: String| {
+        Line::from(vec![Span::styled(format!("  {:<12}", label), theme.muted), Span::raw(value)])
+    };
+    let lookups = diffs.hits + diffs.misses;
+    let lines = vec![
+        Line::from(Span::styled("Commit diffs", theme.accent)),
+        row("Budget", format!("{} (diff_cache_mb in the config)", format_size(diffs.budget as u64))),
+        row(
+            "Used",
+            format!(
+                "{} ({:.1}%) in {} diff{}",
+                format_size(diffs.bytes as u64),
+                percent(diffs.bytes, diffs.budget),
+                diffs.entries,
+                if diffs.entries == 1 { "" } else { "s" }
+            ),
+        ),
+        row("Hits", format!("{} of {} lookups ({:.0}%)", diffs.hits, lookups, percent(diffs.hits, lookups))),
+        row("Evicted", format!("{} diffs, {}", diffs.evictions, format_size(diffs.evicted_bytes as u64))),
+        row("Too big", format!("{} diffs larger than the whole budget, not kept", diffs.rejected)),
+    ];
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title("Debug (Esc to close)").borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,248 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+use std::ops::Range;
+use crate::git::{binary_summary, omitted_note};
+use crate::models::{DiffLine, FileChange, LineKind};
+use super::highlight::{emphasise, highlight_diff};
+use super::word_diff::{changed_words, WordChanges, DIFF_BUDGET};
+use super::{App, Theme};
+
+/// One row of the side-by-side view: either a heading spanning both
+/// columns, or an old line and a new line aligned next to each other, with
+/// the words that changed between them
+#[derive(Debug, Clone)]
+pub enum SplitRow {
+    File(String),
+    Hunk(String),
+    Pair(Option<DiffLine>, Option<DiffLine>, Option<WordChanges>),
+}
+
+/// Highlights the unified diff of each file, or of the one at `index`,
+/// putting the sizes of binary files in place of git's line about them and
+/// saying how much of a large file's diff was left out
+pub fn unified_lines(patch: &str, files: &[FileChange], index: Option<usize>) -> Vec<Line<'static>> {
+    let theme = Theme::current();
+    let indices = match index {
+        Some(index) => index..index + 1,
+        None => 0..files.len(),
+    };
+    let mut lines = Vec::new();
+    for (index, file) in indices.filter_map(|index| Some((index, files.get(index)?))) {
+        let section = file_section(patch, index);
+        if file.binary {
+            let text: String = section.lines().filter(|line| !line.starts_with("Binary files ")).map(|line| format!("{}\n", line)).collect();
+            lines.extend(highlight_diff(&text));
+            lines.push(Line::from(Span::styled(binary_summary(file.status, file.old_size, file.new_size), theme.muted)));
+        } else {
+            lines.extend(highlight_diff(section));
+        }
+        if file.omitted_lines > 0 {
+            lines.push(Line::from(Span::styled(omitted_note(file.omitted_lines), theme.muted)));
+        }
+    }
+    lines
+}
+
+/// Aligns each hunk into rows. Context lines appear on both sides, and a run
+/// of removals is paired up with the run of additions that follows it.
+pub fn build_split_rows(files: &[FileChange]) -> Vec<SplitRow> {
+    let mut rows = Vec::new();
+    let mut budget = DIFF_BUDGET;
+
+    for file in files {
+        rows.push(SplitRow::File(file.path().to_string()));
+        if file.binary {
+            rows.push(SplitRow::Hunk(binary_summary(file.status, file.old_size, file.new_size)));
+        }
+        for hunk in &file.hunks {
+            rows.push(SplitRow::Hunk(hunk.header.clone()));
+
+            let mut removed: Vec<&DiffLine> = Vec::new();
+            let mut added: Vec<&DiffLine> = Vec::new();
+            for line in &hunk.lines {
+                match line.kind {
+                    LineKind::Removed => {
+                        if !added.is_empty() {
+                            flush_changes(&mut rows, &mut removed, &mut added, &mut budget);
+                        }
+                        removed.push(line);
+                    },
+                    LineKind::Added => added.push(line),
+                    LineKind::Context => {
+                        flush_changes(&mut rows, &mut removed, &mut added, &mut budget);
+                        rows.push(SplitRow::Pair(Some(line.clone()), Some(line.clone()), None));
+                    },
+                }
+            }
+            flush_changes(&mut rows, &mut removed, &mut added, &mut budget);
+        }
+        if file.omitted_lines > 0 {
+            rows.push(SplitRow::Hunk(omitted_note(file.omitted_lines)));
+        }
+    }
+
+    rows
+}
+
+/// The part of a unified patch belonging to its `index`th file
+pub fn file_section(patch: &str, index: usize) -> &str {
+    let starts: Vec<usize> = patch
+        .match_indices("diff --git")
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || patch.as_bytes()[i - 1] == b'\n')
+        .collect();
+    match starts.get(index) {
+        Some(&start) => {
+            let end = starts.get(index + 1).copied().unwrap_or(patch.len());
+            &patch[start..end]
+        },
+        None => "",
+    }
+}
+
+fn flush_changes(rows: &mut Vec<SplitRow>, removed: &mut Vec<&DiffLine>, added: &mut Vec<&DiffLine>, budget: &mut usize) {
+    for i in 0..removed.len().max(added.len()) {
+        let (old, new) = (removed.get(i), added.get(i));
+        let words = old.zip(new).and_then(|(old, new)| changed_words(&old.content, &new.content, budget));
+        rows.push(SplitRow::Pair(old.map(|l| (*l).clone()), new.map(|l| (*l).clone()), words));
+    }
+    removed.clear();
+    added.clear();
+}
+
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.replace('\t', "    ").chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
+
+/// Like `fit`, for styled text scrolled along by `offset` characters
+fn fit_spans(spans: Vec<Span<'static>>, width: usize, offset: usize, pad: Style) -> Vec<Span<'static>> {
+    let mut fitted = Vec::new();
+    let (mut skip, mut room) = (offset, width);
+    for span in spans {
+        let text = span.content.replace('\t', "    ");
+        let length = text.chars().count();
+        let shown: String = text.chars().skip(skip).take(room).collect();
+        skip = skip.saturating_sub(length);
+        room -= shown.chars().count();
+        if !shown.is_empty() {
+            fitted.push(Span::styled(shown, span.style));
+        }
+    }
+    fitted.push(Span::styled(" ".repeat(room), pad));
+    fitted
+}
+
+fn side_spans(line: Option<&DiffLine>, words: &[Range<usize>], old_side: bool, width: usize, offset: usize, theme: &Theme) -> Vec<Span<'static>> {
+    let line = match line {
+        Some(line) => line,
+        None => return vec![Span::styled(fit("", width), theme.selected_unfocused)],
+    };
+
+    let lineno = if old_side { line.old_lineno } else { line.new_lineno };
+    let number = lineno.map(|n| format!("{:>4} ", n)).unwrap_or_else(|| "     ".to_string());
+    let style = match line.kind {
+        LineKind::Added => theme.added,
+        LineKind::Removed => theme.removed,
+        LineKind::Context => Style::default(),
+    };
+
+    let emphasis = if old_side { theme.removed_word } else { theme.added_word };
+    let number_width = number.chars().count().min(width);
+    let mut spans = vec![Span::styled(fit(&number, number_width), theme.muted)];
+    let text = emphasise(vec![(style, line.content.clone())], words, emphasis);
+    spans.extend(fit_spans(text, width - number_width, offset, style));
+    spans
+}
+
+/// One row of the split view, each side's text scrolled along by `offset`
+fn render_split_row(row: &SplitRow, width: usize, offset: usize, theme: &Theme) -> Line<'static> {
+    match row {
+        SplitRow::File(path) => Line::from(Span::styled(
+            path.clone(),
+            theme.file_header.add_modifier(Modifier::BOLD),
+        )),
+        SplitRow::Hunk(header) => Line::from(Span::styled(header.clone(), theme.accent)),
+        SplitRow::Pair(old, new, words) => {
+            let half = width.saturating_sub(1) / 2;
+            let (old_words, new_words) = match words {
+                Some(words) => (words.old.as_slice(), words.new.as_slice()),
+                None => (&[][..], &[][..]),
+            };
+            let mut spans = side_spans(old.as_ref(), old_words, true, half, offset, theme);
+            spans.push(Span::styled("│", theme.muted));
+            spans.extend(side_spans(new.as_ref(), new_words, false, width.saturating_sub(1) - half, offset, theme));
+            Line::from(spans)
+        },
+    }
+}
+
+pub fn draw_diff_view(f: &mut Frame, app: &App, area: Rect) {
+    let total = app.diff_line_count();
+    let mode = if app.split_diff { "split" } else { "unified" };
+    let file = match app.selected_file_change() {
+        Some(file) => format!(" of {}", file.path()),
+        None => String::new(),
+    };
+    let cut_short = if app.diff_cut_short() { ", cut short (Ctrl-l for all of it)" } else { "" };
+    let options: String = [app.diff_context.label(), app.whitespace.label()]
+        .into_iter()
+        .flatten()
+        .map(|label| format!(", {}", label))
+        .collect();
+    let sideways = match (app.wrap_diff, app.split_diff) {
+        (true, false) => ", wrapped",
+        (false, _) if app.diff_hscroll > 0 => ", scrolled right",
+        _ => "",
+    };
+    let title = format!(
+        "Diff{}, {}{}{}{} ({}/{}, ↑/↓/PgUp/PgDn to scroll, ( and ) to scroll sideways, ←/→ to pick a file, | to toggle split, Esc to close)",
+        file,
+        mode,
+        sideways,
+        options,
+        cut_short,
+        (app.diff_scroll as usize + 1).min(total),
+        total
+    );
+
+    // Only the lines that fit in the pane are handed to the widget
+    let height = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2) as usize;
+    let theme = Theme::current();
+    let lines: Vec<Line> = if total == 0 {
+        vec![Line::from("No diff available")]
+    } else if app.split_diff {
+        app.split_rows
+            .iter()
+            .skip(app.diff_scroll as usize)
+            .take(height)
+            .map(|row| render_split_row(row, width, app.diff_hscroll as usize, &theme))
+            .collect()
+    } else {
+        app.diff_lines
+            .iter()
+            .skip(app.diff_scroll as usize)
+            .take(height)
+            .cloned()
+            .collect()
+    };
+
+    let mut paragraph = Paragraph::new(lines)
+        .block(super::pane_block(title, app.focus == super::Focus::Details));
+    // Split rows are already cut to fit each side
+    if app.wrap_diff && !app.split_diff {
+        paragraph = paragraph.wrap(Wrap { trim: false });
+    } else if !app.split_diff {
+        paragraph = paragraph.scroll((0, app.diff_hscroll));
+    }
+
+    f.render_widget(paragraph, area);
+}
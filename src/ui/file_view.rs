@@ -0,0 +1,163 @@
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use regex::Regex;
+use super::highlight::{emphasise, highlight_file};
+use super::{App, Focus, Theme};
+
+/// A file's whole content as of one commit, opened from the file list
+pub struct FileView {
+    pub path: String,
+    pub commit: String,
+    // Said in the title when it isn't the selected commit's own version
+    pub note: Option<String>,
+    // Each line's text, searched through, and its highlighted spans
+    text: Vec<String>,
+    lines: Vec<Line<'static>>,
+    pub scroll: usize,
+    // The line picked, and where a range of lines picked with it starts
+    pub cursor: usize,
+    pub anchor: Option<usize>,
+    // The search, the lines it matches and which of them was jumped to
+    pub query: String,
+    pattern: Option<Regex>,
+    matches: Vec<usize>,
+    current: usize,
+}
+
+impl FileView {
+    pub fn new(path: String, commit: String, note: Option<String>, content: &str) -> Self {
+        FileView {
+            lines: highlight_file(&path, content),
+            text: content.lines().map(str::to_string).collect(),
+            path,
+            commit,
+            note,
+            scroll: 0,
+            cursor: 0,
+            anchor: None,
+            query: String::new(),
+            pattern: None,
+            matches: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Moves the cursor, scrolling to keep it among the `height` lines shown
+    pub fn move_cursor(&mut self, delta: i32, height: usize) {
+        let max = self.lines.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+        self.scroll = self.scroll.clamp((self.cursor + 1).saturating_sub(height.max(1)), self.cursor);
+    }
+
+    /// Starts picking a range of lines at the cursor, or stops
+    pub fn toggle_anchor(&mut self) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some(self.cursor),
+        };
+    }
+
+    /// The first and last lines picked, counted from 0
+    pub fn selection(&self) -> (usize, usize) {
+        let anchor = self.anchor.unwrap_or(self.cursor);
+        (anchor.min(self.cursor), anchor.max(self.cursor))
+    }
+
+    /// Finds the lines matching the text, or /regex/, and jumps to the
+    /// first at or after the top of the view. Text in lower case matches
+    /// either case.
+    pub fn search(&mut self, query: &str) -> Result<(), regex::Error> {
+        let pattern = match query.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            Some(pattern) if !pattern.is_empty() => Regex::new(pattern)?,
+            _ if query.chars().any(char::is_uppercase) => Regex::new(&regex::escape(query))?,
+            _ => Regex::new(&format!("(?i){}", regex::escape(query)))?,
+        };
+        self.matches = (0..self.text.len()).filter(|&i| pattern.is_match(&self.text[i])).collect();
+        self.pattern = Some(pattern);
+        self.query = query.to_string();
+        self.current = self.matches.iter().position(|&line| line >= self.scroll).unwrap_or(0);
+        if let Some(&line) = self.matches.get(self.current) {
+            (self.cursor, self.scroll) = (line, line);
+        }
+        Ok(())
+    }
+
+    /// Moves to the next or previous matching line, wrapping around
+    pub fn jump_to_match(&mut self, forward: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let count = self.matches.len();
+        self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+        self.cursor = self.matches[self.current];
+        self.scroll = self.cursor;
+    }
+}
+
+pub fn draw_file_view(f: &mut Frame, app: &App, view: &FileView, area: Rect) {
+    let theme = Theme::current();
+    let selection = match view.anchor {
+        Some(_) => {
+            let (first, last) = view.selection();
+            format!(", lines {}-{} picked", first + 1, last + 1)
+        },
+        None => String::new(),
+    };
+    let search = match (&view.pattern, view.matches.len()) {
+        (None, _) => String::new(),
+        (Some(_), 0) => format!(", no lines match {}", view.query),
+        (Some(_), count) => format!(", match {}/{} for {}", view.current + 1, count, view.query),
+    };
+    let title = format!(
+        "{} @ {}{}{}{} ({}/{}, ↑/↓/PgUp/PgDn to move, / to search, n/N for the next/previous match, V to pick lines, f for their history, Esc to close)",
+        view.path,
+        &view.commit[..7.min(view.commit.len())],
+        view.note.as_deref().map(|note| format!(" ({})", note)).unwrap_or_default(),
+        selection,
+        search,
+        (view.cursor + 1).min(view.lines.len()),
+        view.lines.len()
+    );
+    let block = super::pane_block(title, app.focus == Focus::Details);
+
+    let height = area.height.saturating_sub(2) as usize;
+    let number_width = view.lines.len().to_string().len();
+    let current = view.matches.get(view.current).copied();
+    let (first, last) = view.selection();
+    let lines: Vec<Line> = view
+        .lines
+        .iter()
+        .enumerate()
+        .skip(view.scroll)
+        .take(height)
+        .map(|(i, line)| {
+            let number_style = if i == view.cursor {
+                theme.selected
+            } else if (first..=last).contains(&i) {
+                theme.selected_unfocused
+            } else if Some(i) == current {
+                theme.accent
+            } else {
+                theme.muted
+            };
+            let mut spans = vec![Span::styled(format!("{:>width$} ", i + 1, width = number_width), number_style)];
+            let found: Vec<_> = match &view.pattern {
+                Some(pattern) => pattern.find_iter(&view.text[i]).map(|found| found.range()).collect(),
+                None => Vec::new(),
+            };
+            if found.is_empty() {
+                spans.extend(line.spans.iter().cloned());
+            } else {
+                let parts = line.spans.iter().map(|span| (span.style, span.content.to_string())).collect();
+                spans.extend(emphasise(parts, &found, theme.search_match));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
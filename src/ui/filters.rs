@@ -0,0 +1,99 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem},
+    Frame,
+};
+use crate::models::MergeFilter;
+use super::{dates, App, Theme};
+
+/// Everything the commit list can be narrowed down by. The first six are
+/// applied by the loader; the type filter and pickaxe work on what's loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    Author,
+    Dates,
+    Paths,
+    Message,
+    Merges,
+    FirstParent,
+    Type,
+    Pickaxe,
+}
+
+pub const FILTER_KINDS: [FilterKind; 8] = [
+    FilterKind::Author,
+    FilterKind::Dates,
+    FilterKind::Paths,
+    FilterKind::Message,
+    FilterKind::Merges,
+    FilterKind::FirstParent,
+    FilterKind::Type,
+    FilterKind::Pickaxe,
+];
+
+impl FilterKind {
+    fn label(self) -> &'static str {
+        match self {
+            FilterKind::Author => "Author",
+            FilterKind::Dates => "Dates",
+            FilterKind::Paths => "Paths",
+            FilterKind::Message => "Message",
+            FilterKind::Merges => "Merges",
+            FilterKind::FirstParent => "Mainline",
+            FilterKind::Type => "Type",
+            FilterKind::Pickaxe => "Diff contains",
+        }
+    }
+}
+
+/// The filter's setting, or None when it lets every commit through
+pub fn describe(app: &App, kind: FilterKind) -> Option<String> {
+    let filters = &app.filters;
+    match kind {
+        FilterKind::Author => filters.author.as_ref().map(|author| format!("author: {}", author.name)),
+        FilterKind::Dates => filters.dates.map(dates::describe_range),
+        FilterKind::Paths => filters.paths.as_ref().map(|paths| format!("paths: {}", paths.describe())),
+        FilterKind::Message => filters.message.as_ref().map(|message| format!("message: /{}/", message.as_str())),
+        FilterKind::Merges => match filters.merges {
+            MergeFilter::All => None,
+            MergeFilter::Only => Some("merges only".to_string()),
+            MergeFilter::Exclude => Some("no merges".to_string()),
+        },
+        FilterKind::FirstParent => filters.first_parent.then(|| "first parent only".to_string()),
+        FilterKind::Type => app.type_filter.as_ref().map(|kind| format!("type: {}", kind)),
+        FilterKind::Pickaxe => app.pickaxe.as_ref().map(|pickaxe| format!("diffs with {}", pickaxe.query)),
+    }
+}
+
+/// The loader's filters that are in use, as shown in the list's title
+pub fn active_filters(app: &App) -> impl Iterator<Item = String> + '_ {
+    FILTER_KINDS[..6].iter().filter_map(|&kind| describe(app, kind))
+}
+
+pub fn draw_filter_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let items: Vec<ListItem> = FILTER_KINDS
+        .iter()
+        .enumerate()
+        .map(|(i, &kind)| {
+            let style = if i == app.filter_panel_index {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            let value = match describe(app, kind) {
+                Some(value) => Span::styled(value, style.patch(theme.current)),
+                None => Span::styled("any", style.patch(theme.muted)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<15}", kind.label()), style.add_modifier(Modifier::BOLD)),
+                value,
+            ]))
+        })
+        .collect();
+
+    let title = "Filters (↑/↓ to navigate, Enter to change, d to clear, Esc to close)";
+    f.render_widget(List::new(items).block(Block::default().title(title).borders(Borders::ALL)), area);
+}
@@ -0,0 +1,87 @@
+use crate::models::BranchInfo;
+
+/// How many branches picked lately are remembered for each repository
+pub const MAX_RECENT: usize = 10;
+
+/// Whether the character at `i` starts a word: the start of the text, after
+/// a separator, or a capital after a lower case letter
+fn starts_word(chars: &[char], i: usize) -> bool {
+    i == 0 || matches!(chars[i - 1], '/' | '-' | '_' | '.' | ' ') || (chars[i - 1].is_lowercase() && chars[i].is_uppercase())
+}
+
+/// Scores how well `query` matches `text` as a subsequence, ignoring case,
+/// with the positions of the characters matched, or None if it doesn't.
+/// Matches that start words or run together score higher, and gaps lower.
+pub fn fuzzy_match(text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    // Where the first match from the left ends, then the latest start that
+    // still reaches it, which makes the match as tight as it can be there
+    let mut matched = 0;
+    let mut end = None;
+    for (i, &c) in lower.iter().enumerate() {
+        if c == query[matched] {
+            matched += 1;
+            if matched == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut start = end;
+    let mut left = query.len();
+    for i in (0..=end).rev() {
+        if lower[i] == query[left - 1] {
+            left -= 1;
+            if left == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    let mut positions = Vec::with_capacity(query.len());
+    for (i, &c) in lower.iter().enumerate().take(end + 1).skip(start) {
+        if positions.len() < query.len() && c == query[positions.len()] {
+            positions.push(i);
+        }
+    }
+
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += 16;
+        if starts_word(&chars, i) {
+            score += if n == 0 { 16 } else { 8 };
+        }
+        if n > 0 {
+            let gap = (i - positions[n - 1] - 1) as i64;
+            score += if gap == 0 { 4 } else { -3 - gap };
+        }
+    }
+    Some((score, positions))
+}
+
+/// The branches to list, as indices into `branches` with the characters
+/// matching the query. Without a query, those picked lately come first,
+/// most recent at the top, then the rest as they are. With one, only the
+/// branches matching it are listed, best first, recent ones winning ties.
+pub fn order_branches(branches: &[BranchInfo], query: &str, recent: &[String]) -> Vec<(usize, Vec<usize>)> {
+    let rank = |name: &str| recent.iter().position(|r| r == name).unwrap_or(recent.len());
+    let mut order: Vec<(usize, i64, Vec<usize>)> = branches
+        .iter()
+        .enumerate()
+        .filter_map(|(i, branch)| fuzzy_match(&branch.name, query).map(|(score, positions)| (i, score, positions)))
+        .collect();
+    if query.trim().is_empty() {
+        order.sort_by_key(|(i, _, _)| rank(&branches[*i].name));
+    } else {
+        order.sort_by_key(|(i, score, _)| (-score, rank(&branches[*i].name), branches[*i].name.len()));
+    }
+    order.into_iter().map(|(i, _, positions)| (i, positions)).collect()
+}
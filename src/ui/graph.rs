@@ -0,0 +1,173 @@
+use ratatui::text::Span;
+use crate::models::CommitInfo;
+use super::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Line,
+    Commit,
+    Horizontal,
+    Cross,
+    // A merge parent that continues in a lane to the right/left of the commit
+    MergeRight,
+    MergeLeft,
+    // A lane that ends here because it joins the commit's lane
+    JoinRight,
+    JoinLeft,
+    // A merge or join that a longer connection on the same row passes through
+    MergeThrough,
+    JoinThrough,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphRow {
+    pub cells: Vec<(Cell, usize)>,
+}
+
+/// Assigns commits to lanes one row at a time, so rows already built never
+/// change when more commits are appended.
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    lanes: Vec<Option<String>>,
+}
+
+impl GraphBuilder {
+    pub fn push(&mut self, commit: &CommitInfo) -> GraphRow {
+        let mut cells: Vec<(Cell, usize)> = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(i, lane)| if lane.is_some() { (Cell::Line, i) } else { (Cell::Empty, i) })
+            .collect();
+
+        // Find the lane that was waiting for this commit, or open a new one
+        let col = match self.lanes.iter().position(|l| l.as_deref() == Some(commit.hash.as_str())) {
+            Some(col) => col,
+            None => self.free_lane(&mut cells, 0),
+        };
+        cells[col] = (Cell::Commit, col);
+
+        // Other lanes waiting for the same commit are branches that diverged here
+        for j in 0..self.lanes.len() {
+            if j != col && self.lanes[j].as_deref() == Some(commit.hash.as_str()) {
+                let cell = if j > col { Cell::JoinRight } else { Cell::JoinLeft };
+                connect(&mut cells, col, j, cell);
+                self.lanes[j] = None;
+            }
+        }
+
+        self.lanes[col] = commit.parents.first().cloned();
+
+        // Extra parents of a merge either join an existing lane or open a new one
+        for parent in commit.parents.iter().skip(1) {
+            let k = match self.lanes.iter().position(|l| l.as_deref() == Some(parent.as_str())) {
+                Some(k) => k,
+                None => {
+                    let k = self.free_lane(&mut cells, col + 1);
+                    self.lanes[k] = Some(parent.clone());
+                    k
+                }
+            };
+            let cell = if k > col { Cell::MergeRight } else { Cell::MergeLeft };
+            connect(&mut cells, col, k, cell);
+        }
+
+        while matches!(self.lanes.last(), Some(None)) {
+            self.lanes.pop();
+        }
+
+        GraphRow { cells }
+    }
+
+    fn free_lane(&mut self, cells: &mut Vec<(Cell, usize)>, from: usize) -> usize {
+        if let Some(i) = (from..self.lanes.len()).find(|&i| self.lanes[i].is_none()) {
+            return i;
+        }
+        self.lanes.push(None);
+        let i = self.lanes.len() - 1;
+        while cells.len() <= i {
+            cells.push((Cell::Empty, cells.len()));
+        }
+        i
+    }
+}
+
+fn connect(cells: &mut [(Cell, usize)], col: usize, target: usize, end: Cell) {
+    let (lo, hi) = if col < target { (col, target) } else { (target, col) };
+    for cell in cells.iter_mut().take(hi).skip(lo + 1) {
+        cell.0 = match cell.0 {
+            Cell::Empty | Cell::Horizontal => Cell::Horizontal,
+            Cell::MergeRight | Cell::MergeLeft | Cell::MergeThrough => Cell::MergeThrough,
+            Cell::JoinRight | Cell::JoinLeft | Cell::JoinThrough => Cell::JoinThrough,
+            _ => Cell::Cross,
+        };
+    }
+    cells[target] = (end, target);
+}
+
+pub fn build_graph(commits: &[CommitInfo]) -> (GraphBuilder, Vec<GraphRow>) {
+    let mut builder = GraphBuilder::default();
+    let rows = commits.iter().map(|c| builder.push(c)).collect();
+    (builder, rows)
+}
+
+pub fn graph_spans(row: &GraphRow) -> Vec<Span<'static>> {
+    let theme = Theme::current();
+    graph_glyphs(row)
+        .into_iter()
+        .map(|(glyph, lane)| match lane {
+            Some(lane) => Span::styled(glyph, theme.lane(lane)),
+            None => Span::raw(glyph),
+        })
+        .collect()
+}
+
+/// The characters drawing a row, each with the lane whose colour it takes,
+/// or none for the blank gaps between cells
+pub fn graph_glyphs(row: &GraphRow) -> Vec<(&'static str, Option<usize>)> {
+    let mut glyphs = Vec::with_capacity(row.cells.len() * 2);
+    for (i, (cell, lane)) in row.cells.iter().enumerate() {
+        let glyph = match cell {
+            Cell::Empty => " ",
+            Cell::Line => "│",
+            Cell::Commit => "●",
+            Cell::Horizontal => "─",
+            Cell::Cross => "┼",
+            Cell::MergeRight => "╮",
+            Cell::MergeLeft => "╭",
+            Cell::JoinRight => "╯",
+            Cell::JoinLeft => "╰",
+            Cell::MergeThrough => "┬",
+            Cell::JoinThrough => "┴",
+        };
+        glyphs.push((glyph, Some(*lane)));
+
+        // The gap after a cell continues any horizontal connection to the next one
+        let next = row.cells.get(i + 1).map(|c| c.0);
+        let opens_right = match cell {
+            Cell::Horizontal | Cell::Cross | Cell::MergeThrough | Cell::JoinThrough => true,
+            Cell::MergeLeft | Cell::JoinLeft => true,
+            Cell::Commit => !matches!(next, None | Some(Cell::Empty | Cell::Line)),
+            _ => false,
+        };
+        let closes_left = matches!(
+            next,
+            Some(
+                Cell::Horizontal
+                    | Cell::Cross
+                    | Cell::MergeThrough
+                    | Cell::JoinThrough
+                    | Cell::MergeRight
+                    | Cell::JoinRight
+                    | Cell::Commit
+            )
+        );
+        if opens_right && closes_left {
+            glyphs.push(("─", Some(*lane)));
+        } else {
+            glyphs.push((" ", None));
+        }
+    }
+    glyphs
+}
@@ -0,0 +1,89 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use regex::Regex;
+use crate::git::MAX_GREP_MATCHES;
+use crate::models::GrepMatch;
+use super::Theme;
+
+/// Lines matching a pattern in the files as of one commit
+pub struct GrepView {
+    pub commit: String,
+    // As typed, for the title
+    pub query: String,
+    pub pattern: Regex,
+    pub matches: Vec<GrepMatch>,
+    pub cursor: usize,
+}
+
+impl GrepView {
+    pub fn new(commit: String, query: String, pattern: Regex, matches: Vec<GrepMatch>) -> Self {
+        GrepView { commit, query, pattern, matches, cursor: 0 }
+    }
+
+    pub fn selected(&self) -> Option<&GrepMatch> {
+        self.matches.get(self.cursor)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.matches.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+/// The line with each match of the pattern picked out
+fn highlight(text: &str, pattern: &Regex, style: Style) -> Vec<Span<'static>> {
+    let theme = Theme::current();
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for found in pattern.find_iter(text).filter(|found| !found.is_empty()) {
+        spans.push(Span::styled(text[last..found.start()].to_string(), style));
+        spans.push(Span::styled(found.as_str().to_string(), style.patch(theme.search_match).add_modifier(Modifier::BOLD)));
+        last = found.end();
+    }
+    spans.push(Span::styled(text[last..].to_string(), style));
+    spans
+}
+
+pub fn draw_grep_view(f: &mut Frame, grep: &GrepView, area: Rect) {
+    let theme = Theme::current();
+    let height = area.height.saturating_sub(2) as usize;
+    let offset = (grep.cursor + 1).saturating_sub(height);
+    let mut rows: Vec<Line> = grep
+        .matches
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, found)| {
+            let style = if i == grep.cursor {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![
+                Span::styled(found.path.clone(), theme.path),
+                Span::styled(format!(":{}: ", found.line), theme.muted),
+            ];
+            spans.extend(highlight(&found.text, &grep.pattern, style));
+            Line::from(spans)
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(Line::from("No matches."));
+    }
+
+    let more = if grep.matches.len() >= MAX_GREP_MATCHES { "+" } else { "" };
+    let title = format!(
+        "Grep {} at {}: {}{} lines (Enter to open the file, Esc to close)",
+        grep.query,
+        &grep.commit[..7],
+        grep.matches.len(),
+        more
+    );
+    f.render_widget(Paragraph::new(rows).block(Block::default().title(title).borders(Borders::ALL)), area);
+}
@@ -0,0 +1,181 @@
+use lazy_static::lazy_static;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Theme as SyntaxTheme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+};
+use std::ops::Range;
+use super::{word_diff, Theme};
+
+lazy_static! {
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static ref THEMES: ThemeSet = ThemeSet::load_defaults();
+    static ref FALLBACK: SyntaxTheme = SyntaxTheme::default();
+}
+
+/// The syntect theme that goes with the UI's theme, or nothing when the
+/// terminal can't show colours
+fn syntax_theme(theme: &Theme) -> Option<&'static SyntaxTheme> {
+    theme.syntax.map(|name| THEMES.themes.get(name).unwrap_or(&FALLBACK))
+}
+
+/// Picks a syntax from the extension of the path in a `diff --git` header
+fn syntax_for_header(header: &str) -> Option<&'static SyntaxReference> {
+    let path = header.rsplit(' ').next()?.trim_start_matches("b/");
+    let extension = path.rsplit('.').next()?;
+    SYNTAX_SET.find_syntax_by_extension(extension)
+}
+
+fn to_style(style: syntect::highlighting::Style, theme: &Theme) -> Style {
+    let colour = style.foreground;
+    let mut span_style = theme.colour(Color::Rgb(colour.r, colour.g, colour.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        span_style = span_style.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        span_style = span_style.add_modifier(Modifier::ITALIC);
+    }
+    span_style
+}
+
+fn plain_style(line: &str) -> Style {
+    let theme = Theme::current();
+    if line.starts_with("diff --git") {
+        theme.file_header.add_modifier(Modifier::BOLD)
+    } else if line.starts_with("+++") || line.starts_with("---") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with("@@") {
+        theme.accent
+    } else if line.starts_with("Binary file") || line.starts_with("... ") {
+        // Stand-ins for lines that aren't shown
+        theme.muted
+    } else if line.starts_with('+') {
+        theme.added
+    } else if line.starts_with('-') {
+        theme.removed
+    } else {
+        Style::default()
+    }
+}
+
+/// Splits styled text at the edges of the ranges, giving the parts inside
+/// them the emphasis on top of their own style
+pub fn emphasise(parts: Vec<(Style, String)>, ranges: &[Range<usize>], emphasis: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (style, text) in parts {
+        let end = start + text.len();
+        // Where the part is cut: its ends and every range edge inside it
+        let mut cuts: Vec<usize> = ranges
+            .iter()
+            .flat_map(|range| [range.start, range.end])
+            .filter(|&cut| cut > start && cut < end && text.is_char_boundary(cut - start))
+            .collect();
+        cuts.extend([start, end]);
+        cuts.sort_unstable();
+        cuts.dedup();
+        for piece in cuts.windows(2) {
+            let inside = ranges.iter().any(|range| range.start <= piece[0] && piece[1] <= range.end);
+            let piece_style = if inside { style.patch(emphasis) } else { style };
+            spans.push(Span::styled(text[piece[0] - start..piece[1] - start].to_string(), piece_style));
+        }
+        start = end;
+    }
+    spans
+}
+
+/// Renders a unified diff, highlighting the code in each file according to
+/// its language while keeping the usual +/- colouring for the markers. The
+/// words that changed within a changed line are picked out as well.
+pub fn highlight_diff(diff: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut highlighter: Option<HighlightLines> = None;
+    let mut in_header = false;
+    let theme = Theme::current();
+    let diff_lines: Vec<&str> = diff.lines().collect();
+    let changed_words = word_diff::unified_changes(&diff_lines);
+
+    for (line, words) in diff_lines.into_iter().zip(&changed_words) {
+        if line.starts_with("diff --git") {
+            highlighter = syntax_for_header(line)
+                .zip(syntax_theme(&theme))
+                .map(|(syntax, colours)| HighlightLines::new(syntax, colours));
+            in_header = true;
+        } else if line.starts_with("@@") {
+            in_header = false;
+        }
+
+        let marker = line.chars().next();
+        let is_code = !in_header && matches!(marker, Some('+' | '-' | ' '));
+        let emphasis = if marker == Some('+') { theme.added_word } else { theme.removed_word };
+        let highlighter = match highlighter.as_mut() {
+            Some(highlighter) if is_code => highlighter,
+            _ if is_code && !words.is_empty() => {
+                let mut spans = vec![Span::styled(line[..1].to_string(), plain_style(line))];
+                spans.extend(emphasise(vec![(plain_style(line), line[1..].to_string())], words, emphasis));
+                lines.push(Line::from(spans));
+                continue;
+            },
+            _ => {
+                lines.push(Line::from(Span::styled(line.to_string(), plain_style(line))));
+                continue;
+            }
+        };
+
+        let background = match marker {
+            Some('+') => Some(theme.added_background),
+            Some('-') => Some(theme.removed_background),
+            _ => None,
+        };
+        let with_background = |style: Style| match background {
+            Some(background) => style.patch(background),
+            None => style,
+        };
+
+        let mut spans = vec![Span::styled(line[..1].to_string(), with_background(plain_style(line)))];
+        let code = format!("{}\n", &line[1..]);
+        let parts = match highlighter.highlight_line(&code, &SYNTAX_SET) {
+            Ok(ranges) => ranges
+                .into_iter()
+                .map(|(style, text)| (with_background(to_style(style, &theme)), text.trim_end_matches('\n').to_string()))
+                .collect(),
+            Err(_) => vec![(with_background(plain_style(line)), line[1..].to_string())],
+        };
+        spans.extend(emphasise(parts, words, emphasis));
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
+
+/// Highlights a whole file according to the language its extension suggests
+pub fn highlight_file(path: &str, content: &str) -> Vec<Line<'static>> {
+    let syntax = path
+        .rsplit('.')
+        .next()
+        .and_then(|extension| SYNTAX_SET.find_syntax_by_extension(extension));
+    let theme = Theme::current();
+    let mut highlighter = match syntax.zip(syntax_theme(&theme)) {
+        Some((syntax, colours)) => HighlightLines::new(syntax, colours),
+        None => return content.lines().map(|line| Line::from(line.to_string())).collect(),
+    };
+
+    content
+        .lines()
+        .map(|line| match highlighter.highlight_line(&format!("{}\n", line), &SYNTAX_SET) {
+            Ok(ranges) => Line::from(
+                ranges
+                    .into_iter()
+                    .map(|(style, text)| {
+                        Span::styled(text.trim_end_matches('\n').to_string(), to_style(style, &theme))
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            Err(_) => Line::from(line.to_string()),
+        })
+        .collect()
+}
@@ -0,0 +1,115 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use crate::git::format_size;
+use crate::image::{self, Protocol};
+use super::{App, Theme};
+
+/// One side of an image change: its size, and its content unless it's too
+/// big to draw
+pub struct ImageSide {
+    pub size: u64,
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// An image file changed by the selected commit, as it was before and
+/// after, for files that are shown as pictures rather than lines
+pub struct ImagePreview {
+    pub hash: String,
+    pub path: String,
+    pub old: Option<ImageSide>,
+    pub new: Option<ImageSide>,
+    // Where each side's picture goes, set each time the view is drawn
+    pub slots: [Option<Rect>; 2],
+}
+
+impl ImagePreview {
+    pub fn new(hash: String, path: String, old: Option<ImageSide>, new: Option<ImageSide>) -> Self {
+        ImagePreview { hash, path, old, new, slots: [None, None] }
+    }
+
+    /// The pictures to draw, each with a key telling it apart and the
+    /// area it goes in
+    pub fn placements(&self) -> Vec<(String, Rect, &[u8])> {
+        [(&self.old, "old"), (&self.new, "new")]
+            .iter()
+            .zip(self.slots)
+            .filter_map(|((bytes, side), slot)| {
+                let key = format!("{} {} {}", self.hash, self.path, side);
+                Some((key, slot?, bytes.as_ref()?.bytes.as_deref()?))
+            })
+            .collect()
+    }
+}
+
+/// One side's format, dimensions and size, or why there's nothing to show
+fn describe(side: Option<&ImageSide>) -> String {
+    let bytes = match side {
+        Some(ImageSide { bytes: Some(bytes), .. }) => bytes,
+        Some(ImageSide { size, bytes: None }) => return format!("too big to read, {}", format_size(*size)),
+        None => return "none".to_string(),
+    };
+    match image::probe(bytes) {
+        Some(info) => format!("{} {}×{}, {}", info.format, info.width, info.height, format_size(bytes.len() as u64)),
+        None => format!("unrecognised image, {}", format_size(bytes.len() as u64)),
+    }
+}
+
+/// The selected image file's sizes before and after, with the pictures
+/// themselves next to each other when the terminal can draw them
+pub fn draw_image_view(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = Theme::current();
+    let protocol = app.image_protocol;
+    let preview = match app.image_preview.as_mut() {
+        Some(preview) => preview,
+        None => return,
+    };
+    let title = format!("Diff of {}, an image (←/→ to pick a file, Esc to close)", preview.path);
+    let block = super::pane_block(title, app.focus == super::Focus::Details);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let sides = [("Before", preview.old.as_ref()), ("After", preview.new.as_ref())];
+    let drawable = |side: Option<&ImageSide>| {
+        let bytes = side.and_then(|side| side.bytes.as_deref());
+        protocol.zip(bytes).is_some_and(|(protocol, bytes)| image::can_draw(protocol, bytes))
+    };
+    let mut lines: Vec<Line> = sides
+        .iter()
+        .map(|(label, side)| Line::from(vec![Span::styled(format!("{:<8}", format!("{}:", label)), theme.muted), Span::raw(describe(*side))]))
+        .collect();
+    let note = match protocol {
+        None => Some("This terminal can't draw images (see image_protocol in the config)"),
+        Some(Protocol::Kitty | Protocol::Sixel) if sides.iter().any(|(_, side)| side.is_some() && !drawable(*side)) => {
+            Some("Only PNGs up to 8 MB are drawn in this terminal")
+        },
+        _ => None,
+    };
+    if let Some(note) = note {
+        lines.push(Line::from(Span::styled(note, theme.muted)));
+    }
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(lines.len() as u16 + 1), Constraint::Min(0)])
+        .split(inner);
+    f.render_widget(Paragraph::new(lines), rows[0]);
+
+    // Added and deleted files only have the one side to show
+    let shown: Vec<usize> = (0..2).filter(|&side| drawable(sides[side].1)).collect();
+    if shown.is_empty() || rows[1].height < 2 {
+        return;
+    }
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, shown.len() as u32); shown.len()])
+        .split(rows[1]);
+    for (&side, column) in shown.iter().zip(columns.iter()) {
+        f.render_widget(Paragraph::new(Span::styled(sides[side].0, theme.accent)), *column);
+        // A column's gap keeps the pictures apart
+        let slot = Rect { y: column.y + 1, height: column.height - 1, width: column.width.saturating_sub(1), ..*column };
+        preview.slots[side] = Some(slot).filter(|slot| slot.width > 0);
+    }
+}
@@ -0,0 +1,122 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::models::{LineChange, LineKind};
+use super::{dates, App, Theme};
+
+/// The commits that changed some lines of a file, each with the hunks
+/// touching them, read top to bottom like `git log -L`
+pub struct LineHistoryView {
+    pub path: String,
+    // The lines' first and last numbers in the version they were picked in
+    pub range: (usize, usize),
+    pub changes: Vec<LineChange>,
+    pub scroll: usize,
+}
+
+/// Rows taken by a change: its heading, each hunk's header and lines, and
+/// a gap after it
+fn row_count(change: &LineChange) -> usize {
+    2 + change.hunks.iter().map(|hunk| 1 + hunk.lines.len()).sum::<usize>()
+}
+
+impl LineHistoryView {
+    pub fn new(path: String, range: (usize, usize), changes: Vec<LineChange>) -> Self {
+        LineHistoryView { path, range, changes, scroll: 0 }
+    }
+
+    /// The row each change starts on
+    fn starts(&self) -> Vec<usize> {
+        self.changes
+            .iter()
+            .scan(0, |row, change| {
+                let start = *row;
+                *row += row_count(change);
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// The change at the top of the view
+    fn current(&self) -> usize {
+        self.starts().iter().rposition(|&start| start <= self.scroll).unwrap_or(0)
+    }
+
+    pub fn selected(&self) -> Option<&LineChange> {
+        self.changes.get(self.current())
+    }
+
+    pub fn scroll(&mut self, delta: i32) {
+        let total: usize = self.changes.iter().map(row_count).sum();
+        let max = total.saturating_sub(1) as i64;
+        self.scroll = (self.scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    /// Scrolls to the start of the next or previous change
+    pub fn jump(&mut self, forward: bool) {
+        let starts = self.starts();
+        let target = if forward {
+            starts.iter().find(|&&start| start > self.scroll)
+        } else {
+            starts.iter().rev().find(|&&start| start < self.scroll)
+        };
+        if let Some(&start) = target {
+            self.scroll = start;
+        }
+    }
+}
+
+pub fn draw_line_history(f: &mut Frame, app: &App, view: &LineHistoryView, area: Rect) {
+    let theme = Theme::current();
+    let current = view.current();
+    let mut rows: Vec<Line> = Vec::new();
+    for (i, change) in view.changes.iter().enumerate() {
+        let commit = &change.commit;
+        let heading = if i == current { theme.selected } else { Style::default() };
+        let mut spans = vec![
+            Span::styled(commit.hash[..7].to_string(), theme.hash),
+            Span::raw(" "),
+            Span::styled(dates::format(commit.time, app.date_format), theme.date),
+            Span::raw(" "),
+            Span::styled(commit.author.clone(), theme.author),
+            Span::raw(" "),
+            Span::styled(commit.message.lines().next().unwrap_or("").to_string(), heading),
+        ];
+        // Lines that moved, or a file that was renamed, say where they were
+        if change.path != view.path || change.range != view.range {
+            spans.push(Span::styled(format!("  {}:{}-{}", change.path, change.range.0, change.range.1), theme.muted));
+        }
+        rows.push(Line::from(spans));
+        for hunk in &change.hunks {
+            rows.push(Line::from(Span::styled(hunk.header.clone(), theme.accent)));
+            for line in &hunk.lines {
+                let (marker, style) = match line.kind {
+                    LineKind::Added => ('+', theme.added),
+                    LineKind::Removed => ('-', theme.removed),
+                    LineKind::Context => (' ', Style::default()),
+                };
+                rows.push(Line::from(Span::styled(format!("{}{}", marker, line.content), style)));
+            }
+        }
+        rows.push(Line::from(""));
+    }
+    if rows.is_empty() {
+        rows.push(Line::from("No commits changed these lines."));
+    }
+
+    let height = area.height.saturating_sub(2) as usize;
+    let rows: Vec<Line> = rows.into_iter().skip(view.scroll).take(height).collect();
+    let title = format!(
+        "History of lines {}-{} of {}, commit {}/{} (↑/↓/PgUp/PgDn to scroll, n/N for the next/previous commit, Enter to select it, Esc to close)",
+        view.range.0,
+        view.range.1,
+        view.path,
+        (current + 1).min(view.changes.len()),
+        view.changes.len()
+    );
+    f.render_widget(Paragraph::new(rows).block(Block::default().title(title).borders(Borders::ALL)), area);
+}
@@ -1,297 +1,2675 @@
-use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
-    Frame,
-};
-use std::collections::VecDeque;
-use crate::models::CommitInfo;
-
-pub struct App {
-    pub commits: VecDeque<CommitInfo>,
-    pub selected_index: usize,
-    pub current_branch: String,
-    pub branches: Vec<String>,
-    pub show_author_filter: bool,
-    pub show_branch_selector: bool,
-    pub branch_selector_index: usize,
-}
-
-impl App {
-    pub fn toggle_author_filter(&mut self) {
-        self.show_author_filter = !self.show_author_filter;
-    }
-
-    pub fn toggle_branch_selector(&mut self) {
-        self.show_branch_selector = !self.show_branch_selector;
-        if self.show_branch_selector {
-            // Find the current branch in the list
-            self.branch_selector_index = self.branches.iter()
-                .position(|b| b == &self.current_branch)
-                .unwrap_or(0);
-        }
-    }
-
-    pub fn select_branch(&mut self, index: usize) -> bool {
-        if index < self.branches.len() {
-            self.current_branch = self.branches[index].clone();
-            self.branch_selector_index = index;
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn navigate_branch_selector(&mut self, direction: i32) {
-        let new_index = self.branch_selector_index as i32 + direction;
-        if new_index >= 0 && new_index < self.branches.len() as i32 {
-            self.branch_selector_index = new_index as usize;
-        }
-    }
-
-    pub fn navigate_up(&mut self) {
-        if self.selected_index > 0 {
-            self.selected_index -= 1;
-        }
-    }
-
-    pub fn navigate_down(&mut self) {
-        if self.selected_index < self.commits.len().saturating_sub(1) {
-            self.selected_index += 1;
-        }
-    }
-}
-
-pub fn draw_ui(f: &mut Frame, app: &App) {
-    let size = f.size();
-
-    if app.show_branch_selector {
-        draw_branch_selector(f, app, size);
-        return;
-    }
-
-    if app.show_author_filter {
-        draw_author_filter(f, app, size);
-        return;
-    }
-
-    // Create the main layout
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(30),
-            Constraint::Percentage(70),
-        ])
-        .split(size);
-
-    // Draw the commit list
-    draw_commit_list(f, app, chunks[0]);
-
-    // Draw the commit details
-    draw_commit_details(f, app, chunks[1]);
-}
-
-fn draw_branch_selector(f: &mut Frame, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .branches
-        .iter()
-        .enumerate()
-        .map(|(i, branch)| {
-            let style = if i == app.branch_selector_index {
-                Style::default().bg(Color::Blue)
-            } else if branch == &app.current_branch {
-                Style::default().fg(Color::Green)
-            } else {
-                Style::default()
-            };
-
-            let prefix = if branch.contains('/') {
-                "🌐 " // Remote branch
-            } else {
-                "🌿 " // Local branch
-            };
-
-            ListItem::new(Line::from(vec![
-                Span::styled(
-                    format!("{}{}", prefix, branch),
-                    style,
-                ),
-            ]))
-        })
-        .collect();
-
-    let list = List::new(items)
-        .block(Block::default().title("Select Branch (↑/↓ to navigate, Enter to select, Esc to cancel)").borders(Borders::ALL));
-
-    f.render_widget(list, area);
-}
-
-fn draw_author_filter(f: &mut Frame, _app: &App, area: Rect) {
-    let paragraph = Paragraph::new("Author filter (not implemented yet)")
-        .block(Block::default().title("Author Filter").borders(Borders::ALL));
-
-    f.render_widget(paragraph, area);
-}
-
-fn draw_commit_list(f: &mut Frame, app: &App, area: Rect) {
-    if app.commits.is_empty() {
-        let empty_message = Paragraph::new("No commits found in the repository.")
-            .block(Block::default().title("Commits").borders(Borders::ALL));
-        f.render_widget(empty_message, area);
-        return;
-    }
-
-    let items: Vec<ListItem> = app
-        .commits
-        .iter()
-        .enumerate()
-        .map(|(i, commit)| {
-            let style = if i == app.selected_index {
-                Style::default().bg(Color::Blue)
-            } else {
-                Style::default()
-            };
-
-            ListItem::new(Line::from(vec![
-                Span::styled(
-                    format!("{} {}", commit.hash, commit.message),
-                    style,
-                ),
-            ]))
-        })
-        .collect();
-
-    let list = List::new(items)
-        .block(Block::default().title(format!("Commits ({})", app.current_branch)).borders(Borders::ALL));
-
-    f.render_widget(list, area);
-}
-
-fn draw_commit_details(f: &mut Frame, app: &App, area: Rect) {
-    if app.commits.is_empty() {
-        let empty_message = Paragraph::new("No commit selected.")
-            .block(Block::default().title("Details").borders(Borders::ALL));
-        f.render_widget(empty_message, area);
-        return;
-    }
-
-    let commit = app.commits.get(app.selected_index);
-    
-    let content = if let Some(commit) = commit {
-        let mut lines = vec![
-            format!("Hash: {}", commit.hash),
-            format!("Author: {}", commit.author),
-            format!("Date: {}", commit.date),
-            String::new(),
-            format!("Message:\n{}", commit.message),
-            String::new(),
-            "Changed Files:".to_string(),
-        ];
-
-        if let Some(diff) = &commit.diff {
-            // Simplified approach - just extract file names from diff
-            let mut current_file = String::new();
-            let mut old_file = String::new();
-            let mut is_rename = false;
-            let mut is_new_file = false;
-            let mut is_deleted_file = false;
-            let mut file_changes = Vec::new();
-            
-            // Process each line in the diff to extract changed files
-            for line in diff.lines() {
-                if line.starts_with("diff --git") {
-                    // Save previous file info
-                    if !current_file.is_empty() {
-                        let file_type = if is_new_file {
-                            "added"
-                        } else if is_deleted_file {
-                            "deleted"
-                        } else if is_rename {
-                            "renamed"
-                        } else {
-                            "modified"
-                        };
-                        
-                        file_changes.push((current_file.clone(), old_file.clone(), file_type.to_string()));
-                    }
-                    
-                    // Reset for new file
-                    current_file = String::new();
-                    old_file = String::new();
-                    is_rename = false;
-                    is_new_file = false;
-                    is_deleted_file = false;
-                    
-                    // Extract file names from diff header
-                    let parts: Vec<&str> = line.split(' ').collect();
-                    if parts.len() >= 4 {
-                        old_file = parts[2].trim_start_matches("a/").to_string();
-                        current_file = parts[3].trim_start_matches("b/").to_string();
-                    }
-                }
-                else if line.starts_with("new file mode") {
-                    is_new_file = true;
-                }
-                else if line.starts_with("deleted file mode") {
-                    is_deleted_file = true;
-                }
-                else if line.starts_with("rename from") {
-                    is_rename = true;
-                    old_file = line.trim_start_matches("rename from ").to_string();
-                }
-                else if line.starts_with("rename to") {
-                    is_rename = true;
-                    current_file = line.trim_start_matches("rename to ").to_string();
-                }
-            }
-            
-            // Add the last file
-            if !current_file.is_empty() {
-                let file_type = if is_new_file {
-                    "added"
-                } else if is_deleted_file {
-                    "deleted"
-                } else if is_rename {
-                    "renamed"
-                } else {
-                    "modified"
-                };
-                
-                file_changes.push((current_file, old_file, file_type.to_string()));
-            }
-            
-            // Display the files and their changes
-            if file_changes.is_empty() {
-                lines.push("No files changed".to_string());
-            } else {
-                for (file, old_path, change_type) in &file_changes {
-                    // Format the status based on change type
-                    let status = match change_type.as_str() {
-                        "added" => "added".to_string(),
-                        "deleted" => "deleted".to_string(),
-                        "renamed" => format!("renamed {} →", old_path),
-                        _ => "modified".to_string(),
-                    };
-                    
-                    lines.push(format!("{:<30} {}", status, file));
-                }
-                
-                // Add a summary line with just the count of files
-                lines.push(String::new());
-                lines.push(format!("Total: {} files changed", file_changes.len()));
-            }
-        } else {
-            lines.push("No diff available".to_string());
-        }
-
-        lines.join("\n")
-    } else {
-        String::from("No commit selected")
-    };
-
-    let paragraph = Paragraph::new(content)
-        .block(Block::default().title("Details").borders(Borders::ALL));
-
-    f.render_widget(paragraph, area);
-} 
\ No newline at end of file
+mod ascii;
+mod blame;
+mod commit_dialog;
+mod commit_types;
+mod compare;
+mod confirm;
+mod dates;
+mod debug;
+mod diff_view;
+mod file_view;
+mod filters;
+mod fuzzy;
+mod graph;
+mod grep;
+mod highlight;
+mod line_history;
+mod image_view;
+mod notifications;
+mod ownership;
+mod prompt;
+mod reflog;
+mod search;
+mod stash;
+mod stats;
+mod status;
+mod status_bar;
+mod theme;
+mod tree;
+mod word_diff;
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    Frame,
+};
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::config::{Column, DateFormat, ThemeName, COLUMNS};
+use crate::conventional;
+use crate::git::COMMIT_PAGE_SIZE;
+use crate::image::{self, Protocol};
+use crate::git::loader::LoadEvent;
+use crate::git::remote::RemoteOp;
+use crate::models::{AuthorInfo, Bisect, BlameLine, BranchInfo, ChangeStatus, CommitDiff, CommitInfo, DiffCacheStats, DiffContext, DiffInfo, DiffStat, FileChange, FilterSet, MarkedDiff, MergeDiff, MergeFilter, MergeView, PatchPreview, PickaxeResults, PullLookup, PullRequest, RangeDiff, RefKind, RefLabel, SearchResults, TagInfo, Whitespace};
+use self::blame::BlameView;
+use self::graph::GraphRow;
+pub use self::image_view::{ImagePreview, ImageSide};
+use self::notifications::Notification;
+use self::tree::FileViewer;
+pub use self::ascii::{ascii_text, needs_ascii, to_ascii};
+pub use self::tree::TreeView;
+pub use self::reflog::ReflogView;
+pub use self::stash::StashView;
+pub use self::stats::{StatsPage, StatsView};
+pub use self::status::StatusView;
+pub use self::confirm::{Confirmation, PendingAction};
+pub use self::commit_dialog::{CommitDialog, DialogEvent};
+pub use self::compare::CompareView;
+pub use self::grep::GrepView;
+pub use self::file_view::FileView;
+pub use self::line_history::LineHistoryView;
+pub use self::filters::FilterKind;
+pub use self::graph::{graph_glyphs, graph_spans, GraphBuilder};
+pub use self::notifications::Level;
+pub use self::prompt::{Prompt, PromptAction};
+pub use self::theme::Theme;
+
+/// The pane that Up/Down and paging keys act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Commits,
+    // The commit details, or the diff when it's open
+    Details,
+    Files,
+}
+
+/// How far a commit's details and diff were scrolled, and which of its
+/// files was picked, to come back to when it's selected again
+#[derive(Debug, Clone, Copy)]
+struct ScrollPosition {
+    details: u16,
+    diff: u16,
+    file: Option<usize>,
+}
+
+impl Focus {
+    fn next(self) -> Focus {
+        match self {
+            Focus::Commits => Focus::Details,
+            Focus::Details => Focus::Files,
+            Focus::Files => Focus::Commits,
+        }
+    }
+
+    fn prev(self) -> Focus {
+        match self {
+            Focus::Commits => Focus::Files,
+            Focus::Details => Focus::Commits,
+            Focus::Files => Focus::Details,
+        }
+    }
+}
+
+pub struct App {
+    pub repo_root: String,
+    // Without a working tree only the history can be browsed
+    pub bare: bool,
+    // Roots of every repository open in this session, and which one this is
+    pub repos: Vec<String>,
+    pub repo_index: usize,
+    pub show_repo_selector: bool,
+    pub repo_selector_index: usize,
+    // Repository to switch to, which the main loop picks up
+    pub repo_switch: Option<usize>,
+    pub commits: VecDeque<CommitInfo>,
+    pub graph: Vec<GraphRow>,
+    pub graph_builder: GraphBuilder,
+    pub has_more_commits: bool,
+    pub loading: bool,
+    // Commits looked at and kept so far by a long walk for the next page
+    pub load_progress: Option<(usize, usize)>,
+    pub load_error: Option<String>,
+    pub spinner_frame: usize,
+    pub selected_index: usize,
+    // Indices into `commits` that pass the current filters, in display order
+    pub visible: Vec<usize>,
+    // Position in `visible` of the first row shown, and how many rows fit
+    pub list_offset: usize,
+    pub list_height: usize,
+    pub search_mode: bool,
+    pub search_query: String,
+    // Whether the search is a regex, compiled once as it's typed, and the
+    // matches found for it through the whole history
+    pub search_regex: bool,
+    pub search_pattern: Option<Regex>,
+    pub search_error: Option<String>,
+    pub search_results: Option<SearchResults>,
+    pub focus: Focus,
+    // Percentage of the width given to the commit list
+    pub list_width: u16,
+    pub date_format: DateFormat,
+    // Every column of the list in the order shown, and whether it is
+    pub columns: Vec<(Column, bool)>,
+    pub show_column_picker: bool,
+    pub column_picker_index: usize,
+    // Whether the focused side fills the whole screen
+    pub maximised: bool,
+    pub details_scroll: u16,
+    pub show_diff: bool,
+    pub diff_scroll: u16,
+    // Columns the diff is scrolled sideways by, unless its lines are wrapped
+    pub diff_hscroll: u16,
+    pub wrap_diff: bool,
+    // Where each commit looked at was left scrolled to, by hash
+    scroll_positions: HashMap<String, ScrollPosition>,
+    // Rows and columns the details or diff pane showed on the last frame,
+    // used for paging and wrapping
+    pub details_height: usize,
+    pub details_width: usize,
+    // Set when the terminal changes size, so the next frame can pull scroll
+    // positions back inside the resized panes
+    resized: bool,
+    // Highlighted lines of the diff being viewed, and the commit they belong to
+    pub diff_lines: Vec<Line<'static>>,
+    pub split_diff: bool,
+    pub split_rows: Vec<diff_view::SplitRow>,
+    pub diff_lines_key: Option<(String, Option<usize>)>,
+    // File of the selected commit being looked at, or None for all of them
+    pub selected_file: Option<usize>,
+    pub current_branch: String,
+    pub branches: Vec<BranchInfo>,
+    pub show_author_filter: bool,
+    pub authors: Vec<AuthorInfo>,
+    pub author_selector_index: usize,
+    pub show_date_filter: bool,
+    pub date_selector_index: usize,
+    // What the loaded history is limited to; changing it reloads the list
+    pub filters: FilterSet,
+    pub show_filter_panel: bool,
+    pub filter_panel_index: usize,
+    // Only show commits whose diffs match this search
+    pub pickaxe: Option<PickaxeResults>,
+    pub show_type_filter: bool,
+    // Conventional Commit types among the loaded commits, with their counts
+    pub commit_types: Vec<(String, usize)>,
+    pub type_selector_index: usize,
+    pub type_filter: Option<String>,
+    pub show_branch_selector: bool,
+    // Position of the cursor in `branch_order`
+    pub branch_selector_index: usize,
+    // What's typed to narrow the branches down, and whether it's being typed
+    pub branch_query: String,
+    pub branch_typing: bool,
+    // The branches listed, as indices into `branches` with the characters
+    // matching the query
+    pub branch_order: Vec<(usize, Vec<usize>)>,
+    // Branches picked lately in this repository, most recent first
+    pub recent_branches: Vec<String>,
+    // Short hash HEAD pointed at on startup when it wasn't on a branch
+    pub detached_head: Option<String>,
+    pub tags: Vec<TagInfo>,
+    // Branches, tags and HEAD keyed by the commit they point at
+    pub ref_map: HashMap<String, Vec<RefLabel>>,
+    // Where a shallow clone's history is cut off, empty for a full clone
+    pub shallow: HashSet<String>,
+    pub show_tag_selector: bool,
+    pub tag_selector_index: usize,
+    pub bisect: Option<Bisect>,
+    // Pull requests of the commits looked at, by hash
+    pub pull_requests: HashMap<String, PullLookup>,
+    // Path whose history is shown instead of the whole branch
+    pub file_history: Option<String>,
+    // Blame shown in place of the details while it's open
+    pub blame: Option<BlameView>,
+    // A file's whole content, shown in place of the details while it's open
+    pub file_view: Option<FileView>,
+    // Commit to select as soon as it has been loaded
+    pub pending_jump: Option<String>,
+    // Hashes of the commits marked for batch actions such as cherry-picking
+    pub marked: Vec<String>,
+    // Show the marked commits' changes together in place of the selected one's
+    pub show_marked_diff: bool,
+    pub marked_diff: Option<MarkedDiff>,
+    // Patch file read in to be applied, shown in place of the selected commit
+    pub patch_preview: Option<PatchPreview>,
+    // Commit where a range selection started; the range runs to the
+    // selected commit and is shown as one combined diff
+    pub range_anchor: Option<String>,
+    pub range_diff: Option<RangeDiff>,
+    // What merge commits are diffed against; other commits always use
+    // their only parent
+    pub merge_view: MergeView,
+    pub merge_diff: Option<MergeDiff>,
+    // The selected commit's diff, read when it's selected
+    pub commit_diff: Option<CommitDiff>,
+    // The commit whose large files' diffs were asked for in full
+    pub full_diff: Option<String>,
+    // Its line counts, read from git's diffstat rather than the patch
+    pub commit_stat: Option<DiffStat>,
+    // Whitespace differences left out of the diffs shown
+    pub whitespace: Whitespace,
+    // How much unchanged text they show around each change
+    pub diff_context: DiffContext,
+    // How images in diffs can be drawn, if at all
+    pub image_protocol: Option<Protocol>,
+    // The selected image file's content before and after, while it's shown
+    pub image_preview: Option<ImagePreview>,
+    // Snapshot of the selected commit being browsed, if open
+    pub tree: Option<TreeView>,
+    // Working tree status, if open
+    pub status: Option<StatusView>,
+    // Stash list, if open
+    pub stash: Option<StashView>,
+    // Reflog of HEAD and the local branches, if open
+    pub reflog: Option<ReflogView>,
+    // The diff cache's figures, refreshed every frame while the debug view is open
+    pub debug: Option<DiffCacheStats>,
+    // Two refs being compared, if open
+    pub compare: Option<CompareView>,
+    // Repository statistics, if open
+    pub stats: Option<StatsView>,
+    pub grep: Option<GrepView>,
+    // The commits that changed some lines of a file, over everything else
+    pub line_history: Option<LineHistoryView>,
+    // Question shown over everything until it's answered
+    pub confirmation: Option<Confirmation>,
+    // Commit message being written, shown over the status view
+    pub commit_dialog: Option<CommitDialog>,
+    // Name being typed, e.g. for a new branch
+    pub prompt: Option<Prompt>,
+    // Fetch, pull or push for main to start, and the progress of the one
+    // running
+    pub remote_request: Option<RemoteOp>,
+    pub remote_progress: Option<String>,
+    // Messages waiting for, or currently in, the status bar
+    pub notifications: VecDeque<Notification>,
+    // Everything raised this session, for the message log
+    pub message_log: Vec<Notification>,
+    pub show_message_log: bool,
+}
+
+// How long each message stays in the status bar
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+// Oldest messages are dropped from the log past this many
+const MESSAGE_LOG_LIMIT: usize = 200;
+
+// Below this the panes are too cramped to be readable, so a notice is shown
+// instead
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 12;
+
+impl App {
+    pub fn new(current_branch: String, branches: Vec<BranchInfo>) -> Self {
+        App {
+            repo_root: String::new(),
+            bare: false,
+            repos: Vec::new(),
+            repo_index: 0,
+            show_repo_selector: false,
+            repo_selector_index: 0,
+            repo_switch: None,
+            commits: VecDeque::new(),
+            graph: Vec::new(),
+            graph_builder: GraphBuilder::default(),
+            has_more_commits: false,
+            loading: false,
+            load_progress: None,
+            load_error: None,
+            spinner_frame: 0,
+            selected_index: 0,
+            visible: Vec::new(),
+            list_offset: 0,
+            list_height: 0,
+            search_mode: false,
+            search_query: String::new(),
+            search_regex: false,
+            search_pattern: None,
+            search_error: None,
+            search_results: None,
+            focus: Focus::Commits,
+            list_width: 30,
+            date_format: DateFormat::default(),
+            columns: COLUMNS.iter().map(|&column| (column, true)).collect(),
+            show_column_picker: false,
+            column_picker_index: 0,
+            maximised: false,
+            details_scroll: 0,
+            show_diff: false,
+            diff_scroll: 0,
+            diff_hscroll: 0,
+            wrap_diff: false,
+            scroll_positions: HashMap::new(),
+            details_height: 0,
+            details_width: 0,
+            resized: false,
+            diff_lines: Vec::new(),
+            split_diff: false,
+            split_rows: Vec::new(),
+            diff_lines_key: None,
+            selected_file: None,
+            current_branch,
+            branches,
+            show_author_filter: false,
+            authors: Vec::new(),
+            author_selector_index: 0,
+            show_date_filter: false,
+            date_selector_index: 0,
+            filters: FilterSet::default(),
+            show_filter_panel: false,
+            filter_panel_index: 0,
+            pickaxe: None,
+            show_type_filter: false,
+            commit_types: Vec::new(),
+            type_selector_index: 0,
+            type_filter: None,
+            show_branch_selector: false,
+            branch_selector_index: 0,
+            branch_query: String::new(),
+            branch_typing: false,
+            branch_order: Vec::new(),
+            recent_branches: Vec::new(),
+            detached_head: None,
+            tags: Vec::new(),
+            ref_map: HashMap::new(),
+            shallow: HashSet::new(),
+            show_tag_selector: false,
+            tag_selector_index: 0,
+            bisect: None,
+            pull_requests: HashMap::new(),
+            file_history: None,
+            blame: None,
+            file_view: None,
+            pending_jump: None,
+            marked: Vec::new(),
+            show_marked_diff: false,
+            marked_diff: None,
+            patch_preview: None,
+            range_anchor: None,
+            range_diff: None,
+            merge_view: MergeView::Parent(0),
+            merge_diff: None,
+            commit_diff: None,
+            full_diff: None,
+            commit_stat: None,
+            whitespace: Whitespace::default(),
+            diff_context: DiffContext::default(),
+            image_protocol: None,
+            image_preview: None,
+            tree: None,
+            status: None,
+            stash: None,
+            reflog: None,
+            debug: None,
+            compare: None,
+            stats: None,
+            grep: None,
+            line_history: None,
+            confirmation: None,
+            commit_dialog: None,
+            prompt: None,
+            remote_request: None,
+            remote_progress: None,
+            notifications: VecDeque::new(),
+            message_log: Vec::new(),
+            show_message_log: false,
+        }
+    }
+
+    /// Whether a full-screen selector is covering the main view
+    pub fn popup_open(&self) -> bool {
+        self.show_branch_selector
+            || self.show_author_filter
+            || self.show_type_filter
+            || self.show_date_filter
+            || self.show_filter_panel
+            || self.show_tag_selector
+            || self.show_repo_selector
+            || self.show_column_picker
+            || self.show_message_log
+            || self.tree.is_some()
+            || self.status.is_some()
+            || self.stash.is_some()
+            || self.reflog.is_some()
+            || self.debug.is_some()
+            || self.compare.is_some()
+            || self.stats.is_some()
+            || self.grep.is_some()
+            || self.line_history.is_some()
+    }
+
+    pub fn set_branches(&mut self, branches: Vec<BranchInfo>) {
+        self.branches = branches;
+        self.order_branches();
+    }
+
+    /// Lists the branches matching the query, recent ones first, keeping
+    /// the cursor in the list
+    fn order_branches(&mut self) {
+        self.branch_order = fuzzy::order_branches(&self.branches, &self.branch_query, &self.recent_branches);
+        self.branch_selector_index = self.branch_selector_index.min(self.branch_order.len().saturating_sub(1));
+    }
+
+    /// The branch under the cursor in the selector
+    pub fn selected_branch(&self) -> Option<&BranchInfo> {
+        let &(index, _) = self.branch_order.get(self.branch_selector_index)?;
+        self.branches.get(index)
+    }
+
+    /// Starts typing to narrow the branches down
+    pub fn start_branch_query(&mut self) {
+        self.branch_typing = true;
+        self.branch_query.clear();
+        self.order_branches();
+    }
+
+    pub fn branch_query_input(&mut self, c: char) {
+        self.branch_query.push(c);
+        // The best match is the one to pick
+        self.branch_selector_index = 0;
+        self.order_branches();
+    }
+
+    pub fn branch_query_backspace(&mut self) {
+        self.branch_query.pop();
+        self.branch_selector_index = 0;
+        self.order_branches();
+    }
+
+    /// Stops narrowing the branches down, back on the one that was picked
+    pub fn cancel_branch_query(&mut self) {
+        let selected = self.selected_branch().map(|branch| branch.name.clone());
+        self.branch_typing = false;
+        self.branch_query.clear();
+        self.order_branches();
+        if let Some(position) = self.branch_order.iter().position(|&(i, _)| Some(&self.branches[i].name) == selected.as_ref()) {
+            self.branch_selector_index = position;
+        }
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<TagInfo>) {
+        self.tags = tags;
+        self.tag_selector_index = self.tag_selector_index.min(self.tags.len().saturating_sub(1));
+    }
+
+    pub fn toggle_repo_selector(&mut self) {
+        self.show_repo_selector = !self.show_repo_selector;
+        if self.show_repo_selector {
+            self.repo_selector_index = self.repo_index;
+        }
+    }
+
+    pub fn navigate_repo_selector(&mut self, direction: i32) {
+        let new_index = self.repo_selector_index as i32 + direction;
+        if new_index >= 0 && new_index < self.repos.len() as i32 {
+            self.repo_selector_index = new_index as usize;
+        }
+    }
+
+    /// Asks for the whole view to switch to the picked repository
+    pub fn select_repo(&mut self) {
+        self.show_repo_selector = false;
+        if self.repo_selector_index != self.repo_index {
+            self.repo_switch = Some(self.repo_selector_index);
+        }
+    }
+
+    pub fn toggle_tag_selector(&mut self) {
+        self.show_tag_selector = !self.show_tag_selector;
+        if self.show_tag_selector {
+            self.tag_selector_index = self.tags.iter()
+                .position(|t| t.name == self.current_branch)
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn navigate_tag_selector(&mut self, direction: i32) {
+        let new_index = self.tag_selector_index as i32 + direction;
+        if new_index >= 0 && new_index < self.tags.len() as i32 {
+            self.tag_selector_index = new_index as usize;
+        }
+    }
+
+    /// Makes the tag the ref whose history is shown
+    pub fn select_tag(&mut self, index: usize) -> bool {
+        if index < self.tags.len() {
+            self.current_branch = self.tags[index].name.clone();
+            self.tag_selector_index = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn on_tick(&mut self) {
+        let computing_stats = self.stats.as_ref().is_some_and(|s| s.stats.is_none() && s.error.is_none());
+        if self.loading || self.remote_progress.is_some() || computing_stats {
+            self.spinner_frame = self.spinner_frame.wrapping_add(1);
+        }
+        self.advance_notifications();
+    }
+
+    /// Queues `message` for the status bar and records it in the log
+    pub fn notify(&mut self, level: Level, message: String) {
+        let notification = Notification::new(level, message);
+        self.message_log.push(notification.clone());
+        if self.message_log.len() > MESSAGE_LOG_LIMIT {
+            self.message_log.remove(0);
+        }
+        self.notifications.push_back(notification);
+        self.advance_notifications();
+    }
+
+    /// Retires the message in the status bar once it's been up long enough
+    /// and starts the clock on the next one
+    fn advance_notifications(&mut self) {
+        if let Some(shown_at) = self.notifications.front().and_then(|n| n.shown_at) {
+            if shown_at.elapsed() >= NOTIFICATION_TIMEOUT {
+                self.notifications.pop_front();
+            }
+        }
+        if let Some(next) = self.notifications.front_mut() {
+            next.shown_at.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Switches to the history of the picked file, or back to the whole
+    /// branch. Returns false if there's no file to follow.
+    pub fn toggle_file_history(&mut self) -> bool {
+        if self.file_history.take().is_some() {
+            return true;
+        }
+        match self.selected_file_change() {
+            Some(file) => {
+                self.file_history = Some(file.path().to_string());
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn toggle_message_log(&mut self) {
+        self.show_message_log = !self.show_message_log;
+    }
+
+    pub fn handle_load_event(&mut self, event: LoadEvent) {
+        match event {
+            LoadEvent::Commits { branch, path, filters, offset, commits } => {
+                // Results for a branch or file we've since switched away from are stale
+                if branch != self.current_branch || path != self.file_history || filters != self.filters {
+                    return;
+                }
+                if offset == 0 {
+                    self.set_commits(commits);
+                } else if offset == self.commits.len() {
+                    self.append_commits(commits);
+                }
+                self.loading = false;
+                self.load_progress = None;
+            },
+            LoadEvent::Progress { branch, path, filters, scanned, found } => {
+                if self.loading && branch == self.current_branch && path == self.file_history && filters == self.filters {
+                    self.load_progress = Some((scanned, found));
+                }
+            },
+            LoadEvent::Error { branch, path, filters, message } => {
+                if branch == self.current_branch && path == self.file_history && filters == self.filters {
+                    self.loading = false;
+                    self.load_progress = None;
+                    self.has_more_commits = false;
+                    self.notify(Level::Error, format!("Failed to load commits: {}", message));
+                    self.load_error = Some(message);
+                }
+            },
+        }
+        self.resolve_pending_jump();
+    }
+
+    /// Replaces the commit list and rebuilds the graph from scratch
+    pub fn set_commits(&mut self, commits: Vec<CommitInfo>) {
+        let (builder, rows) = graph::build_graph(&commits);
+        self.graph_builder = builder;
+        self.graph = rows;
+        self.has_more_commits = commits.len() >= COMMIT_PAGE_SIZE;
+        self.commits = VecDeque::from(commits);
+        self.selected_index = 0;
+        self.load_error = None;
+        self.load_progress = None;
+        self.refresh_visible();
+    }
+
+    /// Appends the next page of history, extending the graph where it left off
+    pub fn append_commits(&mut self, commits: Vec<CommitInfo>) {
+        self.has_more_commits = commits.len() >= COMMIT_PAGE_SIZE;
+        for commit in commits {
+            self.graph.push(self.graph_builder.push(&commit));
+            self.commits.push_back(commit);
+        }
+        self.refresh_visible();
+    }
+
+    /// The list is only narrowed down by search while the query is being
+    /// typed; once confirmed, matches are highlighted in the full history
+    /// instead. Other filters stay applied until cleared.
+    pub fn is_filtering(&self) -> bool {
+        (self.search_mode && !self.search_query.is_empty())
+            || !self.filters.is_empty()
+            || self.type_filter.is_some()
+            || self.pickaxe.is_some()
+            || self.file_history.is_some()
+    }
+
+    pub fn refresh_visible(&mut self) {
+        let searching = self.search_mode && !self.search_query.is_empty();
+        self.visible = self
+            .commits
+            .iter()
+            .enumerate()
+            .filter(|(_, commit)| !searching || search::commit_matches(commit, &self.search_query, self.search_pattern.as_ref()))
+            .filter(|(_, commit)| match &self.type_filter {
+                Some(kind) => commit_types::commit_type(commit).as_ref() == Some(kind),
+                None => true,
+            })
+            .filter(|(_, commit)| match &self.pickaxe {
+                Some(pickaxe) => pickaxe.matches.contains(&commit.hash),
+                None => true,
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        // Keep the selection on a visible commit
+        if !self.visible.is_empty() && !self.visible.contains(&self.selected_index) {
+            self.selected_index = self.visible[0];
+        }
+    }
+
+    pub fn search_matches(&self) -> impl Iterator<Item = usize> + '_ {
+        self.commits
+            .iter()
+            .enumerate()
+            .filter(|(_, commit)| search::commit_matches(commit, &self.search_query, self.search_pattern.as_ref()))
+            .map(|(i, _)| i)
+    }
+
+    pub fn start_search(&mut self) {
+        self.search_mode = true;
+        self.search_query.clear();
+        self.compile_search();
+    }
+
+    pub fn search_input(&mut self, c: char) {
+        self.search_query.push(c);
+        self.compile_search();
+    }
+
+    pub fn search_backspace(&mut self) {
+        self.search_query.pop();
+        self.compile_search();
+    }
+
+    /// Switches the search between plain text and a regex
+    pub fn toggle_search_regex(&mut self) {
+        self.search_regex = !self.search_regex;
+        self.compile_search();
+    }
+
+    /// Compiles the search when it's a regex, starting over on its matches
+    /// through the whole history
+    fn compile_search(&mut self) {
+        let pattern = if self.search_query.is_empty() || !self.search_regex {
+            Ok(None)
+        } else {
+            search::compile(&self.search_query).map(Some)
+        };
+        (self.search_pattern, self.search_error) = match pattern {
+            Ok(pattern) => (pattern, None),
+            Err(_) => (None, Some("invalid regex".to_string())),
+        };
+        self.search_results = self.search_pattern.as_ref().map(|_| SearchResults { query: self.search_query.clone(), ..Default::default() });
+        self.refresh_visible();
+    }
+
+    pub fn confirm_search(&mut self) {
+        self.search_mode = false;
+        self.refresh_visible();
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search_mode = false;
+        self.search_query.clear();
+        self.compile_search();
+    }
+
+    /// Jumps to the next (or previous) commit matching the confirmed search,
+    /// wrapping around the loaded history
+    pub fn jump_to_match(&mut self, forward: bool) {
+        if self.search_query.is_empty() {
+            return;
+        }
+        let matches: Vec<usize> = self.search_matches().collect();
+        let next = matches.iter().find(|&&i| i > self.selected_index);
+        // Past the loaded matches, a regex search goes on to those found
+        // further down the history, loading it up to them. Once it's all
+        // loaded, it wraps around like any other search.
+        if forward && next.is_none() && self.has_more_commits {
+            if let Some(results) = &self.search_results {
+                let loaded: HashSet<&str> = self.commits.iter().map(|commit| commit.hash.as_str()).collect();
+                if let Some(hash) = results.matches.iter().find(|hash| !loaded.contains(hash.as_str())) {
+                    self.jump_to_commit(hash.clone());
+                    return;
+                }
+            }
+        }
+        let target = if forward {
+            next.or(matches.first())
+        } else {
+            matches.iter().rev().find(|&&i| i < self.selected_index).or(matches.last())
+        };
+        if let Some(&index) = target {
+            self.selected_index = index;
+        }
+    }
+
+    /// Whether the selection is close enough to the end of the loaded
+    /// history that the next page should be fetched. Measured against the
+    /// visible commits so a narrow filter keeps pulling in pages.
+    pub fn needs_more_commits(&self) -> bool {
+        if self.loading || !self.has_more_commits {
+            return false;
+        }
+        if self.pending_jump.is_some() {
+            return true;
+        }
+        let position = self.visible.iter().position(|&i| i == self.selected_index).unwrap_or(0);
+        position + COMMIT_PAGE_SIZE / 4 >= self.visible.len()
+    }
+
+    pub fn toggle_author_filter(&mut self) {
+        self.show_author_filter = !self.show_author_filter;
+        if self.show_author_filter {
+            // Start on the active filter, if there is one
+            self.author_selector_index = self
+                .filters
+                .author
+                .as_ref()
+                .and_then(|active| self.authors.iter().position(|a| a == active))
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn navigate_author_selector(&mut self, direction: i32) {
+        let new_index = self.author_selector_index as i32 + direction;
+        if new_index >= 0 && new_index < self.authors.len() as i32 {
+            self.author_selector_index = new_index as usize;
+        }
+    }
+
+    pub fn select_author(&mut self, index: usize) {
+        if let Some(author) = self.authors.get(index) {
+            let filters = FilterSet { author: Some(author.clone()), ..self.filters.clone() };
+            self.author_selector_index = index;
+            self.show_author_filter = false;
+            self.set_filters(filters);
+        }
+    }
+
+    pub fn toggle_type_filter(&mut self) {
+        self.show_type_filter = !self.show_type_filter;
+        if self.show_type_filter {
+            self.commit_types = commit_types::commit_types(self.commits.iter());
+            self.type_selector_index = self
+                .type_filter
+                .as_ref()
+                .and_then(|active| self.commit_types.iter().position(|(kind, _)| kind == active))
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn navigate_type_selector(&mut self, direction: i32) {
+        let new_index = self.type_selector_index as i32 + direction;
+        if new_index >= 0 && new_index < self.commit_types.len() as i32 {
+            self.type_selector_index = new_index as usize;
+        }
+    }
+
+    pub fn select_type(&mut self, index: usize) {
+        if let Some((kind, _)) = self.commit_types.get(index) {
+            self.type_filter = Some(kind.clone());
+            self.show_type_filter = false;
+            self.refresh_visible();
+        }
+    }
+
+    pub fn clear_type_filter(&mut self) {
+        self.type_filter = None;
+        self.refresh_visible();
+    }
+
+    pub fn set_pickaxe(&mut self, pickaxe: Option<PickaxeResults>) {
+        self.pickaxe = pickaxe;
+        self.refresh_visible();
+    }
+
+    /// Applies new filters by reloading history from the start, as the
+    /// loader filters while it walks
+    pub fn set_filters(&mut self, filters: FilterSet) {
+        if filters == self.filters {
+            return;
+        }
+        self.filters = filters;
+        self.set_commits(Vec::new());
+        // Pages for the old filters still in flight are ignored when they land
+        self.loading = false;
+        self.has_more_commits = true;
+    }
+
+    pub fn set_date_filter(&mut self, dates: Option<(i64, i64)>) {
+        self.set_filters(FilterSet { dates, ..self.filters.clone() });
+    }
+
+    /// Hides merge commits, like `--no-merges`, or shows them all again
+    pub fn toggle_merges(&mut self) {
+        let merges = match self.filters.merges {
+            MergeFilter::Exclude => MergeFilter::All,
+            _ => MergeFilter::Exclude,
+        };
+        self.set_filters(FilterSet { merges, ..self.filters.clone() });
+    }
+
+    /// Follows only the first parent of merges, which lists just the
+    /// commits made on (or merged into) the mainline
+    pub fn toggle_first_parent(&mut self) {
+        let first_parent = !self.filters.first_parent;
+        self.set_filters(FilterSet { first_parent, ..self.filters.clone() });
+    }
+
+    /// Shows `shown` in that order, hiding the other columns
+    pub fn set_columns(&mut self, shown: &[Column]) {
+        self.columns.clear();
+        for &column in shown {
+            if !self.columns.iter().any(|&(c, _)| c == column) {
+                self.columns.push((column, true));
+            }
+        }
+        for column in COLUMNS {
+            if !shown.contains(&column) {
+                self.columns.push((column, false));
+            }
+        }
+    }
+
+    pub fn shown_columns(&self) -> Vec<Column> {
+        self.columns.iter().filter(|(_, shown)| *shown).map(|&(column, _)| column).collect()
+    }
+
+    pub fn toggle_column_picker(&mut self) {
+        self.show_column_picker = !self.show_column_picker;
+    }
+
+    pub fn navigate_column_picker(&mut self, direction: i32) {
+        let new_index = self.column_picker_index as i32 + direction;
+        if new_index >= 0 && new_index < self.columns.len() as i32 {
+            self.column_picker_index = new_index as usize;
+        }
+    }
+
+    /// Shows or hides the highlighted column
+    pub fn toggle_column(&mut self) {
+        if let Some((_, shown)) = self.columns.get_mut(self.column_picker_index) {
+            *shown = !*shown;
+        }
+    }
+
+    pub fn toggle_filter_panel(&mut self) {
+        self.show_filter_panel = !self.show_filter_panel;
+    }
+
+    pub fn navigate_filter_panel(&mut self, direction: i32) {
+        let last = filters::FILTER_KINDS.len() as i32 - 1;
+        self.filter_panel_index = (self.filter_panel_index as i32 + direction).clamp(0, last) as usize;
+    }
+
+    pub fn selected_filter(&self) -> FilterKind {
+        filters::FILTER_KINDS[self.filter_panel_index]
+    }
+
+    pub fn clear_filter(&mut self, kind: FilterKind) {
+        let mut filters = self.filters.clone();
+        match kind {
+            FilterKind::Author => filters.author = None,
+            FilterKind::Dates => filters.dates = None,
+            FilterKind::Paths => filters.paths = None,
+            FilterKind::Message => filters.message = None,
+            FilterKind::Merges => filters.merges = MergeFilter::All,
+            FilterKind::FirstParent => filters.first_parent = false,
+            FilterKind::Type => self.clear_type_filter(),
+            FilterKind::Pickaxe => self.set_pickaxe(None),
+        }
+        self.set_filters(filters);
+    }
+
+    pub fn toggle_date_filter(&mut self) {
+        self.show_date_filter = !self.show_date_filter;
+        self.date_selector_index = 0;
+    }
+
+    /// The presets, then a custom range and no limit
+    pub fn navigate_date_selector(&mut self, direction: i32) {
+        let last = dates::DATE_PRESETS.len() as i32 + 1;
+        self.date_selector_index = (self.date_selector_index as i32 + direction).clamp(0, last) as usize;
+    }
+
+    /// Applies the preset under the cursor, or asks for a custom range
+    pub fn select_date_range(&mut self) {
+        self.show_date_filter = false;
+        match dates::DATE_PRESETS.get(self.date_selector_index) {
+            Some((_, span)) => {
+                let now = chrono::Local::now().timestamp();
+                self.set_date_filter(Some((now - span, i64::MAX)));
+            },
+            None if self.date_selector_index == dates::DATE_PRESETS.len() => {
+                let title = "Dates (since..until, e.g. 2024-01-01..2024-03-31 or 2 weeks ago..)".to_string();
+                self.prompt = Some(Prompt::new(title, String::new(), PromptAction::DateRange));
+            },
+            None => self.set_date_filter(None),
+        }
+    }
+
+    /// Filters by a typed range such as `2024-01-01..2 weeks ago`
+    pub fn set_date_range(&mut self, input: &str) {
+        match dates::parse_range(input, chrono::Local::now()) {
+            Ok(range) => self.set_date_filter(Some(range)),
+            Err(e) => self.notify(Level::Warning, e),
+        }
+    }
+
+    pub fn toggle_branch_selector(&mut self) {
+        self.show_branch_selector = !self.show_branch_selector;
+        self.branch_typing = false;
+        self.branch_query.clear();
+        self.order_branches();
+        if self.show_branch_selector {
+            // Find the current branch in the list
+            self.branch_selector_index = self.branch_order.iter()
+                .position(|&(i, _)| self.branches[i].name == self.current_branch)
+                .unwrap_or(0);
+        }
+    }
+
+    /// The branch or tag being viewed, as `ref_label` shows it
+    pub fn branch_label(&self) -> &str {
+        ref_label(&self.current_branch)
+    }
+
+    /// Switches to the branch under the cursor, putting it at the top of
+    /// the recent ones
+    pub fn select_branch(&mut self) -> bool {
+        let name = match self.selected_branch() {
+            Some(branch) => branch.name.clone(),
+            None => return false,
+        };
+        self.use_branch(&name);
+        self.current_branch = name;
+        true
+    }
+
+    /// Puts the branch at the top of the ones picked lately
+    pub fn use_branch(&mut self, name: &str) {
+        self.recent_branches.retain(|recent| recent != name);
+        self.recent_branches.insert(0, name.to_string());
+        self.recent_branches.truncate(fuzzy::MAX_RECENT);
+    }
+
+    pub fn navigate_branch_selector(&mut self, direction: i32) {
+        let new_index = self.branch_selector_index as i32 + direction;
+        if new_index >= 0 && new_index < self.branch_order.len() as i32 {
+            self.branch_selector_index = new_index as usize;
+        }
+    }
+
+    pub fn toggle_diff_view(&mut self) {
+        self.show_diff = !self.show_diff;
+        self.diff_scroll = 0;
+        // The diff is what you want to scroll once it's open
+        self.focus = if self.show_diff { Focus::Details } else { Focus::Commits };
+    }
+
+    /// Grows or shrinks the commit list by `delta` percent of the width
+    pub fn resize_list(&mut self, delta: i16) {
+        self.list_width = (self.list_width as i16 + delta).clamp(10, 90) as u16;
+    }
+
+    pub fn toggle_maximise(&mut self) {
+        self.maximised = !self.maximised;
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focus = self.focus.next();
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.focus = self.focus.prev();
+    }
+
+    /// Opens a blame of the picked file as of the selected commit
+    pub fn open_blame(&mut self, path: String, lines: Vec<BlameLine>) {
+        let commit = match self.commits.get(self.selected_index) {
+            Some(commit) => commit.hash.clone(),
+            None => return,
+        };
+        self.blame = Some(BlameView { path, commit, lines, cursor: 0, anchor: None });
+        self.focus = Focus::Details;
+    }
+
+    /// Starts browsing the selected commit's tree from its top level
+    pub fn open_tree(&mut self, entries: Vec<(String, bool)>) {
+        if let Some(commit) = self.commits.get(self.selected_index) {
+            self.tree = Some(TreeView::new(commit.hash.clone(), entries));
+        }
+    }
+
+    /// Shows the file's contents scrolled to `line`, counted from 0
+    pub fn open_tree_file_at(&mut self, path: String, content: &str, line: usize) {
+        if let Some(tree) = &mut self.tree {
+            let lines = highlight::highlight_file(&path, content);
+            tree.viewer = Some(FileViewer { path, lines, scroll: line });
+        }
+    }
+
+    pub fn open_tree_file(&mut self, path: String, content: &str) {
+        self.open_tree_file_at(path, content, 0);
+    }
+
+    /// Marks the selected commit, or unmarks it
+    pub fn toggle_mark(&mut self) {
+        let hash = match self.commits.get(self.selected_index) {
+            Some(commit) => commit.hash.clone(),
+            None => return,
+        };
+        match self.marked.iter().position(|h| *h == hash) {
+            Some(i) => {
+                self.marked.remove(i);
+            },
+            None => self.marked.push(hash),
+        }
+        if self.marked.is_empty() {
+            self.show_marked_diff = false;
+        }
+    }
+
+    /// The marked commits that are loaded, oldest first
+    pub fn marked_commits(&self) -> Vec<&CommitInfo> {
+        self.commits.iter().rev().filter(|c| self.marked.contains(&c.hash)).collect()
+    }
+
+    pub fn open_patch_preview(&mut self, preview: PatchPreview) {
+        self.patch_preview = Some(preview);
+        self.selected_file = None;
+        self.diff_scroll = 0;
+        self.details_scroll = 0;
+    }
+
+    pub fn close_patch_preview(&mut self) {
+        self.patch_preview = None;
+        self.selected_file = None;
+        self.diff_scroll = 0;
+    }
+
+    pub fn toggle_marked_diff(&mut self) {
+        if self.marked.is_empty() && !self.show_marked_diff {
+            self.notify(Level::Warning, "Mark some commits first".to_string());
+            return;
+        }
+        self.show_marked_diff = !self.show_marked_diff;
+        self.selected_file = None;
+        self.diff_scroll = 0;
+    }
+
+    /// The marked commits, or else the selected one, oldest first so they
+    /// can be applied in order
+    pub fn commits_to_pick(&self) -> Vec<&CommitInfo> {
+        if self.marked.is_empty() {
+            self.commits.get(self.selected_index).into_iter().collect()
+        } else {
+            self.marked_commits()
+        }
+    }
+
+    /// Asks before doing `action`; main runs it if the answer is yes
+    pub fn confirm(&mut self, message: String, action: PendingAction) {
+        self.confirmation = Some(Confirmation { message, action });
+    }
+
+    /// Shows the diff of the status entry under the cursor
+    pub fn set_status_diff(&mut self, patch: &str) {
+        if let Some(status) = &mut self.status {
+            status.diff_lines = highlight::highlight_diff(patch);
+            status.diff_scroll = 0;
+            status.hunks = patch
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.starts_with("@@"))
+                .map(|(i, _)| i)
+                .collect();
+            status.hunk = 0;
+        }
+    }
+
+    /// Shows the diff of the stash under the cursor
+    pub fn set_stash_diff(&mut self, patch: &str) {
+        if let Some(stash) = &mut self.stash {
+            stash.diff_lines = highlight::highlight_diff(patch);
+            stash.diff_scroll = 0;
+        }
+    }
+
+    pub fn close_blame(&mut self) {
+        self.blame = None;
+    }
+
+    /// Selects the commit behind the blame line under the cursor, loading
+    /// more history first if it hasn't been reached yet
+    pub fn jump_to_blamed_commit(&mut self) {
+        let hash = match self.blame.as_ref().and_then(|b| b.selected_commit()) {
+            Some(hash) => hash.to_string(),
+            None => return,
+        };
+        self.blame = None;
+        self.focus = Focus::Commits;
+        self.jump_to_commit(hash);
+    }
+
+    /// Selects the commit at the top of the line history, closing the views
+    /// it was opened from
+    pub fn jump_to_line_change(&mut self) {
+        let hash = match self.line_history.as_ref().and_then(|view| view.selected()) {
+            Some(change) => change.commit.hash.clone(),
+            None => return,
+        };
+        self.line_history = None;
+        self.file_view = None;
+        self.blame = None;
+        self.focus = Focus::Commits;
+        self.jump_to_commit(hash);
+    }
+
+    /// Selects the commit `hash`, loading more history first if it hasn't
+    /// been reached yet
+    pub fn jump_to_commit(&mut self, hash: String) {
+        self.pending_jump = Some(hash);
+        self.resolve_pending_jump();
+    }
+
+    /// Loaded commits that have `hash` as a parent, nearest first
+    pub fn children_of(&self, hash: &str) -> Vec<&CommitInfo> {
+        let index = self.commits.iter().position(|c| c.hash == hash).unwrap_or(self.commits.len());
+        self.commits
+            .range(..index)
+            .rev()
+            .filter(|c| c.parents.iter().any(|p| p == hash))
+            .collect()
+    }
+
+    /// Selects the selected commit's `nth` parent, loading more history
+    /// if it hasn't been reached yet
+    pub fn jump_to_parent(&mut self, nth: usize) {
+        let parent = match self.commits.get(self.selected_index) {
+            Some(commit) => commit.parents.get(nth).cloned(),
+            None => return,
+        };
+        match parent {
+            Some(hash) => {
+                self.pending_jump = Some(hash);
+                self.resolve_pending_jump();
+            },
+            None if nth == 0 => self.notify(Level::Info, "This is a root commit".to_string()),
+            None => self.notify(Level::Info, "This isn't a merge commit".to_string()),
+        }
+    }
+
+    /// Selects the nearest child of the selected commit
+    pub fn jump_to_child(&mut self) {
+        let child = match self.commits.get(self.selected_index) {
+            Some(commit) => self.children_of(&commit.hash).first().map(|c| c.hash.clone()),
+            None => return,
+        };
+        match child {
+            Some(hash) => {
+                self.pending_jump = Some(hash);
+                self.resolve_pending_jump();
+            },
+            None => self.notify(Level::Info, "No loaded commit has this one as a parent".to_string()),
+        }
+    }
+
+    fn resolve_pending_jump(&mut self) {
+        let hash = match &self.pending_jump {
+            Some(hash) => hash,
+            None => return,
+        };
+        if let Some(index) = self.commits.iter().position(|c| &c.hash == hash) {
+            self.pending_jump = None;
+            if self.visible.contains(&index) {
+                self.select_commit(index);
+            } else {
+                self.notify(Level::Warning, "That commit is hidden by the current filters".to_string());
+            }
+        } else if !self.has_more_commits && !self.loading {
+            self.pending_jump = None;
+            self.notify(Level::Warning, "That commit isn't in the history being shown".to_string());
+        }
+    }
+
+    /// Scrolls the details pane, or the diff if that is showing
+    pub fn scroll_details(&mut self, delta: i32) {
+        if let Some(view) = &mut self.file_view {
+            view.move_cursor(delta, self.details_height);
+            return;
+        }
+        if let Some(blame) = &mut self.blame {
+            blame.move_cursor(delta);
+            return;
+        }
+        if self.show_diff {
+            self.scroll_diff(delta);
+            return;
+        }
+        // The message wraps, so long lines take up several rows
+        let width = self.details_width.max(1);
+        let max = self
+            .commits
+            .get(self.selected_index)
+            .map(|commit| {
+                let message: usize = commit.message.lines().map(|line| line.width().max(1).div_ceil(width)).sum();
+                message + commit.parents.len() + 7
+            })
+            .unwrap_or(0) as i32;
+        self.details_scroll = (self.details_scroll as i32).saturating_add(delta).clamp(0, max) as u16;
+    }
+
+    /// Highlights the selected commit's diff once, rather than on every frame
+    pub fn prepare_diff_view(&mut self) {
+        if !self.show_diff {
+            return;
+        }
+        let (id, patch, files) = match self.shown_diff() {
+            Some(shown) => shown,
+            None => return,
+        };
+        let key = (id, self.selected_file);
+        if self.diff_lines_key.as_ref() == Some(&key) {
+            return;
+        }
+
+        // A patch file being previewed is shown as it's written
+        let diff_lines = match (&self.patch_preview, self.selected_file) {
+            (Some(_), Some(index)) => highlight::highlight_diff(diff_view::file_section(patch, index)),
+            (Some(_), None) => highlight::highlight_diff(patch),
+            (None, index) => diff_view::unified_lines(patch, files, index),
+        };
+        let split_rows = match self.selected_file {
+            Some(index) => diff_view::build_split_rows(files.get(index..=index).unwrap_or(&[])),
+            None => diff_view::build_split_rows(files),
+        };
+        self.diff_lines = diff_lines;
+        self.split_rows = split_rows;
+        self.diff_lines_key = Some(key);
+    }
+
+    /// Positions in `commits` of the newest and oldest ends of the range
+    /// selection, if one is active
+    pub fn range_ends(&self) -> Option<(usize, usize)> {
+        let anchor = self.range_anchor.as_ref()?;
+        let anchor = self.commits.iter().position(|c| &c.hash == anchor)?;
+        Some((anchor.min(self.selected_index), anchor.max(self.selected_index)))
+    }
+
+    /// Starts a range selection at the selected commit, or ends the current one
+    pub fn toggle_range(&mut self) {
+        if self.range_anchor.take().is_none() {
+            self.range_anchor = self.commits.get(self.selected_index).map(|c| c.hash.clone());
+        }
+        self.selected_file = None;
+        self.diff_scroll = 0;
+    }
+
+    /// The range's combined diff once it has been computed for the current
+    /// ends of the selection
+    fn active_range_diff(&self) -> Option<&RangeDiff> {
+        let (newest, oldest) = self.range_ends()?;
+        let range = self.range_diff.as_ref()?;
+        (range.newest == self.commits[newest].hash && range.oldest == self.commits[oldest].hash).then_some(range)
+    }
+
+    /// Steps a merge commit's diff through each parent and then the
+    /// combined view
+    pub fn cycle_merge_view(&mut self) {
+        let parents = match self.commits.get(self.selected_index) {
+            Some(commit) => commit.parents.len(),
+            None => return,
+        };
+        if parents < 2 {
+            self.notify(Level::Info, "Only merge commits have more than one diff".to_string());
+            return;
+        }
+        self.merge_view = match self.merge_view {
+            MergeView::Parent(nth) if nth + 1 < parents => MergeView::Parent(nth + 1),
+            MergeView::Parent(_) => MergeView::Combined,
+            MergeView::Combined => MergeView::Parent(0),
+        };
+        self.selected_file = None;
+        self.diff_scroll = 0;
+    }
+
+    /// How the selected commit's diff is taken, when that isn't simply
+    /// against its first parent
+    pub fn active_merge_view(&self) -> Option<MergeView> {
+        let parents = self.commits.get(self.selected_index)?.parents.len();
+        match self.merge_view {
+            MergeView::Parent(nth) if nth > 0 && nth < parents => Some(self.merge_view),
+            MergeView::Combined if parents > 1 => Some(self.merge_view),
+            _ => None,
+        }
+    }
+
+    fn active_merge_diff(&self) -> Option<&MergeDiff> {
+        let view = self.active_merge_view()?;
+        let merge = self.merge_diff.as_ref()?;
+        (merge.view == view && merge.hash == self.commits[self.selected_index].hash).then_some(merge)
+    }
+
+    /// The selected commit's diff once it has been read
+    fn active_commit_diff(&self) -> Option<&CommitDiff> {
+        let commit = self.commits.get(self.selected_index)?;
+        self.commit_diff.as_ref().filter(|diff| diff.hash == commit.hash && diff.paths == commit.paths)
+    }
+
+    fn active_commit_stat(&self) -> Option<&DiffStat> {
+        let commit = self.commits.get(self.selected_index)?;
+        self.commit_stat.as_ref().filter(|stat| stat.hash == commit.hash && stat.paths == commit.paths)
+    }
+
+    /// The selected commit's diffstat when the file list shows that commit's
+    /// own diff, rather than a range, merge or patch
+    fn shown_commit_stat(&self) -> Option<&DiffStat> {
+        if self.other_diff_shown() {
+            return None;
+        }
+        self.active_commit_stat()
+    }
+
+    /// Whether the diff shown is a range's, merge's or patch's rather than
+    /// the selected commit's own
+    fn other_diff_shown(&self) -> bool {
+        self.patch_preview.is_some()
+            || self.active_marked_diff().is_some()
+            || self.active_range_diff().is_some()
+            || self.active_merge_view().is_some()
+    }
+
+    /// The file picked in the selected commit's open diff when it's an
+    /// image, shown as pictures instead of lines
+    pub fn image_change(&self) -> Option<&FileChange> {
+        if !self.show_diff || self.blame.is_some() || self.file_view.is_some() || self.other_diff_shown() {
+            return None;
+        }
+        self.selected_file_change().filter(|file| image::is_image(file.path()))
+    }
+
+    /// The marked commits' combined diff once it has been computed for the
+    /// commits marked now
+    fn active_marked_diff(&self) -> Option<&MarkedDiff> {
+        if !self.show_marked_diff {
+            return None;
+        }
+        let marked = self.marked_diff.as_ref()?;
+        let current = self.marked_commits();
+        (marked.hashes.len() == current.len() && marked.hashes.iter().zip(&current).all(|(a, b)| *a == b.hash))
+            .then_some(marked)
+    }
+
+    /// The diff being looked at: a patch file being previewed, the marked
+    /// commits' or the range's combined diff while one is shown, otherwise
+    /// the selected commit's against the parent picked for merges. Comes
+    /// with a key telling them apart.
+    pub fn shown_diff(&self) -> Option<(String, &str, &[FileChange])> {
+        if let Some(preview) = &self.patch_preview {
+            return Some((preview.path.clone(), preview.patch.as_str(), preview.info.files.as_slice()));
+        }
+        if let Some(marked) = self.active_marked_diff() {
+            return Some((marked.hashes.join(" "), marked.patch.as_str(), marked.info.files.as_slice()));
+        }
+        if let Some(range) = self.active_range_diff() {
+            let key = format!("{}..{}", range.oldest, range.newest);
+            return Some((key, range.patch.as_str(), range.info.files.as_slice()));
+        }
+        if let Some(merge) = self.active_merge_diff() {
+            let key = format!("{} {:?}", merge.hash, merge.view);
+            return Some((key, merge.patch.as_str(), merge.info.files.as_slice()));
+        }
+        let diff = self.active_commit_diff()?;
+        // The full diff replaces one cut short under the same commit
+        let key = format!("{} {} {}", diff.hash, diff.paths.join(" "), diff.cut_short);
+        Some((key, diff.patch.as_str(), diff.info.files.as_slice()))
+    }
+
+    /// Whether the diff shown is the selected commit's with some large
+    /// files cut short
+    pub fn diff_cut_short(&self) -> bool {
+        self.shown_commit_stat().is_some() && self.active_commit_diff().is_some_and(|diff| diff.cut_short)
+    }
+
+    /// Drops every diff read so far, to be read again once they're shown,
+    /// after a setting that changes what they look like
+    pub fn reload_diffs(&mut self) {
+        self.commit_diff = None;
+        self.commit_stat = None;
+        self.range_diff = None;
+        self.marked_diff = None;
+        self.merge_diff = None;
+        self.image_preview = None;
+        self.diff_lines_key = None;
+        // Files paired up or split apart leave the list a different length
+        self.selected_file = None;
+    }
+
+    /// Asks for the shown diff again without cutting large files short
+    pub fn load_full_diff(&mut self) {
+        if self.diff_cut_short() {
+            self.full_diff = self.commits.get(self.selected_index).map(|commit| commit.hash.clone());
+        }
+    }
+
+    fn shown_diff_info(&self) -> Option<&DiffInfo> {
+        if let Some(preview) = &self.patch_preview {
+            return Some(&preview.info);
+        }
+        if let Some(marked) = self.active_marked_diff() {
+            return Some(&marked.info);
+        }
+        if let Some(range) = self.active_range_diff() {
+            return Some(&range.info);
+        }
+        if let Some(merge) = self.active_merge_diff() {
+            return Some(&merge.info);
+        }
+        self.active_commit_diff().map(|diff| &diff.info)
+    }
+
+    pub fn selected_file_change(&self) -> Option<&FileChange> {
+        self.shown_diff_info()?.files.get(self.selected_file?)
+    }
+
+    /// Moves the file cursor back, returning to "all files" before the first
+    pub fn navigate_left(&mut self) {
+        self.selected_file = match self.selected_file {
+            Some(0) | None => None,
+            Some(index) => Some(index - 1),
+        };
+        self.diff_scroll = 0;
+    }
+
+    pub fn navigate_right(&mut self) {
+        let file_count = self.shown_diff_info().map(|diff| diff.files.len()).unwrap_or(0);
+        let next = self.selected_file.map(|index| index + 1).unwrap_or(0);
+        if next < file_count {
+            self.selected_file = Some(next);
+            self.diff_scroll = 0;
+        }
+    }
+
+    /// Picks a file of the selected commit, or None for all of them
+    pub fn select_file(&mut self, index: Option<usize>) {
+        if index != self.selected_file {
+            self.selected_file = index;
+            self.diff_scroll = 0;
+        }
+    }
+
+    pub fn select_last_file(&mut self) {
+        let file_count = self.shown_diff_info().map(|diff| diff.files.len()).unwrap_or(0);
+        if file_count > 0 {
+            self.select_file(Some(file_count - 1));
+        }
+    }
+
+    pub fn toggle_split_diff(&mut self) {
+        self.split_diff = !self.split_diff;
+        self.diff_scroll = 0;
+    }
+
+    /// Wraps long diff lines instead of letting them run off the pane
+    pub fn toggle_wrap_diff(&mut self) {
+        self.wrap_diff = !self.wrap_diff;
+        self.diff_hscroll = 0;
+    }
+
+    /// Switches to the next built-in theme and returns it
+    pub fn cycle_theme(&mut self) -> ThemeName {
+        let name = Theme::current().name.next();
+        Theme::set(name);
+        // The highlighted diff was coloured by the old theme
+        self.diff_lines_key = None;
+        name
+    }
+
+    /// Scrolls the diff sideways by `delta` columns, up to its longest line
+    pub fn scroll_diff_sideways(&mut self, delta: i32) {
+        if self.wrap_diff {
+            return;
+        }
+        let longest = self.diff_lines.iter().map(|line| line.width()).max().unwrap_or(0) as i32;
+        let max = longest - self.details_width as i32 / 2;
+        self.diff_hscroll = (self.diff_hscroll as i32).saturating_add(delta).clamp(0, max.max(0)) as u16;
+    }
+
+    /// Notes that the terminal changed size; the layout is recomputed on the
+    /// next draw
+    pub fn resize(&mut self) {
+        self.resized = true;
+    }
+
+    /// Keeps the details and diff scroll positions within their content
+    /// once the panes have their new size
+    fn clamp_scroll(&mut self) {
+        self.scroll_details(0);
+        self.scroll_diff(0);
+        self.scroll_diff_sideways(0);
+    }
+
+    pub fn diff_line_count(&self) -> usize {
+        if self.split_diff {
+            self.split_rows.len()
+        } else {
+            self.diff_lines.len()
+        }
+    }
+
+    /// Scrolls the full diff by `delta` lines, stopping at either end
+    pub fn scroll_diff(&mut self, delta: i32) {
+        let max = self.diff_line_count().saturating_sub(1) as i32;
+        self.diff_scroll = (self.diff_scroll as i32).saturating_add(delta).clamp(0, max.max(0)) as u16;
+    }
+
+    pub fn navigate_up(&mut self) {
+        if let Some(pos) = self.visible.iter().position(|&i| i == self.selected_index) {
+            if pos > 0 {
+                self.select_commit(self.visible[pos - 1]);
+            }
+        }
+    }
+
+    /// Scrolls the commit list just far enough to keep the selection in view
+    fn scroll_list_to_selection(&mut self) {
+        let height = self.list_height.max(1);
+        let pos = self.visible.iter().position(|&i| i == self.selected_index).unwrap_or(0);
+        if pos < self.list_offset {
+            self.list_offset = pos;
+        } else if pos >= self.list_offset + height {
+            self.list_offset = pos + 1 - height;
+        }
+        self.list_offset = self.list_offset.min(self.visible.len().saturating_sub(height));
+    }
+
+    /// Moves the selection and the view by a screenful, keeping the selected
+    /// commit on the same row
+    pub fn page_commits(&mut self, forward: bool) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let height = self.list_height.max(1);
+        let pos = self.visible.iter().position(|&i| i == self.selected_index).unwrap_or(0);
+        let new_pos = if forward {
+            self.list_offset += height;
+            (pos + height).min(self.visible.len() - 1)
+        } else {
+            self.list_offset = self.list_offset.saturating_sub(height);
+            pos.saturating_sub(height)
+        };
+        self.select_commit(self.visible[new_pos]);
+        self.scroll_list_to_selection();
+    }
+
+    pub fn navigate_first(&mut self) {
+        if let Some(&first) = self.visible.first() {
+            self.select_commit(first);
+        }
+    }
+
+    pub fn navigate_last(&mut self) {
+        if let Some(&last) = self.visible.last() {
+            self.select_commit(last);
+        }
+    }
+
+    /// Selects the commit, putting its details and diff back where they
+    /// were last left
+    fn select_commit(&mut self, index: usize) {
+        if index == self.selected_index {
+            return;
+        }
+        if let Some(commit) = self.commits.get(self.selected_index) {
+            let position = ScrollPosition {
+                details: self.details_scroll,
+                diff: self.diff_scroll,
+                file: self.selected_file,
+            };
+            self.scroll_positions.insert(commit.hash.clone(), position);
+        }
+        self.selected_index = index;
+        let position = self.commits.get(index).and_then(|commit| self.scroll_positions.get(&commit.hash).copied());
+        match position {
+            Some(position) => {
+                self.details_scroll = position.details;
+                self.diff_scroll = position.diff;
+                self.selected_file = position.file;
+            },
+            None => {
+                self.diff_scroll = 0;
+                self.details_scroll = 0;
+                self.selected_file = None;
+            },
+        }
+    }
+
+    pub fn navigate_down(&mut self) {
+        if let Some(pos) = self.visible.iter().position(|&i| i == self.selected_index) {
+            if pos + 1 < self.visible.len() {
+                self.select_commit(self.visible[pos + 1]);
+            }
+        }
+    }
+}
+
+pub fn draw_ui(f: &mut Frame, app: &mut App) {
+    let size = f.size();
+    // Pictures are only placed where this frame's drawing puts them
+    if let Some(preview) = app.image_preview.as_mut() {
+        preview.slots = [None, None];
+    }
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        draw_too_small(f, size);
+        return;
+    }
+    // The status bar sits under everything, popups included
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(size);
+    status_bar::draw_status_bar(f, app, rows[1]);
+    draw_screen(f, app, rows[0]);
+
+    if let Some(dialog) = &app.commit_dialog {
+        commit_dialog::draw_commit_dialog(f, dialog, rows[0]);
+    }
+    if let Some(prompt) = &app.prompt {
+        prompt::draw_prompt(f, prompt, rows[0]);
+    }
+    if let Some(confirmation) = &app.confirmation {
+        confirm::draw_confirm(f, confirmation, rows[0]);
+    }
+}
+
+/// Shown in place of the UI while the terminal is smaller than it can fit in
+fn draw_too_small(f: &mut Frame, size: Rect) {
+    let theme = Theme::current();
+    let text = vec![
+        Line::from(Span::styled("Terminal too small", theme.warning)),
+        Line::from(format!("{}x{}, needs at least {}x{}", size.width, size.height, MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    // Vertically centred, as far as the height allows
+    let top = size.height.saturating_sub(text.len() as u16) / 2;
+    let area = Rect { y: size.y + top, height: size.height - top, ..size };
+    let notice = Paragraph::new(text).alignment(Alignment::Center).wrap(Wrap { trim: true });
+    f.render_widget(notice, area);
+}
+
+/// Whichever view is open, or the commit list and details
+fn draw_screen(f: &mut Frame, app: &mut App, size: Rect) {
+    if app.show_branch_selector {
+        draw_branch_selector(f, app, size);
+        return;
+    }
+
+    if app.show_author_filter {
+        draw_author_filter(f, app, size);
+        return;
+    }
+
+    if app.show_type_filter {
+        commit_types::draw_type_filter(f, app, size);
+        return;
+    }
+
+    if app.show_date_filter {
+        draw_date_filter(f, app, size);
+        return;
+    }
+
+    if app.show_filter_panel {
+        filters::draw_filter_panel(f, app, size);
+        return;
+    }
+
+    if app.show_tag_selector {
+        draw_tag_selector(f, app, size);
+        return;
+    }
+
+    if app.show_repo_selector {
+        draw_repo_selector(f, app, size);
+        return;
+    }
+
+    if app.show_column_picker {
+        draw_column_picker(f, app, size);
+        return;
+    }
+
+    if app.show_message_log {
+        notifications::draw_message_log(f, app, size);
+        return;
+    }
+
+    if let Some(tree) = &app.tree {
+        tree::draw_tree_view(f, tree, size);
+        return;
+    }
+
+    if let Some(grep) = &app.grep {
+        grep::draw_grep_view(f, grep, size);
+        return;
+    }
+
+    if let Some(view) = &app.line_history {
+        line_history::draw_line_history(f, app, view, size);
+        return;
+    }
+
+    if let Some(status) = &app.status {
+        status::draw_status_view(f, status, size);
+        return;
+    }
+
+    if let Some(stash) = &app.stash {
+        stash::draw_stash_view(f, stash, size);
+        return;
+    }
+
+    if let Some(reflog) = &app.reflog {
+        reflog::draw_reflog_view(f, reflog, size);
+        return;
+    }
+
+    if let Some(debug) = &app.debug {
+        debug::draw_debug_view(f, debug, size);
+        return;
+    }
+
+    if let Some(compare) = &app.compare {
+        compare::draw_compare_view(f, compare, size);
+        return;
+    }
+
+    if let Some(stats) = &app.stats {
+        let spinner = SPINNER_FRAMES[app.spinner_frame % SPINNER_FRAMES.len()];
+        stats::draw_stats_view(f, stats, spinner, size);
+        return;
+    }
+
+    // Create the main layout. A maximised pane takes the whole width and
+    // the other side collapses to nothing
+    let list_width = match (app.maximised, app.focus) {
+        (false, _) => app.list_width,
+        (true, Focus::Commits) => 100,
+        (true, _) => 0,
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(list_width),
+            Constraint::Percentage(100 - list_width),
+        ])
+        .split(size);
+
+    // Draw the commit list, with the search box below it while searching
+    if app.search_mode || !app.search_query.is_empty() {
+        let list_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(chunks[0]);
+        draw_commit_list(f, app, list_chunks[0]);
+        search::draw_search_box(f, app, list_chunks[1]);
+    } else {
+        draw_commit_list(f, app, chunks[0]);
+    }
+
+    // Draw the commit details, or the full diff when it's open, above the
+    // files the commit changed
+    let file_count = app.shown_diff_info().map(|diff| diff.files.len()).unwrap_or(0);
+    let files_height = (file_count as u16 + 2).min(chunks[1].height / 2).max(3);
+    let right_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(files_height)])
+        .split(chunks[1]);
+    app.details_height = right_chunks[0].height.saturating_sub(2) as usize;
+    app.details_width = right_chunks[0].width.saturating_sub(2) as usize;
+    if std::mem::take(&mut app.resized) {
+        app.clamp_scroll();
+    }
+    if let Some(view) = &app.file_view {
+        file_view::draw_file_view(f, app, view, right_chunks[0]);
+    } else if let Some(blame) = &app.blame {
+        blame::draw_blame(f, app, blame, right_chunks[0]);
+    } else if app.image_change().is_some() && app.image_preview.is_some() {
+        image_view::draw_image_view(f, app, right_chunks[0]);
+    } else if app.show_diff {
+        diff_view::draw_diff_view(f, app, right_chunks[0]);
+    } else {
+        draw_commit_details(f, app, right_chunks[0]);
+    }
+    draw_file_list(f, app, right_chunks[1]);
+}
+
+fn draw_branch_selector(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut current_remote: Option<&str> = None;
+    // The row the cursor is on, kept in view
+    let mut selected_row = 0;
+    let filtering = !app.branch_query.is_empty();
+    let recent = if filtering {
+        0
+    } else {
+        app.branch_order.iter().take_while(|&&(i, _)| app.recent_branches.contains(&app.branches[i].name)).count()
+    };
+
+    for (position, (i, matched)) in app.branch_order.iter().enumerate() {
+        let branch = &app.branches[*i];
+        if position == 0 && recent > 0 {
+            items.push(ListItem::new(Line::from(Span::styled("🕘 Recent", theme.accent))));
+        } else if position == recent && recent > 0 {
+            items.push(ListItem::new(Line::from(Span::styled("All branches", theme.accent))));
+        }
+        // Remote branches are listed under a heading for their remote, unless
+        // they're recent or matched, which are listed by their full names
+        let full_name = filtering || position < recent;
+        if let Some(remote) = branch.remote.as_deref().filter(|_| !full_name) {
+            if current_remote != Some(remote) {
+                current_remote = Some(remote);
+                items.push(ListItem::new(Line::from(Span::styled(
+                    format!("🌐 {}", remote),
+                    theme.remote,
+                ))));
+            }
+        }
+
+        let style = if position == app.branch_selector_index {
+            selected_row = items.len();
+            theme.selected
+        } else if branch.name == app.current_branch {
+            theme.current
+        } else {
+            Style::default()
+        };
+
+        let (icon, name) = match branch.remote.as_deref() {
+            Some(_) if full_name => ("🌐 ", branch.name.as_str()),
+            Some(remote) => ("   ", branch.name.strip_prefix(&format!("{}/", remote)).unwrap_or(&branch.name)),
+            None => ("🌿 ", branch.name.as_str()),
+        };
+
+        let mut spans = vec![Span::styled(icon, style)];
+        if filtering {
+            // The matched characters are emphasised, as byte ranges
+            let ranges: Vec<_> = name
+                .char_indices()
+                .enumerate()
+                .filter(|(n, _)| matched.contains(n))
+                .map(|(_, (at, c))| at..at + c.len_utf8())
+                .collect();
+            spans.extend(highlight::emphasise(vec![(style, name.to_string())], &ranges, theme.search_match));
+        } else {
+            spans.push(Span::styled(name.to_string(), style));
+        }
+        if branch.remote.is_none() {
+            spans.push(match branch.ahead_behind {
+                Some((0, 0)) => Span::styled(" ✓", theme.muted),
+                Some((ahead, behind)) => {
+                    let mut counts = String::new();
+                    if ahead > 0 {
+                        counts.push_str(&format!(" ↑{}", ahead));
+                    }
+                    if behind > 0 {
+                        counts.push_str(&format!(" ↓{}", behind));
+                    }
+                    Span::styled(counts, theme.accent)
+                },
+                None => Span::styled(" (no upstream)", theme.warning),
+            });
+        }
+        items.push(ListItem::new(Line::from(spans)));
+    }
+
+    if items.is_empty() {
+        items.push(ListItem::new(Line::from(Span::styled("No branches match", theme.muted))));
+    }
+
+    let title = if app.branch_typing {
+        format!("Select Branch: {}▏ (type to filter, ↑/↓ to navigate, Enter to select, Esc to stop filtering)", app.branch_query)
+    } else {
+        "Select Branch (↑/↓ to navigate, / to filter, Enter to select, K to check out, n/d/R to create/delete/rename, Esc to cancel)".to_string()
+    };
+    let height = area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = items.into_iter().skip((selected_row + 1).saturating_sub(height.max(1))).collect();
+    let list = List::new(items).block(Block::default().title(title).borders(Borders::ALL));
+
+    f.render_widget(list, area);
+}
+
+fn draw_tag_selector(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    if app.tags.is_empty() {
+        let paragraph = Paragraph::new("No tags found.")
+            .block(Block::default().title("Tags").borders(Borders::ALL));
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = app
+        .tags
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| {
+            let style = if i == app.tag_selector_index {
+                theme.selected
+            } else if tag.name == app.current_branch {
+                theme.current
+            } else {
+                Style::default()
+            };
+
+            let mut spans = vec![
+                Span::styled(format!("🏷 {}", tag.name), style),
+                Span::styled(format!(" {}", &tag.target[..7.min(tag.target.len())]), theme.hash),
+            ];
+            if let Some(message) = tag.message.as_deref().and_then(|m| m.lines().next()) {
+                spans.push(Span::raw(format!(" {}", message)));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Select Tag (↑/↓ to navigate, Enter to browse from it, d to delete, Esc to cancel)").borders(Borders::ALL));
+
+    f.render_widget(list, area);
+}
+
+fn draw_column_picker(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let items: Vec<ListItem> = app
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, &(column, shown))| {
+            let style = if i == app.column_picker_index {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            let check = if shown { "[x] " } else { "[ ] " };
+            ListItem::new(Line::from(vec![
+                Span::styled(check, style.patch(if shown { theme.current } else { theme.muted })),
+                Span::styled(column.label(), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Columns (↑/↓ to navigate, Space or Enter to show or hide, Esc to close)").borders(Borders::ALL));
+
+    f.render_widget(list, area);
+}
+
+fn draw_repo_selector(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let items: Vec<ListItem> = app
+        .repos
+        .iter()
+        .enumerate()
+        .map(|(i, repo)| {
+            let style = if i == app.repo_selector_index {
+                theme.selected
+            } else if i == app.repo_index {
+                theme.current
+            } else {
+                Style::default()
+            };
+            let name = std::path::Path::new(repo)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| repo.clone());
+            let mut spans = vec![
+                Span::styled(format!("{:<24}", name), style.add_modifier(Modifier::BOLD)),
+                Span::styled(repo.clone(), style.patch(theme.muted)),
+            ];
+            if i == app.repo_index {
+                spans.push(Span::styled(" (current)", style.patch(theme.current)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Select Repository (↑/↓ to navigate, Enter to switch, Esc to cancel)").borders(Borders::ALL));
+
+    f.render_widget(list, area);
+}
+
+fn draw_date_filter(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let choices = dates::DATE_PRESETS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(["Custom range...".to_string(), "Any time".to_string()]);
+    let items: Vec<ListItem> = choices
+        .enumerate()
+        .map(|(i, name)| {
+            let style = if i == app.date_selector_index {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(Span::styled(name, style)))
+        })
+        .collect();
+
+    let mut title = "Filter by Date (↑/↓ to navigate, Enter to select, Esc to cancel)".to_string();
+    if let Some(range) = app.filters.dates {
+        title.push_str(&format!(" [now {}]", dates::describe_range(range)));
+    }
+    f.render_widget(List::new(items).block(Block::default().title(title).borders(Borders::ALL)), area);
+}
+
+fn draw_author_filter(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    if app.authors.is_empty() {
+        let paragraph = Paragraph::new("No authors found.")
+            .block(Block::default().title("Author Filter").borders(Borders::ALL));
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = app
+        .authors
+        .iter()
+        .enumerate()
+        .map(|(i, author)| {
+            let style = if i == app.author_selector_index {
+                theme.selected
+            } else if app.filters.author.as_ref() == Some(author) {
+                theme.current
+            } else {
+                Style::default()
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} <{}>", author.name, author.email), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().title("Filter by Author (↑/↓ to navigate, Enter to select, Esc to cancel)").borders(Borders::ALL));
+
+    f.render_widget(list, area);
+}
+
+/// Renders refs like `git log --decorate`: "(HEAD -> main, origin/main, tag: v1.0) "
+fn decoration_spans(labels: &[RefLabel]) -> Vec<Span<'static>> {
+    let theme = Theme::current();
+    let mut spans = vec![Span::styled("(", theme.tag)];
+    for (i, label) in labels.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(", ", theme.tag));
+        }
+        let (text, style) = match label.kind {
+            RefKind::Head => (label.name.clone(), theme.head.add_modifier(Modifier::BOLD)),
+            RefKind::LocalBranch => (label.name.clone(), theme.branch.add_modifier(Modifier::BOLD)),
+            RefKind::RemoteBranch => (label.name.clone(), theme.remote.add_modifier(Modifier::BOLD)),
+            RefKind::Tag => (format!("tag: {}", label.name), theme.tag.add_modifier(Modifier::BOLD)),
+        };
+        spans.push(Span::styled(text, style));
+    }
+    spans.push(Span::styled(") ", theme.tag));
+    spans
+}
+
+/// Two-letter initials of the commit's author on a colour picked from
+/// their email, so the same person always looks the same
+/// Cuts `text` down to `width` columns, ending it with an ellipsis if
+/// anything had to go
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+fn author_badge(commit: &CommitInfo) -> Span<'static> {
+    let theme = Theme::current();
+    // FNV-1a, which unlike std's hasher gives the same colour on every run
+    let hash = commit
+        .author_email
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+
+    let name = commit.author.split(" <").next().unwrap_or("");
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let initials: String = match words.as_slice() {
+        [] => "?".to_string(),
+        [word] => word.chars().take(2).collect(),
+        [first, .., last] => first.chars().take(1).chain(last.chars().take(1)).collect(),
+    };
+    Span::styled(
+        format!("{:<2}", initials.to_uppercase()),
+        theme.badge(hash as usize).add_modifier(Modifier::BOLD),
+    )
+}
+
+/// Where a commit stands in the bisect: the culprit, a marked good or
+/// bad commit, or the one to test next
+fn bisect_mark(bisect: &Bisect, hash: &str) -> Option<Span<'static>> {
+    let theme = Theme::current();
+    let is = |other: &Option<String>| other.as_deref() == Some(hash);
+    let (text, style) = if is(&bisect.culprit) {
+        ("first bad ", theme.error)
+    } else if is(&bisect.bad) {
+        ("bad ", theme.removed)
+    } else if bisect.good.iter().any(|good| good == hash) {
+        ("good ", theme.added)
+    } else if is(&bisect.probe) {
+        ("test? ", theme.warning)
+    } else {
+        return None;
+    };
+    Some(Span::styled(text, style.add_modifier(Modifier::BOLD)))
+}
+
+pub(crate) const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// A bordered pane, with the border highlighted when it has focus
+fn pane_block(title: String, focused: bool) -> Block<'static> {
+    let block = Block::default().title(title).borders(Borders::ALL);
+    if focused {
+        block.border_style(Theme::current().border)
+    } else {
+        block
+    }
+}
+
+/// A ref's name as shown, with a commit browsed from without a ref
+/// abbreviated to its short hash
+pub fn ref_label(name: &str) -> &str {
+    match git2::Oid::from_str(name) {
+        Ok(_) if name.len() == 40 => &name[..7],
+        _ => name,
+    }
+}
+
+fn draw_commit_list(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = Theme::current();
+    let mut title = match &app.detached_head {
+        Some(short_hash) if app.current_branch == "HEAD" => format!("Commits (detached HEAD @ {})", short_hash),
+        _ => format!("Commits ({})", app.branch_label()),
+    };
+    if let Some(path) = &app.file_history {
+        title.push_str(&format!(" [history of {}, f to leave]", path));
+    }
+    for value in filters::active_filters(app) {
+        title.push_str(&format!(" [{}]", value));
+    }
+    if let Some(kind) = &app.type_filter {
+        title.push_str(&format!(" [type: {}]", kind));
+    }
+    if let Some(pickaxe) = &app.pickaxe {
+        let progress = if pickaxe.done { String::new() } else { format!(", searching {}...", pickaxe.scanned) };
+        title.push_str(&format!(" [diffs with {}: {} found{}]", pickaxe.query, pickaxe.matches.len(), progress));
+    }
+    if let Some(bisect) = &app.bisect {
+        match (&bisect.culprit, &bisect.probe) {
+            (Some(culprit), _) => title.push_str(&format!(" [bisect: {} is the first bad commit]", &culprit[..7])),
+            (None, Some(_)) => title.push_str(&format!(" [bisect: {} left, g/b to mark]", bisect.remaining)),
+            (None, None) => title.push_str(" [bisect: g/b to mark good and bad]"),
+        }
+    }
+    if !app.shallow.is_empty() {
+        title.push_str(" [shallow clone, history is cut off, U to fetch the rest]");
+    }
+    if !app.marked.is_empty() {
+        title.push_str(&format!(" [{} marked, C to cherry-pick, + to diff]", app.marked.len()));
+    }
+    if app.loading {
+        let frame = SPINNER_FRAMES[app.spinner_frame % SPINNER_FRAMES.len()];
+        title.push_str(&format!(" {} loading...", frame));
+    }
+
+    if app.commits.is_empty() {
+        let message = if let Some(error) = &app.load_error {
+            format!("Failed to load commits: {}", error)
+        } else if app.loading {
+            "Loading commits...".to_string()
+        } else {
+            "No commits found in the repository.".to_string()
+        };
+        let empty_message = Paragraph::new(message)
+            .block(pane_block(title, app.focus == Focus::Commits));
+        f.render_widget(empty_message, area);
+        return;
+    }
+
+    // Only the rows on screen are built, so long histories stay cheap to draw
+    app.list_height = area.height.saturating_sub(2) as usize;
+    app.scroll_list_to_selection();
+    let end = (app.list_offset + app.list_height).min(app.visible.len());
+
+    let range = app.range_ends();
+    let row_width = area.width.saturating_sub(2) as usize;
+    let columns = app.shown_columns();
+    let items: Vec<ListItem> = app.visible[app.list_offset..end]
+        .iter()
+        .map(|&i| {
+            let commit = &app.commits[i];
+            let in_range = range.is_some_and(|(newest, oldest)| (newest..=oldest).contains(&i));
+            let style = if i == app.selected_index {
+                theme.selected
+            } else if in_range {
+                theme.selected_unfocused
+            } else {
+                Style::default()
+            };
+
+            // The graph only makes sense when every commit is shown
+            let mut spans = if app.is_filtering() {
+                Vec::new()
+            } else {
+                app.graph.get(i).map(graph::graph_spans).unwrap_or_default()
+            };
+            // While anything is marked every row gets a marker column
+            if app.marked.contains(&commit.hash) {
+                spans.push(Span::styled("✓ ", theme.current.add_modifier(Modifier::BOLD)));
+            } else if !app.marked.is_empty() {
+                spans.push(Span::raw("  "));
+            }
+            let parsed = conventional::parse(&commit.message);
+            let mut subject_at = None;
+            for (n, column) in columns.iter().enumerate() {
+                if n > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                match column {
+                    Column::Author => spans.push(author_badge(commit)),
+                    Column::Date => spans.push(Span::styled(dates::format(commit.time, app.date_format), style.patch(theme.date))),
+                    Column::Hash => spans.extend(search::highlight_matches(&commit.hash[..7], &app.search_query, app.search_pattern.as_ref(), style.patch(theme.hash))),
+                    Column::Subject => {
+                        if let Some(bisect) = &app.bisect {
+                            spans.extend(bisect_mark(bisect, &commit.hash));
+                        }
+                        if let Some(labels) = app.ref_map.get(&commit.hash) {
+                            spans.extend(decoration_spans(labels));
+                        }
+                        // A file's history names the path it had before it was renamed
+                        if let Some(path) = app.file_history.as_deref().and_then(|followed| history_path(commit, followed)) {
+                            spans.push(Span::styled(format!("{} ", path), theme.muted));
+                        }
+                        // Its parents were never fetched, so the history stops here
+                        if app.shallow.contains(&commit.hash) {
+                            spans.push(Span::styled("┄ shallow ", theme.warning));
+                        }
+                        // A Conventional Commit's prefix is shown as a badge instead
+                        if let Some(parsed) = &parsed {
+                            spans.extend(commit_types::type_badge(parsed));
+                        }
+                        subject_at = Some(spans.len());
+                    },
+                }
+            }
+
+            // The subject gets whatever room the other columns leave
+            if let Some(at) = subject_at {
+                let summary = commit.message.lines().next().unwrap_or("");
+                let subject = match parsed {
+                    Some(_) => summary.split_once(':').map(|(_, rest)| rest.trim_start()).unwrap_or(summary),
+                    None => summary,
+                };
+                let used: usize = spans.iter().map(|span| span.width()).sum();
+                let subject = truncate(subject, row_width.saturating_sub(used));
+                spans.splice(at..at, search::highlight_matches(&subject, &app.search_query, app.search_pattern.as_ref(), style));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(title, app.focus == Focus::Commits));
+
+    f.render_widget(list, area);
+
+    if app.visible.len() > app.list_height {
+        let position = app.visible.iter().position(|&i| i == app.selected_index).unwrap_or(0);
+        let mut state = ScrollbarState::new(app.visible.len())
+            .position(position)
+            .viewport_content_length(app.list_height);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None);
+        f.render_stateful_widget(scrollbar, area.inner(&Margin { vertical: 1, horizontal: 0 }), &mut state);
+    }
+}
+
+/// The path a commit in a file's history changed, when it isn't the one
+/// being followed: the file's old name, or the rename itself
+fn history_path(commit: &CommitInfo, followed: &str) -> Option<String> {
+    match commit.paths.as_slice() {
+        [path, old_path] => Some(format!("{} → {}", old_path, path)),
+        [path] if path != followed => Some(path.clone()),
+        _ => None,
+    }
+}
+
+fn draw_commit_details(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let title = format!("Details ({})", app.repo_root);
+    let block = pane_block(title, app.focus == Focus::Details);
+
+    if let Some(preview) = &app.patch_preview {
+        draw_patch_details(f, app, preview, block, area);
+        return;
+    }
+
+    let commit = match app.commits.get(app.selected_index) {
+        Some(commit) => commit,
+        None => {
+            f.render_widget(Paragraph::new("No commit selected.").block(block), area);
+            return;
+        }
+    };
+
+    if app.show_marked_diff {
+        draw_marked_details(f, app, block, area);
+        return;
+    }
+
+    if let Some((newest, oldest)) = app.range_ends() {
+        draw_range_details(f, app, newest, oldest, block, area);
+        return;
+    }
+
+    let now = chrono::Local::now().timestamp();
+    let mut lines = vec![
+        Line::from(vec![Span::raw("Hash: "), Span::styled(commit.hash.clone(), theme.hash)]),
+        Line::from(vec![Span::raw("Author: "), Span::styled(commit.author.clone(), theme.author)]),
+        Line::from(vec![
+            Span::raw("Date: "),
+            Span::styled(
+                format!("{} ({})", dates::exact(commit.time, app.date_format), dates::relative(commit.time, now)),
+                theme.date,
+            ),
+        ]),
+    ];
+    if commit.committer != commit.author || commit.commit_time != commit.time {
+        lines.push(Line::from(format!("Committer: {}", commit.committer)));
+        lines.push(Line::from(format!(
+            "Committed: {} ({})",
+            dates::exact(commit.commit_time, app.date_format),
+            dates::relative(commit.commit_time, now)
+        )));
+    }
+    if let Some(followed) = &app.file_history {
+        let path = history_path(commit, followed).unwrap_or_else(|| followed.clone());
+        lines.push(Line::from(format!("Path:     {}", path)));
+    }
+    // Each parent and child is shown with the key that jumps to it
+    let parent_keys = ["[", "{"];
+    for (i, parent) in commit.parents.iter().enumerate() {
+        let label = if i == 0 { "Parents:  " } else { "          " };
+        lines.push(commit_link(app, label, parent_keys.get(i).copied(), parent));
+    }
+    for (i, child) in app.children_of(&commit.hash).iter().enumerate() {
+        let label = if i == 0 { "Children: " } else { "          " };
+        lines.push(commit_link(app, label, if i == 0 { Some("]") } else { None }, &child.hash));
+    }
+    if commit.parents.len() > 1 {
+        let against = match app.merge_view {
+            MergeView::Parent(nth) if nth < commit.parents.len() => {
+                format!("parent {} of {} ({})", nth + 1, commit.parents.len(), &commit.parents[nth][..7])
+            },
+            MergeView::Parent(_) => format!("parent 1 of {} ({})", commit.parents.len(), &commit.parents[0][..7]),
+            MergeView::Combined => "combined, files that differ from every parent".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("Diff:     {}", against)),
+            Span::styled(" (M to switch)", theme.muted),
+        ]));
+    }
+    if let Some(stat) = app.shown_commit_stat() {
+        lines.push(Line::from(vec![
+            Span::raw(format!("Changed:  {} file{}, ", stat.files.len(), if stat.files.len() == 1 { "" } else { "s" })),
+            Span::styled(format!("+{}", stat.insertions), theme.added),
+            Span::raw(" "),
+            Span::styled(format!("-{}", stat.deletions), theme.removed),
+        ]));
+    }
+    if let Some(lookup) = app.pull_requests.get(&commit.hash) {
+        lines.push(pull_request_line(lookup));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("Message:"));
+    lines.extend(commit.message.lines().map(|l| Line::from(l.to_string())));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((app.details_scroll, 0));
+
+    f.render_widget(paragraph, area);
+}
+
+/// The pull request that brought the commit in, coloured by its state
+fn pull_request_line(lookup: &PullLookup) -> Line<'static> {
+    let theme = Theme::current();
+    let grey = theme.muted;
+    let (pull, note): (&PullRequest, &str) = match lookup {
+        PullLookup::Loading => return Line::from(Span::styled("PR:       looking it up...", grey)),
+        PullLookup::Found(None) => return Line::from(Span::styled("PR:       none", grey)),
+        PullLookup::Failed(e) => return Line::from(Span::styled(format!("PR:       unknown ({})", e), grey)),
+        PullLookup::Found(Some(pull)) => (pull, ""),
+        PullLookup::Cached(pull) => (pull, " (cached, GitHub is unreachable)"),
+    };
+    let state_style = match pull.state.as_str() {
+        "open" => theme.added,
+        "merged" => theme.path,
+        _ => theme.removed,
+    };
+    Line::from(vec![
+        Span::raw(format!("PR:       #{} {} ", pull.number, pull.title)),
+        Span::styled(format!("[{}]", pull.state), state_style),
+        Span::styled(note.to_string(), grey),
+    ])
+}
+
+/// A related commit in the details pane: its short hash and summary, if
+/// it has been loaded, after the key that selects it
+fn commit_link<'a>(app: &App, label: &'a str, key: Option<&'a str>, hash: &str) -> Line<'a> {
+    let theme = Theme::current();
+    let summary = app
+        .commits
+        .iter()
+        .find(|c| c.hash == hash)
+        .and_then(|c| c.message.lines().next())
+        .unwrap_or("")
+        .to_string();
+    Line::from(vec![
+        Span::raw(label),
+        Span::styled(format!("{:<2}", key.unwrap_or("")), theme.muted),
+        Span::styled(format!("{} ", &hash[..7]), theme.hash),
+        Span::raw(summary),
+    ])
+}
+
+/// Summary of a range selection in place of a single commit's details
+fn draw_range_details(f: &mut Frame, app: &App, newest: usize, oldest: usize, block: Block, area: Rect) {
+    let theme = Theme::current();
+    let count = oldest - newest + 1;
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("Range of {} commit{} (V to end it)", count, if count == 1 { "" } else { "s" }),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("From: {} (oldest)", &app.commits[oldest].hash[..7])),
+        Line::from(format!("To:   {} (newest)", &app.commits[newest].hash[..7])),
+        Line::from(""),
+    ];
+    lines.extend(app.commits.range(newest..=oldest).map(|commit| {
+        Line::from(vec![
+            Span::styled(format!("{} ", &commit.hash[..7]), theme.hash),
+            Span::raw(commit.message.lines().next().unwrap_or("").to_string()),
+        ])
+    }));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((app.details_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_marked_details(f: &mut Frame, app: &App, block: Block, area: Rect) {
+    let theme = Theme::current();
+    let marked = app.marked_commits();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} marked commit{} applied in turn (+ to leave)", marked.len(), if marked.len() == 1 { "" } else { "s" }),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(marked.iter().map(|commit| {
+        Line::from(vec![
+            Span::styled(format!("{} ", &commit.hash[..7]), theme.hash),
+            Span::raw(commit.message.lines().next().unwrap_or("").to_string()),
+        ])
+    }));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((app.details_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_patch_details(f: &mut Frame, app: &App, preview: &PatchPreview, block: Block, area: Rect) {
+    let theme = Theme::current();
+    let count = preview.patches.len();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("Patch file {}", preview.path),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{} patch{} changing {} file{}",
+            count,
+            if count == 1 { "" } else { "es" },
+            preview.info.files.len(),
+            if preview.info.files.len() == 1 { "" } else { "s" },
+        )),
+        Line::from(""),
+    ];
+    lines.extend(preview.patches.iter().enumerate().map(|(i, (subject, _))| {
+        Line::from(vec![
+            Span::styled(format!("{:>3}. ", i + 1), theme.hash),
+            Span::raw(subject.clone().unwrap_or_else(|| "(no subject)".to_string())),
+        ])
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Enter applies to the working tree, i to the index, Esc cancels",
+        theme.muted,
+    )));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((app.details_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_file_list(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let diff = app.shown_diff_info();
+    let title = match diff {
+        Some(diff) => format!("Changed Files ({}, ←/→ to pick one)", diff.files.len()),
+        None => "Changed Files".to_string(),
+    };
+    let block = pane_block(title, app.focus == Focus::Files);
+
+    let diff = match diff {
+        Some(diff) if !diff.files.is_empty() => diff,
+        Some(_) => {
+            f.render_widget(Paragraph::new("No files changed").block(block), area);
+            return;
+        },
+        None => {
+            f.render_widget(Paragraph::new("No diff available").block(block), area);
+            return;
+        },
+    };
+
+    // A commit's own diff has exact counts in its diffstat, in the same order
+    let stat = app.shown_commit_stat().filter(|stat| stat.files.len() == diff.files.len());
+    let lines: Vec<Line> = diff.files.iter().enumerate().map(|(i, file)| {
+        // Format the status based on change type
+        let mut status = match file.status {
+            ChangeStatus::Added => "added".to_string(),
+            ChangeStatus::Deleted => "deleted".to_string(),
+            ChangeStatus::Modified => "modified".to_string(),
+            ChangeStatus::TypeChanged => "type changed".to_string(),
+            ChangeStatus::Renamed => format!("renamed {} →", file.old_path.as_deref().unwrap_or("")),
+            ChangeStatus::Copied => format!("copied {} →", file.old_path.as_deref().unwrap_or("")),
+        };
+        if file.binary {
+            status.push_str(" (binary)");
+        }
+
+        let style = if app.selected_file == Some(i) {
+            theme.selected
+        } else {
+            Style::default()
+        };
+        let (added, removed) = match stat {
+            Some(stat) => (stat.files[i].insertions, stat.files[i].deletions),
+            None => file.line_stats(),
+        };
+        Line::from(Span::styled(
+            format!("{:<30} {} +{} -{}", status, file.path(), added, removed),
+            style,
+        ))
+    }).collect();
+
+    // Keep the selected file in view when the list is taller than the pane
+    let height = area.height.saturating_sub(2) as usize;
+    let scroll = app.selected_file.map(|i| (i + 1).saturating_sub(height)).unwrap_or(0);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+
+    f.render_widget(paragraph, area);
+}
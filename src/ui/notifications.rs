@@ -0,0 +1,78 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use chrono::{DateTime, Local};
+use std::time::Instant;
+use super::{App, Theme};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn style(self) -> Style {
+        let theme = Theme::current();
+        match self {
+            Level::Info => theme.accent,
+            Level::Warning => theme.warning,
+            Level::Error => theme.error,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    pub time: DateTime<Local>,
+    // When it reached the status bar, None while it waits its turn
+    pub shown_at: Option<Instant>,
+}
+
+impl Notification {
+    pub fn new(level: Level, message: String) -> Self {
+        Notification { level, message, time: Local::now(), shown_at: None }
+    }
+}
+
+/// Every message raised this session, newest first
+pub fn draw_message_log(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title("Messages (Esc to close)")
+        .borders(Borders::ALL);
+
+    if app.message_log.is_empty() {
+        f.render_widget(Paragraph::new("Nothing to report.").block(block), area);
+        return;
+    }
+
+    let lines: Vec<Line> = app
+        .message_log
+        .iter()
+        .rev()
+        .map(|notification| {
+            Line::from(vec![
+                Span::styled(notification.time.format("%H:%M:%S ").to_string(), Theme::current().muted),
+                Span::styled(format!("{:<8}", notification.level.label()), notification.level.style()),
+                Span::raw(notification.message.clone()),
+            ])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
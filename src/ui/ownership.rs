@@ -0,0 +1,205 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::collections::{HashMap, HashSet};
+use crate::models::RepoStats;
+use super::Theme;
+
+// An area whose top author made at least this share of its commits
+// depends on them alone
+const SINGLE_OWNER_SHARE: f64 = 0.8;
+
+/// Who made the commits under one directory
+#[derive(Default)]
+struct DirOwners {
+    // Subdirectories, busiest first
+    children: Vec<String>,
+    // Author names with their commit counts, most active first
+    authors: Vec<(String, usize)>,
+    commits: usize,
+}
+
+impl DirOwners {
+    /// The fewest authors who between them made over half the commits
+    fn bus_factor(&self) -> usize {
+        let mut covered = 0;
+        for (i, (_, commits)) in self.authors.iter().enumerate() {
+            covered += commits;
+            if covered * 2 > self.commits {
+                return i + 1;
+            }
+        }
+        self.authors.len()
+    }
+
+    fn top_share(&self) -> f64 {
+        match self.authors.first() {
+            Some((_, commits)) => *commits as f64 / self.commits.max(1) as f64,
+            None => 0.0,
+        }
+    }
+}
+
+/// Directories the history touched and who made their commits, browsed
+/// one level at a time
+#[derive(Default)]
+pub struct Ownership {
+    dirs: HashMap<String, DirOwners>,
+    // The directory being looked at, "" for the root
+    pub path: String,
+    pub cursor: usize,
+}
+
+fn parent_dir(path: &str) -> Option<&str> {
+    match path.rfind('/') {
+        Some(slash) => Some(&path[..slash]),
+        None if path.is_empty() => None,
+        None => Some(""),
+    }
+}
+
+impl Ownership {
+    pub fn new(stats: &RepoStats) -> Self {
+        // Commits by author for every directory above each changed file
+        let mut commits: HashMap<String, HashMap<usize, HashSet<usize>>> = HashMap::new();
+        for touch in &stats.touches {
+            let mut dir = parent_dir(&stats.paths[touch.file]);
+            while let Some(path) = dir {
+                commits.entry(path.to_string()).or_default().entry(touch.author).or_default().insert(touch.commit);
+                dir = parent_dir(path);
+            }
+        }
+
+        let mut dirs: HashMap<String, DirOwners> = commits
+            .iter()
+            .map(|(path, by_author)| {
+                let mut authors: Vec<(String, usize)> = by_author
+                    .iter()
+                    .map(|(author, commits)| (stats.author_names[*author].clone(), commits.len()))
+                    .collect();
+                authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                let commits = by_author.values().flatten().collect::<HashSet<_>>().len();
+                (path.clone(), DirOwners { children: Vec::new(), authors, commits })
+            })
+            .collect();
+
+        let mut children: Vec<(String, usize)> = dirs
+            .iter()
+            .filter(|(path, _)| !path.is_empty())
+            .map(|(path, owners)| (path.clone(), owners.commits))
+            .collect();
+        children.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (child, _) in children {
+            if let Some(parent) = parent_dir(&child).and_then(|parent| dirs.get_mut(parent)) {
+                parent.children.push(child);
+            }
+        }
+
+        Ownership { dirs, path: String::new(), cursor: 0 }
+    }
+
+    fn current(&self) -> Option<&DirOwners> {
+        self.dirs.get(&self.path)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.current().map(|dir| dir.children.len()).unwrap_or(0).saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    /// Opens the subdirectory under the cursor
+    pub fn enter(&mut self) {
+        if let Some(child) = self.current().and_then(|dir| dir.children.get(self.cursor)).cloned() {
+            self.path = child;
+            self.cursor = 0;
+        }
+    }
+
+    /// Goes back up to the parent directory, with the cursor on the one just left
+    pub fn leave(&mut self) {
+        let parent = match parent_dir(&self.path) {
+            Some(parent) => parent.to_string(),
+            None => return,
+        };
+        let left = std::mem::replace(&mut self.path, parent);
+        self.cursor = self
+            .current()
+            .and_then(|dir| dir.children.iter().position(|child| *child == left))
+            .unwrap_or(0);
+    }
+}
+
+fn share_style(share: f64) -> Style {
+    if share >= SINGLE_OWNER_SHARE {
+        Theme::current().warning.add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    }
+}
+
+pub fn draw_ownership(f: &mut Frame, ownership: &Ownership, area: Rect) {
+    let name = if ownership.path.is_empty() { "/" } else { ownership.path.as_str() };
+    let dir = match ownership.current() {
+        Some(dir) => dir,
+        None => {
+            let block = Block::default().title("Ownership").borders(Borders::ALL);
+            f.render_widget(Paragraph::new("No files were changed").block(block), area);
+            return;
+        },
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(area);
+    let theme = Theme::current();
+
+    // Subdirectories with their main author and bus factor
+    let height = chunks[0].height.saturating_sub(3) as usize;
+    let offset = (ownership.cursor + 1).saturating_sub(height);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:>7} {:>4}  {:<30} {}", "commits", "bus", "top author", "directory"),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(dir.children.iter().enumerate().skip(offset).take(height).map(|(i, child)| {
+        let owners = &ownership.dirs[child];
+        let top = owners.authors.first().map(|(name, _)| name.as_str()).unwrap_or("");
+        let top: String = top.chars().take(22).collect();
+        let style = if i == ownership.cursor {
+            theme.selected
+        } else {
+            Style::default()
+        };
+        let child_name = child.rsplit('/').next().unwrap_or(child);
+        Line::from(vec![
+            Span::styled(format!("{:>7} ", owners.commits), theme.modified),
+            Span::styled(format!("{:>4}  ", owners.bus_factor()), share_style(owners.top_share())),
+            Span::styled(format!("{:<22} {:>5.0}%  ", top, owners.top_share() * 100.0), share_style(owners.top_share())),
+            Span::styled(format!("{}/", child_name), style),
+        ])
+    }));
+    if dir.children.is_empty() {
+        lines.push(Line::from(Span::styled("No subdirectories", theme.muted)));
+    }
+    let title = format!("Ownership of {} (Enter/→ to open a directory, ← to go up)", name);
+    f.render_widget(Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL)), chunks[0]);
+
+    // Every author of the directory being looked at
+    let mut lines = vec![
+        Line::from(format!("{} commits, bus factor {}", dir.commits, dir.bus_factor())),
+        Line::from(""),
+    ];
+    lines.extend(dir.authors.iter().map(|(author, commits)| {
+        let share = *commits as f64 / dir.commits.max(1) as f64;
+        Line::from(vec![
+            Span::styled(format!("{:>5.1}% ", share * 100.0), share_style(share)),
+            Span::raw(format!("{} ({})", author, commits)),
+        ])
+    }));
+    let title = format!("Authors of {}", name);
+    f.render_widget(Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL)), chunks[1]);
+}
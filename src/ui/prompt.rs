@@ -0,0 +1,87 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use super::confirm::centered_rect;
+use super::{DialogEvent, Theme};
+
+/// What to do with the text once it's entered
+pub enum PromptAction {
+    // Create a branch at the given commit
+    CreateBranch(String),
+    // Rename the given branch
+    RenameBranch(String),
+    // Name a tag for the given commit, then ask for its message
+    TagName(String),
+    // Create the named tag at the commit; an empty message makes it lightweight
+    TagMessage { name: String, hash: String },
+    // Fetch from the named remote
+    Fetch,
+    // Compare the two refs given as `base...target`
+    Compare,
+    // Write the commits being shown to the given file
+    Export,
+    // List the commits between two refs given as `from..to`, then ask where to write them
+    Changelog,
+    // Only show commits from the range given as `since..until`
+    DateRange,
+    // Only show commits touching these pathspecs; empty clears the filter
+    PathFilter,
+    // Write the marked or selected commits as patch files to this directory
+    FormatPatch,
+    // Read the .patch or mbox file at this path and preview it for applying
+    ApplyPatch,
+    // Only show commits whose message matches this regex; empty clears it
+    MessageFilter,
+    // Find the commits whose diffs add or remove the given text or /regex/
+    Pickaxe,
+    // Search the files at the given commit for the text or /regex/
+    Grep(String),
+    // Search the file being viewed for the text or /regex/
+    FileSearch,
+    // Write the changelog of `from..to` to the given file, or copy it for `-`
+    ChangelogOut { from: String, to: String },
+}
+
+/// A one-line text box asking for a name
+pub struct Prompt {
+    pub title: String,
+    pub input: String,
+    pub action: PromptAction,
+}
+
+impl Prompt {
+    pub fn new(title: String, input: String, action: PromptAction) -> Self {
+        Prompt { title, input, action }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> DialogEvent {
+        match key.code {
+            KeyCode::Enter => return DialogEvent::Submit,
+            KeyCode::Esc => return DialogEvent::Cancel,
+            KeyCode::Backspace => {
+                self.input.pop();
+            },
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {},
+        }
+        DialogEvent::Nothing
+    }
+}
+
+pub fn draw_prompt(f: &mut Frame, prompt: &Prompt, area: Rect) {
+    let area = centered_rect(80, 3, area);
+    let theme = Theme::current();
+    let line = Line::from(vec![
+        Span::raw(prompt.input.clone()),
+        Span::styled(" ", theme.cursor),
+    ]);
+    let title = format!("{} (Enter to confirm, Esc to cancel)", prompt.title);
+    let paragraph = Paragraph::new(line)
+        .block(Block::default().title(title).borders(Borders::ALL).border_style(theme.border));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
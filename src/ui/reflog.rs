@@ -0,0 +1,67 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::models::ReflogEntry;
+use super::Theme;
+
+/// Where HEAD and the local branches have pointed over time
+pub struct ReflogView {
+    pub entries: Vec<ReflogEntry>,
+    pub cursor: usize,
+}
+
+impl ReflogView {
+    pub fn new(entries: Vec<ReflogEntry>) -> Self {
+        ReflogView { entries, cursor: 0 }
+    }
+
+    pub fn selected(&self) -> Option<&ReflogEntry> {
+        self.entries.get(self.cursor)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.entries.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+pub fn draw_reflog_view(f: &mut Frame, reflog: &ReflogView, area: Rect) {
+    // Line up the messages after the widest `ref@{n}`
+    let selector = |entry: &ReflogEntry| format!("{}@{{{}}}", entry.ref_name, entry.index);
+    let width = reflog.entries.iter().map(|e| selector(e).chars().count()).max().unwrap_or(0);
+    let theme = Theme::current();
+
+    let height = area.height.saturating_sub(2) as usize;
+    let offset = (reflog.cursor + 1).saturating_sub(height);
+    let mut rows: Vec<Line> = reflog
+        .entries
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, entry)| {
+            let style = if i == reflog.cursor {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<width$} ", selector(entry), width = width), theme.head),
+                Span::styled(format!("{} ", &entry.hash[..7.min(entry.hash.len())]), theme.hash),
+                Span::styled(format!("{} ", entry.date), theme.date),
+                Span::styled(entry.message.clone(), style),
+            ])
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(Line::from("The reflog is empty."));
+    }
+
+    let list = Paragraph::new(rows)
+        .block(Block::default().title("Reflog (Enter to browse history from an entry, Esc to close)").borders(Borders::ALL));
+    f.render_widget(list, area);
+}
@@ -0,0 +1,94 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use regex::{Regex, RegexBuilder};
+use crate::models::CommitInfo;
+use super::{App, Theme};
+
+/// The search typed as a regex, which like plain text ignores case unless
+/// it says otherwise with `(?-i)`
+pub fn compile(query: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(query).case_insensitive(true).build()
+}
+
+/// Case-insensitive match against the hash, message and author of a
+/// commit, by the regex when searching with one
+pub fn commit_matches(commit: &CommitInfo, query: &str, pattern: Option<&Regex>) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    if let Some(pattern) = pattern {
+        return commit.search_fields().iter().any(|field| pattern.is_match(field));
+    }
+    let query = query.to_ascii_lowercase();
+    commit.search_fields().iter().any(|field| field.to_ascii_lowercase().contains(&query))
+}
+
+/// Splits `text` into spans, highlighting every occurrence of `query`, or
+/// of the regex when searching with one
+pub fn highlight_matches(text: &str, query: &str, pattern: Option<&Regex>, style: Style) -> Vec<Span<'static>> {
+    if query.is_empty() {
+        return vec![Span::styled(text.to_string(), style)];
+    }
+
+    // ASCII lowercasing keeps byte offsets identical between the two strings
+    let haystack = text.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    let found: Vec<(usize, usize)> = match pattern {
+        Some(pattern) => pattern.find_iter(text).filter(|found| !found.is_empty()).map(|found| (found.start(), found.end())).collect(),
+        None => haystack.match_indices(&needle).map(|(start, _)| (start, start + needle.len())).collect(),
+    };
+    let theme = Theme::current();
+    let match_style = style.patch(theme.search_match);
+
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, end) in found {
+        if start < last {
+            continue;
+        }
+        if start > last {
+            spans.push(Span::styled(text[last..start].to_string(), style));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), match_style));
+        last = end;
+    }
+    if last < text.len() {
+        spans.push(Span::styled(text[last..].to_string(), style));
+    }
+    spans
+}
+
+pub fn draw_search_box(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let mut spans = vec![
+        Span::styled(if app.search_regex { "regex /" } else { "/" }, theme.border),
+        Span::raw(app.search_query.clone()),
+    ];
+    if app.search_mode {
+        spans.push(Span::styled(" ", theme.cursor));
+    }
+
+    // A regex is also looked for through the whole history, not only
+    // what's loaded
+    let counts = match (&app.search_error, &app.search_results) {
+        (Some(error), _) => error.clone(),
+        (None, Some(results)) if results.done => format!("{} matches in {} commits searched", results.matches.len(), results.scanned),
+        (None, Some(results)) => format!("{} matches so far, searching {}...", results.matches.len(), results.scanned),
+        (None, None) => format!("{} matches", app.search_matches().count()),
+    };
+    let title = if app.search_mode {
+        format!("Search, {} (Enter to confirm, Esc to cancel, Ctrl-r for {})", counts, if app.search_regex { "text" } else { "a regex" })
+    } else {
+        format!("Search ({}, n/N to jump)", counts)
+    };
+
+    let paragraph = Paragraph::new(Line::from(spans))
+        .block(Block::default().title(title).borders(Borders::ALL));
+
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,81 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::models::StashInfo;
+use super::Theme;
+
+/// The stash list with a preview of the selected stash's diff
+pub struct StashView {
+    pub stashes: Vec<StashInfo>,
+    pub cursor: usize,
+    pub diff_lines: Vec<Line<'static>>,
+    pub diff_scroll: usize,
+}
+
+impl StashView {
+    pub fn new(stashes: Vec<StashInfo>) -> Self {
+        StashView { stashes, cursor: 0, diff_lines: Vec::new(), diff_scroll: 0 }
+    }
+
+    pub fn selected(&self) -> Option<&StashInfo> {
+        self.stashes.get(self.cursor)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.stashes.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    pub fn scroll_diff(&mut self, delta: i32) {
+        let max = self.diff_lines.len().saturating_sub(1) as i64;
+        self.diff_scroll = (self.diff_scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+pub fn draw_stash_view(f: &mut Frame, stash: &StashView, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+    let theme = Theme::current();
+
+    let mut rows: Vec<Line> = stash
+        .stashes
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let style = if i == stash.cursor {
+                theme.selected
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("stash@{{{}}} ", entry.index), theme.hash),
+                Span::styled(entry.message.clone(), style),
+            ])
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(Line::from("No stashes."));
+    }
+
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let offset = (stash.cursor + 1).saturating_sub(height);
+    let list = Paragraph::new(rows)
+        .scroll((offset as u16, 0))
+        .block(Block::default().title("Stashes (Enter to apply, p to pop, x to drop, Esc to close)").borders(Borders::ALL));
+    f.render_widget(list, chunks[0]);
+
+    let title = match stash.selected() {
+        Some(entry) => format!("stash@{{{}}}: {}", entry.index, entry.message),
+        None => "Diff".to_string(),
+    };
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = stash.diff_lines.iter().skip(stash.diff_scroll).take(height).cloned().collect();
+    let diff = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(diff, chunks[1]);
+}
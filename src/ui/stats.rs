@@ -0,0 +1,432 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+use crate::models::RepoStats;
+use chrono::Local;
+use super::dates::day;
+use super::{ref_label, Theme};
+use super::ownership::{self, Ownership};
+
+const DAY: i64 = 24 * 3600;
+const WEEK: i64 = 7 * DAY;
+// Spans the hot files report can cover, counted back from now
+const HOT_WINDOWS: [(Option<i64>, &str); 4] = [
+    (Some(30 * DAY), "the last 30 days"),
+    (Some(90 * DAY), "the last 90 days"),
+    (Some(365 * DAY), "the last year"),
+    (None, "all time"),
+];
+// Zooming in never shows fewer weeks than this
+const MIN_WINDOW: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPage {
+    Overview,
+    // Insertions and deletions per week
+    Churn,
+    // The most changed files over a window of time
+    HotFiles,
+    // Who made the commits in each directory
+    Ownership,
+}
+
+/// A file's changes within the hot files window
+pub struct HotFile {
+    pub path: String,
+    pub commits: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Analytics over the whole history of a branch, filled in once the
+/// stats worker has finished with it
+pub struct StatsView {
+    pub branch: String,
+    pub stats: Option<RepoStats>,
+    pub error: Option<String>,
+    pub files_scroll: usize,
+    pub page: StatsPage,
+    // First and last week shown on the churn chart, all of them if unset
+    window: Option<(usize, usize)>,
+    // Index into HOT_WINDOWS
+    hot_window: usize,
+    pub hot_files: Vec<HotFile>,
+    pub hot_cursor: usize,
+    pub ownership: Ownership,
+}
+
+impl StatsView {
+    pub fn new(branch: String) -> Self {
+        StatsView {
+            branch,
+            stats: None,
+            error: None,
+            files_scroll: 0,
+            page: StatsPage::Overview,
+            window: None,
+            hot_window: 1,
+            hot_files: Vec::new(),
+            hot_cursor: 0,
+            ownership: Ownership::default(),
+        }
+    }
+
+    pub fn set_stats(&mut self, stats: RepoStats) {
+        self.ownership = Ownership::new(&stats);
+        self.stats = Some(stats);
+        self.refresh_hot_files();
+    }
+
+    pub fn switch_page(&mut self) {
+        self.page = match self.page {
+            StatsPage::Overview => StatsPage::Churn,
+            StatsPage::Churn => StatsPage::HotFiles,
+            StatsPage::HotFiles => StatsPage::Ownership,
+            StatsPage::Ownership => StatsPage::Overview,
+        };
+    }
+
+    /// Steps to a longer or shorter window for the hot files report
+    pub fn change_hot_window(&mut self, longer: bool) {
+        self.hot_window = if longer {
+            (self.hot_window + 1).min(HOT_WINDOWS.len() - 1)
+        } else {
+            self.hot_window.saturating_sub(1)
+        };
+        self.refresh_hot_files();
+    }
+
+    fn refresh_hot_files(&mut self) {
+        let stats = match &self.stats {
+            Some(stats) => stats,
+            None => return,
+        };
+        let since = HOT_WINDOWS[self.hot_window].0.map(|span| Local::now().timestamp() - span);
+
+        let mut files: HashMap<usize, HotFile> = HashMap::new();
+        for touch in &stats.touches {
+            if since.is_some_and(|since| touch.time < since) {
+                continue;
+            }
+            let file = files.entry(touch.file).or_insert_with(|| HotFile {
+                path: stats.paths[touch.file].clone(),
+                commits: 0,
+                insertions: 0,
+                deletions: 0,
+            });
+            file.commits += 1;
+            file.insertions += touch.insertions;
+            file.deletions += touch.deletions;
+        }
+        self.hot_files = files.into_values().collect();
+        self.hot_files.sort_by(|a, b| {
+            b.commits
+                .cmp(&a.commits)
+                .then_with(|| (b.insertions + b.deletions).cmp(&(a.insertions + a.deletions)))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        self.hot_cursor = 0;
+    }
+
+    pub fn move_hot_cursor(&mut self, delta: i32) {
+        let max = self.hot_files.len().saturating_sub(1) as i64;
+        self.hot_cursor = (self.hot_cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    pub fn selected_hot_file(&self) -> Option<&str> {
+        self.hot_files.get(self.hot_cursor).map(|file| file.path.as_str())
+    }
+
+    fn week_count(&self) -> usize {
+        self.stats.as_ref().map(|s| s.weeks.len()).unwrap_or(0)
+    }
+
+    /// The weeks on the churn chart, as indices into `stats.weeks`
+    pub fn window(&self) -> (usize, usize) {
+        self.window.unwrap_or((0, self.week_count().saturating_sub(1)))
+    }
+
+    /// Halves the weeks shown around the middle of the window, or doubles them
+    pub fn zoom(&mut self, zoom_in: bool) {
+        let count = self.week_count();
+        if count == 0 {
+            return;
+        }
+        let (from, to) = self.window();
+        let width = to - from + 1;
+        let width = if zoom_in { (width / 2).max(MIN_WINDOW.min(count)) } else { (width * 2).min(count) };
+        let middle = (from + to) / 2;
+        let from = middle.saturating_sub(width / 2).min(count - width);
+        self.window = Some((from, from + width - 1));
+    }
+
+    /// Moves the window by a quarter of its width
+    pub fn pan(&mut self, forward: bool) {
+        let count = self.week_count();
+        if count == 0 {
+            return;
+        }
+        let (from, to) = self.window();
+        let width = to - from + 1;
+        let step = (width / 4).max(1);
+        let from = if forward { (from + step).min(count - width) } else { from.saturating_sub(step) };
+        self.window = Some((from, from + width - 1));
+    }
+
+    /// The time span the churn chart shows, from the start of its first
+    /// week to the end of its last
+    pub fn window_times(&self) -> Option<(i64, i64)> {
+        let stats = self.stats.as_ref()?;
+        let (from, to) = self.window();
+        Some((stats.weeks.get(from)?.start, stats.weeks.get(to)?.start + WEEK - 1))
+    }
+
+    pub fn scroll_files(&mut self, delta: i32) {
+        let max = self.stats.as_ref().map(|s| s.files.len()).unwrap_or(0).saturating_sub(1) as i64;
+        self.files_scroll = (self.files_scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+/// `value` as a bar of block characters, `width` long at `max`
+fn text_bar(value: usize, max: usize, width: usize) -> String {
+    "█".repeat((value * width).div_ceil(max.max(1)))
+}
+
+fn draw_summary(f: &mut Frame, stats: &RepoStats, area: Rect) {
+    let theme = Theme::current();
+    let average = |total: usize| total as f64 / stats.diffed_commits.max(1) as f64;
+    let mut lines = vec![
+        Line::from(format!("Commits: {}", stats.commits)),
+        Line::from(format!("Authors: {}", stats.authors.len())),
+    ];
+    if let (Some(first), Some(last)) = (stats.weeks.first(), stats.weeks.last()) {
+        let weeks = stats.weeks.len();
+        lines.push(Line::from(format!(
+            "Active: {} to {} ({} week{})",
+            day(first.start),
+            day(last.start),
+            weeks,
+            if weeks == 1 { "" } else { "s" }
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("Average commit, merges aside:"));
+    lines.push(Line::from(vec![
+        Span::styled(format!("  +{:.1}", average(stats.insertions)), theme.added),
+        Span::styled(format!(" -{:.1}", average(stats.deletions)), theme.removed),
+        Span::raw(format!(" in {:.1} files", average(stats.files_changed))),
+    ]));
+
+    let paragraph = Paragraph::new(lines).block(Block::default().title("Summary").borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_authors(f: &mut Frame, stats: &RepoStats, area: Rect) {
+    let theme = Theme::current();
+    let height = area.height.saturating_sub(2) as usize;
+    let name_width = stats.authors.iter().take(height).map(|(name, _)| name.chars().count()).max().unwrap_or(0).min(24);
+    let max = stats.authors.first().map(|(_, count)| *count).unwrap_or(0);
+    let bar_width = (area.width as usize).saturating_sub(name_width + 12);
+
+    let lines: Vec<Line> = stats
+        .authors
+        .iter()
+        .take(height)
+        .map(|(name, count)| {
+            let name: String = name.chars().take(name_width).collect();
+            Line::from(vec![
+                Span::raw(format!("{:<width$} ", name, width = name_width)),
+                Span::styled(text_bar(*count, max, bar_width), theme.accent),
+                Span::raw(format!(" {}", count)),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(Block::default().title("Commits per author").borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_weeks(f: &mut Frame, stats: &RepoStats, area: Rect) {
+    let theme = Theme::current();
+    // One column per week, so only the latest weeks that fit are drawn
+    let fits = area.width.saturating_sub(2) as usize;
+    let weeks = &stats.weeks[stats.weeks.len().saturating_sub(fits)..];
+    let bars: Vec<Bar> = weeks
+        .iter()
+        .map(|week| Bar::default().value(week.commits as u64).text_value(String::new()))
+        .collect();
+    let peak = weeks.iter().map(|week| week.commits).max().unwrap_or(0);
+    let title = match (weeks.first(), weeks.last()) {
+        (Some(first), Some(last)) => format!("Commits per week, {} to {} (busiest: {})", day(first.start), day(last.start), peak),
+        _ => "Commits per week".to_string(),
+    };
+
+    let chart = BarChart::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(theme.added)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+fn draw_files(f: &mut Frame, view: &StatsView, stats: &RepoStats, area: Rect) {
+    let theme = Theme::current();
+    let count_width = stats.files.first().map(|(_, count)| count.to_string().len()).unwrap_or(1);
+    let lines: Vec<Line> = stats
+        .files
+        .iter()
+        .skip(view.files_scroll)
+        .map(|(path, count)| {
+            Line::from(vec![
+                Span::styled(format!("{:>width$} ", count, width = count_width), theme.modified),
+                Span::raw(path.clone()),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title("Busiest files (commits touching each, ↑/↓ to scroll)").borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_churn(f: &mut Frame, view: &StatsView, stats: &RepoStats, area: Rect) {
+    let theme = Theme::current();
+    let (from, to) = view.window();
+    let weeks = match stats.weeks.get(from..=to) {
+        Some(weeks) if !weeks.is_empty() => weeks,
+        _ => return,
+    };
+    let insertions: Vec<(f64, f64)> = weeks.iter().enumerate().map(|(i, w)| (i as f64, w.insertions as f64)).collect();
+    let deletions: Vec<(f64, f64)> = weeks.iter().enumerate().map(|(i, w)| (i as f64, -(w.deletions as f64))).collect();
+    let most_added = weeks.iter().map(|w| w.insertions).max().unwrap_or(0).max(1);
+    let most_deleted = weeks.iter().map(|w| w.deletions).max().unwrap_or(0).max(1);
+
+    let datasets = vec![
+        Dataset::default()
+            .name("insertions")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(theme.added)
+            .data(&insertions),
+        Dataset::default()
+            .name("deletions")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(theme.removed)
+            .data(&deletions),
+    ];
+    let first = day(weeks[0].start);
+    let last = day(weeks[weeks.len() - 1].start);
+    let title = format!(
+        "Code frequency, weeks of {} to {} (←/→ to move, ↑/↓ to zoom, Enter to show these commits)",
+        first, last
+    );
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, (weeks.len() - 1).max(1) as f64])
+                .labels(vec![Span::raw(first), Span::raw(last)]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([-(most_deleted as f64), most_added as f64])
+                // Labels are spread evenly, so a middle one wouldn't sit at zero
+                .labels(vec![
+                    Span::styled(format!("-{}", most_deleted), theme.removed),
+                    Span::styled(format!("+{}", most_added), theme.added),
+                ]),
+        );
+    f.render_widget(chart, area);
+}
+
+fn draw_hot_files(f: &mut Frame, view: &StatsView, area: Rect) {
+    let theme = Theme::current();
+    let title = format!(
+        "Hot files over {} (←/→ for a shorter or longer span, Enter for a file's history)",
+        HOT_WINDOWS[view.hot_window].1
+    );
+    let block = Block::default().title(title).borders(Borders::ALL);
+    if view.hot_files.is_empty() {
+        f.render_widget(Paragraph::new("Nothing changed in this span").block(block), area);
+        return;
+    }
+
+    let height = area.height.saturating_sub(3) as usize;
+    let offset = (view.hot_cursor + 1).saturating_sub(height);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:>7} {:>9} {:>9}  {}", "commits", "added", "deleted", "path"),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(view.hot_files.iter().enumerate().skip(offset).take(height).map(|(i, file)| {
+        let style = if i == view.hot_cursor {
+            theme.selected
+        } else {
+            Style::default()
+        };
+        Line::from(vec![
+            Span::styled(format!("{:>7} ", file.commits), theme.modified),
+            Span::styled(format!("{:>9} ", format!("+{}", file.insertions)), theme.added),
+            Span::styled(format!("{:>9}  ", format!("-{}", file.deletions)), theme.removed),
+            Span::styled(file.path.clone(), style),
+        ])
+    }));
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+pub fn draw_stats_view(f: &mut Frame, view: &StatsView, spinner: &str, area: Rect) {
+    let theme = Theme::current();
+    let title = format!("Statistics for {} (Tab for {}, Esc to close)", ref_label(&view.branch), match view.page {
+        StatsPage::Overview => "code frequency",
+        StatsPage::Churn => "hot files",
+        StatsPage::HotFiles => "ownership",
+        StatsPage::Ownership => "the overview",
+    });
+    let block = Block::default()
+        .title(Span::styled(title, Style::default().add_modifier(Modifier::BOLD)))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let stats = match (&view.stats, &view.error) {
+        (Some(stats), _) => stats,
+        (None, Some(error)) => {
+            let text = Paragraph::new(format!("Failed to compute statistics: {}", error)).style(theme.error);
+            f.render_widget(text, inner);
+            return;
+        },
+        (None, None) => {
+            f.render_widget(Paragraph::new(format!("{} Going through the history...", spinner)), inner);
+            return;
+        },
+    };
+
+    match view.page {
+        StatsPage::Churn => return draw_churn(f, view, stats, inner),
+        StatsPage::HotFiles => return draw_hot_files(f, view, inner),
+        StatsPage::Ownership => return ownership::draw_ownership(f, &view.ownership, inner),
+        StatsPage::Overview => {},
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(9), Constraint::Min(8), Constraint::Percentage(35)])
+        .split(inner);
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[0]);
+
+    draw_summary(f, stats, top[0]);
+    draw_authors(f, stats, top[1]);
+    draw_weeks(f, stats, rows[1]);
+    draw_files(f, view, stats, rows[2]);
+}
@@ -0,0 +1,142 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use crate::models::{ChangeStatus, StatusSection, WorkingFile};
+use super::Theme;
+
+/// Uncommitted changes in the working tree and index, like `git status`
+pub struct StatusView {
+    pub files: Vec<WorkingFile>,
+    pub cursor: usize,
+    // Highlighted diff of the file under the cursor
+    pub diff_lines: Vec<Line<'static>>,
+    pub diff_scroll: usize,
+    // Line of each `@@` header in the diff, and which hunk is picked
+    pub hunks: Vec<usize>,
+    pub hunk: usize,
+}
+
+impl StatusView {
+    pub fn new(files: Vec<WorkingFile>) -> Self {
+        StatusView { files, cursor: 0, diff_lines: Vec::new(), diff_scroll: 0, hunks: Vec::new(), hunk: 0 }
+    }
+
+    pub fn selected(&self) -> Option<&WorkingFile> {
+        self.files.get(self.cursor)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.files.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    /// Picks the next or previous hunk and scrolls its header to the top
+    pub fn move_hunk(&mut self, delta: i32) {
+        if self.hunks.is_empty() {
+            return;
+        }
+        let max = self.hunks.len() as i64 - 1;
+        self.hunk = (self.hunk as i64 + delta as i64).clamp(0, max) as usize;
+        self.diff_scroll = self.hunks[self.hunk];
+    }
+
+    pub fn scroll_diff(&mut self, delta: i32) {
+        let max = self.diff_lines.len().saturating_sub(1) as i64;
+        self.diff_scroll = (self.diff_scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+}
+
+fn section_title(section: StatusSection) -> &'static str {
+    match section {
+        StatusSection::Staged => "Staged changes",
+        StatusSection::Unstaged => "Unstaged changes",
+        StatusSection::Untracked => "Untracked files",
+    }
+}
+
+fn status_marker(status: ChangeStatus, theme: &Theme) -> (&'static str, Style) {
+    match status {
+        ChangeStatus::Added => ("A", theme.added),
+        ChangeStatus::Deleted => ("D", theme.removed),
+        ChangeStatus::Modified => ("M", theme.modified),
+        ChangeStatus::Renamed => ("R", theme.renamed),
+        ChangeStatus::Copied => ("C", theme.renamed),
+        ChangeStatus::TypeChanged => ("T", theme.path),
+    }
+}
+
+pub fn draw_status_view(f: &mut Frame, status: &StatusView, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+    let theme = Theme::current();
+
+    // Files grouped under a heading per section, remembering which row the
+    // cursor ends up on so it can be kept in view
+    let mut rows = Vec::new();
+    let mut cursor_row = 0;
+    let mut section = None;
+    for (i, file) in status.files.iter().enumerate() {
+        if section != Some(file.section) {
+            section = Some(file.section);
+            rows.push(Line::from(Span::styled(
+                section_title(file.section),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+        }
+        if i == status.cursor {
+            cursor_row = rows.len();
+        }
+        let (marker, marker_style) = match file.section {
+            StatusSection::Untracked => ("?", theme.muted),
+            _ => status_marker(file.status, &theme),
+        };
+        let style = if i == status.cursor {
+            theme.selected
+        } else {
+            Style::default()
+        };
+        rows.push(Line::from(vec![
+            Span::styled(format!("  {} ", marker), marker_style),
+            Span::styled(file.path.clone(), style),
+        ]));
+    }
+    if rows.is_empty() {
+        rows.push(Line::from("Nothing to commit, working tree clean."));
+    }
+
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let offset = (cursor_row + 1).saturating_sub(height);
+    let list = Paragraph::new(rows)
+        .scroll((offset as u16, 0))
+        .block(Block::default().title("Status (↑/↓ to move, ←/→ to pick a hunk, H to stage it, Esc to close)").borders(Borders::ALL));
+    f.render_widget(list, chunks[0]);
+
+    let title = match status.selected() {
+        Some(file) => format!("{}: {}", section_title(file.section), file.path),
+        None => "Diff".to_string(),
+    };
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let picked = status.hunks.get(status.hunk).copied();
+    let lines: Vec<Line> = status
+        .diff_lines
+        .iter()
+        .enumerate()
+        .skip(status.diff_scroll)
+        .take(height)
+        .map(|(i, line)| {
+            let mut line = line.clone();
+            if Some(i) == picked {
+                line.patch_style(theme.selected);
+            }
+            line
+        })
+        .collect();
+    let diff = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(diff, chunks[1]);
+}
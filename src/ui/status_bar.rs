@@ -0,0 +1,92 @@
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use super::{App, Theme};
+
+/// A count with commas between the thousands, e.g. "4,200"
+fn thousands(count: usize) -> String {
+    let digits = count.to_string();
+    let mut text = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            text.push(',');
+        }
+        text.push(digit);
+    }
+    text
+}
+
+/// One line at the bottom of the screen: where we are, what's filtered and
+/// how much is loaded, followed by the latest message if there is one
+pub fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = Theme::current();
+    let separator = Span::styled(" │ ", theme.muted);
+    let mut spans = vec![
+        Span::raw(format!(" {}", app.repo_root)),
+        separator.clone(),
+        Span::styled(app.branch_label().to_string(), theme.current),
+    ];
+    if app.bare {
+        spans.push(separator.clone());
+        spans.push(Span::styled("bare", theme.warning));
+    }
+
+    if let Some(author) = &app.filters.author {
+        spans.push(separator.clone());
+        spans.push(Span::raw(format!("author: {}", author.name)));
+    }
+    if let Some(range) = app.filters.dates {
+        spans.push(separator.clone());
+        spans.push(Span::raw(format!("dates: {}", super::dates::describe_range(range))));
+    }
+    if let Some(kind) = &app.type_filter {
+        spans.push(separator.clone());
+        spans.push(Span::raw(format!("type: {}", kind)));
+    }
+    if !app.search_query.is_empty() {
+        spans.push(separator.clone());
+        spans.push(Span::raw(format!("search: \"{}\"", app.search_query)));
+    }
+
+    spans.push(separator.clone());
+    if app.loading {
+        // Counting up as the walk goes shows it's still making headway
+        let frame = super::SPINNER_FRAMES[app.spinner_frame % super::SPINNER_FRAMES.len()];
+        let (scanned, found) = app.load_progress.unwrap_or((0, 0));
+        let mut text = format!("{} Loaded {} commits…", frame, thousands(app.commits.len() + found));
+        if scanned > found {
+            text.push_str(&format!(" ({} looked at)", thousands(scanned)));
+        }
+        spans.push(Span::styled(text, theme.accent));
+    } else {
+        let more = if app.has_more_commits { "+" } else { "" };
+        spans.push(Span::raw(format!("{}{} commits loaded", thousands(app.commits.len()), more)));
+    }
+
+    if let Some(progress) = &app.remote_progress {
+        let frame = super::SPINNER_FRAMES[app.spinner_frame % super::SPINNER_FRAMES.len()];
+        spans.push(separator.clone());
+        spans.push(Span::styled(format!("{} {}", frame, progress), theme.accent));
+    }
+
+    if let Some(notification) = app.notifications.front() {
+        spans.push(separator);
+        let style = notification.level.style().add_modifier(Modifier::BOLD);
+        spans.push(Span::styled(notification.message.clone(), style));
+        if app.notifications.len() > 1 {
+            spans.push(Span::styled(
+                format!(" (+{} more, e for the log)", app.notifications.len() - 1),
+                theme.muted,
+            ));
+        }
+    }
+
+    let paragraph = Paragraph::new(Line::from(spans))
+        .style(theme.status_bar);
+
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,489 @@
+use lazy_static::lazy_static;
+use ratatui::style::{Color, Modifier, Style};
+use std::sync::RwLock;
+use crate::config::ThemeName;
+
+/// How much colour the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColourSupport {
+    // NO_COLOR is set or the terminal is dumb: text attributes only
+    Monochrome,
+    // The 16 standard colours
+    Basic,
+    // The xterm 256-colour palette
+    Extended,
+    // Any RGB colour
+    TrueColour,
+}
+
+impl ColourSupport {
+    /// Works out the terminal's colours from `NO_COLOR`, `COLORTERM` and
+    /// `TERM`, the way most command line tools do
+    pub fn detect() -> ColourSupport {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        if !var("NO_COLOR").is_empty() || term == "dumb" {
+            ColourSupport::Monochrome
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            ColourSupport::TrueColour
+        } else if term.contains("256color") {
+            ColourSupport::Extended
+        } else {
+            ColourSupport::Basic
+        }
+    }
+}
+
+/// The style of each part of the UI, named after what it marks rather than
+/// how it looks. Draw functions take every style from here, so the theme
+/// and the terminal's colour support are decided in one place.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub name: ThemeName,
+    pub support: ColourSupport,
+    // The highlighted row in a list
+    pub selected: Style,
+    // The highlighted rows of a range, and filler in the split diff
+    pub selected_unfocused: Style,
+    // The checked-out branch, the current repository and the like
+    pub current: Style,
+    pub added: Style,
+    pub removed: Style,
+    pub modified: Style,
+    pub renamed: Style,
+    // The line naming each file in a diff
+    pub file_header: Style,
+    pub hash: Style,
+    pub author: Style,
+    pub date: Style,
+    // Line numbers, separators and hints
+    pub muted: Style,
+    // Hunk headers, progress and other things worth a second look
+    pub accent: Style,
+    // The focused pane and open prompts
+    pub border: Style,
+    pub warning: Style,
+    pub error: Style,
+    pub head: Style,
+    pub branch: Style,
+    pub remote: Style,
+    pub tag: Style,
+    pub path: Style,
+    // The text cursor in prompts, and badges of no particular kind
+    pub cursor: Style,
+    pub status_bar: Style,
+    pub search_match: Style,
+    // Behind syntax-highlighted code in a diff
+    pub added_background: Style,
+    pub removed_background: Style,
+    // The words that changed within a changed line
+    pub added_word: Style,
+    pub removed_word: Style,
+    // The syntect theme for highlighted code, if code is highlighted
+    pub syntax: Option<&'static str>,
+    palette: [Color; 6],
+    badge_text: Color,
+}
+
+/// The colours a theme is made from, before they're fitted to the terminal
+struct Palette {
+    selected: Color,
+    selected_unfocused: Color,
+    current: Color,
+    added: Color,
+    removed: Color,
+    modified: Color,
+    renamed: Color,
+    file_header: Color,
+    hash: Color,
+    author: Color,
+    date: Color,
+    muted: Color,
+    accent: Color,
+    border: Color,
+    warning: Color,
+    error: Color,
+    head: Color,
+    branch: Color,
+    remote: Color,
+    tag: Color,
+    path: Color,
+    badge_text: Color,
+    neutral: Color,
+    status_bar: Color,
+    search_match: Color,
+    added_background: Color,
+    removed_background: Color,
+    added_word: Color,
+    removed_word: Color,
+    // Graph lanes, author initials and commit type badges
+    palette: [Color; 6],
+    syntax: &'static str,
+}
+
+lazy_static! {
+    static ref SUPPORT: ColourSupport = ColourSupport::detect();
+    static ref CURRENT: RwLock<Theme> = RwLock::new(Theme::new(ThemeName::default(), *SUPPORT));
+}
+
+impl Theme {
+    /// Resolves a built-in theme to styles the terminal can show, falling
+    /// back to bold, underline and reverse video when it has no colours
+    pub fn new(name: ThemeName, support: ColourSupport) -> Theme {
+        if support == ColourSupport::Monochrome {
+            return monochrome(name);
+        }
+        let palette = match name {
+            ThemeName::Dark => dark(),
+            ThemeName::Light => light(),
+            ThemeName::Solarized => solarized(),
+            ThemeName::Gruvbox => gruvbox(),
+        };
+        let colour = |colour: Color| adapt(colour, support);
+        let fg = |c: Color| Style::default().fg(colour(c));
+        let bg = |c: Color| Style::default().bg(colour(c));
+        Theme {
+            name,
+            support,
+            selected: bg(palette.selected),
+            selected_unfocused: bg(palette.selected_unfocused),
+            current: fg(palette.current),
+            added: fg(palette.added),
+            removed: fg(palette.removed),
+            modified: fg(palette.modified),
+            renamed: fg(palette.renamed),
+            file_header: fg(palette.file_header),
+            hash: fg(palette.hash),
+            author: fg(palette.author),
+            date: fg(palette.date),
+            muted: fg(palette.muted),
+            accent: fg(palette.accent),
+            border: fg(palette.border),
+            warning: fg(palette.warning),
+            error: fg(palette.error),
+            head: fg(palette.head),
+            branch: fg(palette.branch),
+            remote: fg(palette.remote),
+            tag: fg(palette.tag),
+            path: fg(palette.path),
+            cursor: bg(palette.neutral).fg(colour(palette.badge_text)),
+            status_bar: bg(palette.status_bar),
+            search_match: bg(palette.search_match).fg(colour(palette.badge_text)).add_modifier(Modifier::BOLD),
+            added_background: bg(palette.added_background),
+            removed_background: bg(palette.removed_background),
+            // Bold as well, for terminals whose few colours can't tell the
+            // two backgrounds apart
+            added_word: bg(palette.added_word).add_modifier(Modifier::BOLD),
+            removed_word: bg(palette.removed_word).add_modifier(Modifier::BOLD),
+            syntax: Some(palette.syntax),
+            palette: palette.palette.map(colour),
+            badge_text: colour(palette.badge_text),
+        }
+    }
+
+    /// The theme the UI is drawn with
+    pub fn current() -> Theme {
+        *CURRENT.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set(name: ThemeName) {
+        *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = Theme::new(name, *SUPPORT);
+    }
+
+    /// One of a handful of distinct colours, for graph lanes and the like
+    pub fn lane(&self, index: usize) -> Style {
+        match self.support {
+            ColourSupport::Monochrome => Style::default(),
+            _ => Style::default().fg(self.palette[index % self.palette.len()]),
+        }
+    }
+
+    /// Text on a block of one of the lane colours
+    pub fn badge(&self, index: usize) -> Style {
+        match self.support {
+            ColourSupport::Monochrome => Style::default().add_modifier(Modifier::REVERSED),
+            _ => Style::default().fg(self.badge_text).bg(self.palette[index % self.palette.len()]),
+        }
+    }
+
+    /// Text in an arbitrary colour, such as blame's age shading or
+    /// highlighted code, as near as the terminal allows
+    pub fn colour(&self, colour: Color) -> Style {
+        match self.support {
+            ColourSupport::Monochrome => Style::default(),
+            support => Style::default().fg(adapt(colour, support)),
+        }
+    }
+}
+
+/// Swaps an RGB colour for the nearest one the terminal can show
+fn adapt(colour: Color, support: ColourSupport) -> Color {
+    const ANSI: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::Gray, (229, 229, 229)),
+        (Color::DarkGray, (127, 127, 127)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (92, 92, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    let (r, g, b) = match colour {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => return colour,
+    };
+    let distance = |(ar, ag, ab): (u8, u8, u8)| {
+        (ar as i32 - r as i32).pow(2) + (ag as i32 - g as i32).pow(2) + (ab as i32 - b as i32).pow(2)
+    };
+    match support {
+        ColourSupport::TrueColour | ColourSupport::Monochrome => colour,
+        ColourSupport::Extended => {
+            // The 6x6x6 cube from 16 to 231, or the grey ramp from 232 to 255
+            const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+            let step = |value: u8| (0..6).min_by_key(|&i| (LEVELS[i] as i32 - value as i32).abs()).unwrap_or(0);
+            let (cr, cg, cb) = (step(r), step(g), step(b));
+            let cube = (16 + 36 * cr + 6 * cg + cb) as u8;
+            let grey = ((r as u32 + g as u32 + b as u32) / 3).saturating_sub(8).min(230) / 10;
+            let grey_level = (8 + grey * 10) as u8;
+            if distance((grey_level, grey_level, grey_level)) < distance((LEVELS[cr], LEVELS[cg], LEVELS[cb])) {
+                Color::Indexed(232 + grey as u8)
+            } else {
+                Color::Indexed(cube)
+            }
+        },
+        ColourSupport::Basic => ANSI
+            .iter()
+            .min_by_key(|(_, rgb)| distance(*rgb))
+            .map(|(named, _)| *named)
+            .unwrap_or(colour),
+    }
+}
+
+// No colours at all, so the parts that matter stand out through bold,
+// underline and reverse video instead
+fn monochrome(name: ThemeName) -> Theme {
+    let plain = Style::default();
+    let bold = plain.add_modifier(Modifier::BOLD);
+    let underlined = plain.add_modifier(Modifier::UNDERLINED);
+    let dim = plain.add_modifier(Modifier::DIM);
+    let reversed = plain.add_modifier(Modifier::REVERSED);
+    Theme {
+        name,
+        support: ColourSupport::Monochrome,
+        selected: reversed,
+        selected_unfocused: underlined,
+        current: bold,
+        added: bold,
+        removed: dim,
+        modified: plain,
+        renamed: underlined,
+        file_header: bold.add_modifier(Modifier::UNDERLINED),
+        hash: plain,
+        author: plain,
+        date: dim,
+        muted: dim,
+        accent: underlined,
+        border: bold,
+        warning: bold,
+        error: bold.add_modifier(Modifier::UNDERLINED),
+        head: bold.add_modifier(Modifier::UNDERLINED),
+        branch: bold,
+        remote: plain,
+        tag: underlined,
+        path: underlined,
+        cursor: reversed,
+        status_bar: plain,
+        search_match: reversed.add_modifier(Modifier::BOLD),
+        added_background: plain,
+        removed_background: plain,
+        added_word: reversed,
+        removed_word: reversed,
+        syntax: None,
+        palette: [Color::Reset; 6],
+        badge_text: Color::Reset,
+    }
+}
+
+// The original colours, which only use the 16 standard ones
+fn dark() -> Palette {
+    Palette {
+        selected: Color::Blue,
+        selected_unfocused: Color::DarkGray,
+        current: Color::Green,
+        added: Color::Green,
+        removed: Color::Red,
+        modified: Color::Yellow,
+        renamed: Color::Cyan,
+        file_header: Color::Yellow,
+        hash: Color::Yellow,
+        author: Color::Reset,
+        date: Color::DarkGray,
+        muted: Color::DarkGray,
+        accent: Color::Cyan,
+        border: Color::Yellow,
+        warning: Color::Yellow,
+        error: Color::Red,
+        head: Color::Cyan,
+        branch: Color::Green,
+        remote: Color::Red,
+        tag: Color::Yellow,
+        path: Color::Magenta,
+        badge_text: Color::Black,
+        neutral: Color::Gray,
+        status_bar: Color::Black,
+        search_match: Color::Yellow,
+        added_background: Color::Rgb(0, 60, 0),
+        removed_background: Color::Rgb(70, 0, 0),
+        added_word: Color::Rgb(0, 120, 0),
+        removed_word: Color::Rgb(140, 0, 0),
+        palette: [Color::Red, Color::Green, Color::Yellow, Color::Magenta, Color::Cyan, Color::Blue],
+        syntax: "base16-ocean.dark",
+    }
+}
+
+// Darker colours that stay readable on a white background
+fn light() -> Palette {
+    Palette {
+        selected: Color::Rgb(190, 215, 245),
+        selected_unfocused: Color::Rgb(225, 225, 225),
+        current: Color::Rgb(0, 120, 0),
+        added: Color::Rgb(0, 120, 0),
+        removed: Color::Rgb(180, 0, 0),
+        modified: Color::Rgb(160, 90, 0),
+        renamed: Color::Rgb(0, 110, 140),
+        file_header: Color::Rgb(160, 90, 0),
+        hash: Color::Rgb(160, 90, 0),
+        author: Color::Rgb(40, 40, 120),
+        date: Color::Rgb(110, 110, 110),
+        muted: Color::Rgb(130, 130, 130),
+        accent: Color::Rgb(0, 90, 170),
+        border: Color::Rgb(0, 90, 170),
+        warning: Color::Rgb(160, 90, 0),
+        error: Color::Rgb(180, 0, 0),
+        head: Color::Rgb(0, 110, 140),
+        branch: Color::Rgb(0, 120, 0),
+        remote: Color::Rgb(180, 0, 0),
+        tag: Color::Rgb(160, 90, 0),
+        path: Color::Rgb(140, 0, 140),
+        badge_text: Color::White,
+        neutral: Color::Rgb(100, 100, 100),
+        status_bar: Color::Rgb(230, 230, 230),
+        search_match: Color::Rgb(255, 220, 90),
+        added_background: Color::Rgb(215, 245, 215),
+        removed_background: Color::Rgb(250, 215, 215),
+        added_word: Color::Rgb(160, 230, 160),
+        removed_word: Color::Rgb(245, 165, 165),
+        palette: [
+            Color::Rgb(180, 0, 0),
+            Color::Rgb(0, 120, 0),
+            Color::Rgb(160, 90, 0),
+            Color::Rgb(140, 0, 140),
+            Color::Rgb(0, 110, 140),
+            Color::Rgb(0, 60, 180),
+        ],
+        syntax: "InspiredGitHub",
+    }
+}
+
+// https://ethanschoonover.com/solarized, dark variant
+fn solarized() -> Palette {
+    let base02 = Color::Rgb(7, 54, 66);
+    let base01 = Color::Rgb(88, 110, 117);
+    let base1 = Color::Rgb(147, 161, 161);
+    let yellow = Color::Rgb(181, 137, 0);
+    let orange = Color::Rgb(203, 75, 22);
+    let red = Color::Rgb(220, 50, 47);
+    let magenta = Color::Rgb(211, 54, 130);
+    let violet = Color::Rgb(108, 113, 196);
+    let blue = Color::Rgb(38, 139, 210);
+    let cyan = Color::Rgb(42, 161, 152);
+    let green = Color::Rgb(133, 153, 0);
+    Palette {
+        selected: Color::Rgb(0, 70, 100),
+        selected_unfocused: base02,
+        current: green,
+        added: green,
+        removed: red,
+        modified: yellow,
+        renamed: cyan,
+        file_header: yellow,
+        hash: yellow,
+        author: violet,
+        date: base01,
+        muted: base01,
+        accent: cyan,
+        border: blue,
+        warning: orange,
+        error: red,
+        head: cyan,
+        branch: green,
+        remote: orange,
+        tag: yellow,
+        path: magenta,
+        badge_text: Color::Rgb(0, 43, 54),
+        neutral: base1,
+        status_bar: base02,
+        search_match: yellow,
+        added_background: Color::Rgb(20, 60, 30),
+        removed_background: Color::Rgb(70, 30, 40),
+        added_word: Color::Rgb(40, 110, 50),
+        removed_word: Color::Rgb(130, 45, 60),
+        palette: [red, green, yellow, magenta, cyan, blue],
+        syntax: "Solarized (dark)",
+    }
+}
+
+// https://github.com/morhetz/gruvbox, dark variant
+fn gruvbox() -> Palette {
+    let bg1 = Color::Rgb(60, 56, 54);
+    let gray = Color::Rgb(146, 131, 116);
+    let fg3 = Color::Rgb(189, 174, 147);
+    let red = Color::Rgb(251, 73, 52);
+    let green = Color::Rgb(184, 187, 38);
+    let yellow = Color::Rgb(250, 189, 47);
+    let blue = Color::Rgb(131, 165, 152);
+    let purple = Color::Rgb(211, 134, 155);
+    let aqua = Color::Rgb(142, 192, 124);
+    let orange = Color::Rgb(254, 128, 25);
+    Palette {
+        selected: Color::Rgb(80, 73, 69),
+        selected_unfocused: bg1,
+        current: green,
+        added: green,
+        removed: red,
+        modified: yellow,
+        renamed: aqua,
+        file_header: yellow,
+        hash: yellow,
+        author: blue,
+        date: gray,
+        muted: gray,
+        accent: aqua,
+        border: orange,
+        warning: orange,
+        error: red,
+        head: aqua,
+        branch: green,
+        remote: red,
+        tag: yellow,
+        path: purple,
+        badge_text: Color::Rgb(40, 40, 40),
+        neutral: fg3,
+        status_bar: bg1,
+        search_match: yellow,
+        added_background: Color::Rgb(50, 61, 30),
+        removed_background: Color::Rgb(74, 38, 34),
+        added_word: Color::Rgb(90, 110, 40),
+        removed_word: Color::Rgb(130, 60, 50),
+        palette: [red, green, yellow, purple, aqua, blue],
+        syntax: "base16-eighties.dark",
+    }
+}
@@ -0,0 +1,180 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use super::Theme;
+
+/// One file or directory in the tree, in display order
+pub struct TreeRow {
+    pub path: String,
+    pub name: String,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+}
+
+/// A file's contents at the commit being browsed
+pub struct FileViewer {
+    pub path: String,
+    pub lines: Vec<Line<'static>>,
+    pub scroll: usize,
+}
+
+/// The repository as it was at one commit. Directories are read when they
+/// are first expanded, so large trees open instantly.
+pub struct TreeView {
+    pub commit: String,
+    pub rows: Vec<TreeRow>,
+    pub cursor: usize,
+    pub viewer: Option<FileViewer>,
+}
+
+impl TreeView {
+    /// `entries` are the (name, is_dir) pairs at the top of the tree
+    pub fn new(commit: String, entries: Vec<(String, bool)>) -> Self {
+        let rows = Self::rows_for("", 0, entries);
+        TreeView { commit, rows, cursor: 0, viewer: None }
+    }
+
+    fn rows_for(dir: &str, depth: usize, entries: Vec<(String, bool)>) -> Vec<TreeRow> {
+        entries
+            .into_iter()
+            .map(|(name, is_dir)| TreeRow {
+                path: if dir.is_empty() { name.clone() } else { format!("{}/{}", dir, name) },
+                name,
+                depth,
+                is_dir,
+                expanded: false,
+            })
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&TreeRow> {
+        self.rows.get(self.cursor)
+    }
+
+    /// Puts the cursor on the row for `path`, if it's showing
+    pub fn select_path(&mut self, path: &str) {
+        if let Some(index) = self.rows.iter().position(|row| row.path == path) {
+            self.cursor = index;
+        }
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        let max = self.rows.len().saturating_sub(1) as i64;
+        self.cursor = (self.cursor as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+    }
+
+    /// Shows `entries` beneath the directory under the cursor
+    pub fn expand(&mut self, entries: Vec<(String, bool)>) {
+        let row = match self.rows.get_mut(self.cursor) {
+            Some(row) if row.is_dir && !row.expanded => row,
+            _ => return,
+        };
+        row.expanded = true;
+        let children = Self::rows_for(&row.path.clone(), row.depth + 1, entries);
+        let at = self.cursor + 1;
+        self.rows.splice(at..at, children);
+    }
+
+    /// Folds the directory under the cursor, or the one containing it
+    pub fn collapse(&mut self) {
+        let (depth, expanded) = match self.selected() {
+            Some(row) => (row.depth, row.is_dir && row.expanded),
+            None => return,
+        };
+        if !expanded {
+            // Step up to the parent directory and fold that instead
+            if let Some(parent) = self.rows[..self.cursor].iter().rposition(|r| r.depth + 1 == depth) {
+                self.cursor = parent;
+                self.collapse();
+            }
+            return;
+        }
+
+        self.rows[self.cursor].expanded = false;
+        let end = self.rows[self.cursor + 1..]
+            .iter()
+            .position(|r| r.depth <= depth)
+            .map(|i| self.cursor + 1 + i)
+            .unwrap_or(self.rows.len());
+        self.rows.drain(self.cursor + 1..end);
+    }
+
+    pub fn scroll_viewer(&mut self, delta: i32) {
+        if let Some(viewer) = &mut self.viewer {
+            let max = viewer.lines.len().saturating_sub(1) as i64;
+            viewer.scroll = (viewer.scroll as i64).saturating_add(delta as i64).clamp(0, max) as usize;
+        }
+    }
+}
+
+pub fn draw_tree_view(f: &mut Frame, tree: &TreeView, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+    let theme = Theme::current();
+
+    let title = format!(
+        "Tree @ {} (↑/↓ to move, Enter to open, ← to fold, Esc to close)",
+        &tree.commit[..7.min(tree.commit.len())]
+    );
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    let offset = (tree.cursor + 1).saturating_sub(height);
+    let rows: Vec<Line> = tree
+        .rows
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, row)| {
+            let icon = match (row.is_dir, row.expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            let mut style = if row.is_dir {
+                theme.accent.add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            if i == tree.cursor {
+                style = style.patch(theme.selected);
+            }
+            Line::from(vec![
+                Span::raw("  ".repeat(row.depth)),
+                Span::styled(format!("{}{}", icon, row.name), style),
+            ])
+        })
+        .collect();
+    let list = Paragraph::new(rows).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(list, chunks[0]);
+
+    let (title, lines) = match &tree.viewer {
+        Some(viewer) => {
+            let title = format!("{} ({}/{}, PgUp/PgDn to scroll)", viewer.path, (viewer.scroll + 1).min(viewer.lines.len()), viewer.lines.len());
+            let height = chunks[1].height.saturating_sub(2) as usize;
+            let number_width = viewer.lines.len().to_string().len();
+            let lines: Vec<Line> = viewer
+                .lines
+                .iter()
+                .enumerate()
+                .skip(viewer.scroll)
+                .take(height)
+                .map(|(i, line)| {
+                    let mut spans = vec![Span::styled(format!("{:>width$} ", i + 1, width = number_width), theme.muted)];
+                    spans.extend(line.spans.iter().cloned());
+                    Line::from(spans)
+                })
+                .collect();
+            (title, lines)
+        },
+        None => ("File".to_string(), vec![Line::from("Open a file to see its contents.")]),
+    };
+    let viewer = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(viewer, chunks[1]);
+}
@@ -0,0 +1,150 @@
+use std::ops::Range;
+
+/// Lines with more tokens than this between them aren't compared, as the
+/// comparison takes time and memory in proportion to the two multiplied
+const MAX_TOKENS: usize = 40_000;
+
+/// How many token comparisons a whole diff may take between its line
+/// pairs, after which the rest are left as they are, so huge diffs still
+/// open quickly
+pub const DIFF_BUDGET: usize = 4_000_000;
+
+/// A pair of lines more different than this, as a share of their text,
+/// are left as they are: picking out most of both wouldn't help
+const MAX_CHANGED: f64 = 0.6;
+
+/// The parts of a removed line and the added line paired with it that
+/// differ, as byte ranges of each
+#[derive(Debug, Clone, Default)]
+pub struct WordChanges {
+    pub old: Vec<Range<usize>>,
+    pub new: Vec<Range<usize>>,
+}
+
+/// Splits a line into words, runs of whitespace and single other characters
+fn tokens(line: &str) -> Vec<Range<usize>> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous = None;
+    for (i, c) in line.char_indices() {
+        let kind = class(c);
+        match tokens.last_mut() {
+            Some(token) if previous == Some(kind) && kind != 2 => token.end = i + c.len_utf8(),
+            _ => tokens.push(i..i + c.len_utf8()),
+        }
+        previous = Some(kind);
+    }
+    tokens
+}
+
+/// Finds the words that changed between a removed line and the added line
+/// it's paired with, or None if the lines have too little in common for
+/// that to be worth showing. The comparison's cost comes out of `budget`.
+pub fn changed_words(old: &str, new: &str, budget: &mut usize) -> Option<WordChanges> {
+    let (a, b) = (tokens(old), tokens(new));
+    let cost = a.len() * b.len();
+    if cost > MAX_TOKENS || cost > *budget || old == new {
+        return None;
+    }
+    *budget -= cost;
+
+    // The longest common subsequence of tokens, filled in from the end
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = if old[a[i].clone()] == new[b[j].clone()] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+    let (mut kept_a, mut kept_b) = (vec![false; a.len()], vec![false; b.len()]);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if old[a[i].clone()] == new[b[j].clone()] {
+            kept_a[i] = true;
+            kept_b[j] = true;
+            i += 1;
+            j += 1;
+        } else if common[(i + 1) * width + j] >= common[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let changes = WordChanges { old: changed_ranges(old, &a, &kept_a), new: changed_ranges(new, &b, &kept_b) };
+    let changed: usize = changes.old.iter().chain(&changes.new).map(|range| range.len()).sum();
+    if changed as f64 > MAX_CHANGED * (old.len() + new.len()) as f64 {
+        return None;
+    }
+    Some(changes)
+}
+
+/// Joins the tokens that aren't kept into ranges, taking in the whitespace
+/// between two changed words so they show as one change
+fn changed_ranges(line: &str, tokens: &[Range<usize>], kept: &[bool]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let bridged = kept[i]
+            && line[token.clone()].trim().is_empty()
+            && i > 0
+            && !kept[i - 1]
+            && kept.get(i + 1) == Some(&false);
+        if kept[i] && !bridged {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == token.start => range.end = token.end,
+            _ => ranges.push(token.clone()),
+        }
+    }
+    ranges
+}
+
+/// Pairs each run of removed lines in a unified diff with the run of added
+/// lines right after it, first with first as the split view does, and
+/// finds the words that changed in each pair. Gives each line the ranges
+/// of its text after the +/- marker to pick out.
+pub fn unified_changes(lines: &[&str]) -> Vec<Vec<Range<usize>>> {
+    let mut changes = vec![Vec::new(); lines.len()];
+    let mut in_header = false;
+    let mut budget = DIFF_BUDGET;
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].starts_with("diff --git") {
+            in_header = true;
+        } else if lines[i].starts_with("@@") {
+            in_header = false;
+        }
+        if in_header || !lines[i].starts_with('-') {
+            i += 1;
+            continue;
+        }
+        let removed = i;
+        while i < lines.len() && lines[i].starts_with('-') {
+            i += 1;
+        }
+        let added = i;
+        while i < lines.len() && lines[i].starts_with('+') {
+            i += 1;
+        }
+        for (old, new) in (removed..added).zip(added..i) {
+            if let Some(words) = changed_words(&lines[old][1..], &lines[new][1..], &mut budget) {
+                changes[old] = words.old;
+                changes[new] = words.new;
+            }
+        }
+    }
+    changes
+}
@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// The repositories directly inside `dir`, and `dir` itself if it is one,
+/// sorted by path. Nested repositories further down aren't looked for.
+pub fn find_repositories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    if is_repository(dir) {
+        repos.push(dir.to_path_buf());
+    }
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() && is_repository(&path) {
+            repos.push(path);
+        }
+    }
+    repos.sort();
+    Ok(repos)
+}
+
+/// Whether `dir` is the top of a repository, or a bare one. A worktree or
+/// submodule has a .git file rather than a directory.
+fn is_repository(dir: &Path) -> bool {
+    dir.join(".git").exists() || (dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir())
+}